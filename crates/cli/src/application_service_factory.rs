@@ -1,22 +1,44 @@
 use crypto_balance_core::{
     // Import adapters
     adapters::{
-        config::app_config::CONFIG, exchange::binance_factory::BinanceAccountFactory,
+        blockchain::chains::ChainRegistry,
+        config::app_config::CONFIG,
+        config::persistence_config::PersistenceBackend,
+        exchange::binance_factory::BinanceAccountFactory,
         exchange::kraken_factory::KrakenFactory,
         exchange::spreadsheet_balance_repository::SpreadsheetBalanceRepository,
+        hold::spreadsheet_hold_balance_repository::SpreadsheetHoldBalanceRepository,
+        http::rate_limiter::RATE_LIMITERS,
+        persistence::dry_run::{DryRunBalanceRepository, DryRunSnapshotRepository},
+        persistence::fan_out_snapshot_repository::FanOutSnapshotRepository,
+        persistence::file_balance_repository::FileBalanceRepository,
+        persistence::spreadsheet_snapshot_repository::SpreadsheetSnapshotRepository,
+        persistence::sqlite_snapshot_repository::SqliteSnapshotRepository,
+        price::api::CoinGeckoApi,
+        price::spreadsheet_asset_prices::SpreadsheetAssetPrices,
+        sheets::backend::dry_run::{DryRunBackend, DryRunRecorder},
+        sheets::backend::google::GoogleSheetsBackend,
+        sheets::backend::SheetsBackend,
+        sheets::schema_bootstrapper::{RangeLayout, SpreadsheetSchemaBootstrapper},
+        sheets::schema_validator::{SchemaExpectation, SpreadsheetSchemaValidator},
         sheets::spreadsheet_manager::SpreadsheetManager,
     },
     application::service::CryptoBalanceApplicationService,
     // Import existing routines and implementations
     application::{
+        airdrops::cosmos_use_cases::CosmosUseCases, airdrops::solana_use_cases::SolanaUseCases,
         debank::debank_routine::DebankRoutine, exchange::binance_use_cases::BinanceUseCases,
         exchange::exchange_balances_routine::ExchangeBalancesRoutine,
-        exchange::kraken_use_cases::KrakenUseCases, price::token_prices::TokenPricesRoutine,
+        exchange::kraken_use_cases::KrakenUseCases,
+        hold::update_hold_balance_on_sheets::UpdateHoldBalanceOnSheetsRoutine,
+        price::token_prices::TokenPricesRoutine,
     },
 
+    domain::sheets::ranges::{RangeShape, HOLD_TABLE_LAYOUT},
     ports::{
-        application_service::ApplicationService, balance_repository::BalanceRepository,
-        routine::Routine,
+        application_service::ApplicationService, asset_price_source::AssetPriceSource,
+        balance_repository::BalanceRepository, hold_balance_repository::HoldBalanceRepository,
+        routine::Routine, snapshot_repository::SnapshotRepository,
     },
 };
 
@@ -25,33 +47,237 @@ use std::sync::Arc;
 pub struct ApplicationServiceFactory;
 
 impl ApplicationServiceFactory {
-    pub async fn create() -> Result<Arc<dyn ApplicationService>, Box<dyn std::error::Error>> {
-        let routines = Self::create_routines().await;
-        let app_service = CryptoBalanceApplicationService::new(routines);
+    /// With `dry_run`, spreadsheet and local persistence writes are recorded there instead of
+    /// being made
+    pub async fn create(
+        dry_run: Option<Arc<DryRunRecorder>>,
+    ) -> Result<Arc<dyn ApplicationService>, Box<dyn std::error::Error>> {
+        RATE_LIMITERS.configure(&CONFIG.http);
+
+        // Without credentials, only the routines that do not need the spreadsheet run
+        let spreadsheet_manager = if CONFIG.sheets.has_credentials() {
+            Some(Arc::new(
+                Self::create_spreadsheet_manager(dry_run.clone()).await?,
+            ))
+        } else {
+            None
+        };
+
+        let routines = Self::create_routines(spreadsheet_manager.clone(), dry_run)?;
+        let mut app_service = CryptoBalanceApplicationService::new(routines);
+        if let Some(spreadsheet_manager) = spreadsheet_manager {
+            app_service = app_service
+                .with_schema_validator(Arc::new(Self::create_schema_validator(
+                    spreadsheet_manager.clone(),
+                )))
+                .with_schema_bootstrapper(Arc::new(Self::create_schema_bootstrapper(
+                    spreadsheet_manager,
+                )));
+        }
         Ok(Arc::new(app_service))
     }
 
-    async fn create_routines() -> Vec<Box<dyn Routine>> {
-        let spreadsheet_manager = Arc::new(SpreadsheetManager::new(CONFIG.sheets.clone()).await);
+    fn create_routines(
+        spreadsheet_manager: Option<Arc<SpreadsheetManager>>,
+        dry_run: Option<Arc<DryRunRecorder>>,
+    ) -> Result<Vec<Box<dyn Routine>>, Box<dyn std::error::Error>> {
+        let balance_repository: Arc<dyn BalanceRepository> = match CONFIG.persistence.backend {
+            PersistenceBackend::Sheets => Arc::new(SpreadsheetBalanceRepository::new(
+                spreadsheet_manager
+                    .clone()
+                    .ok_or("[persistence] backend \"sheets\" needs [sheets] to be configured")?,
+            )),
+            PersistenceBackend::File => {
+                let repository = FileBalanceRepository::from_config(&CONFIG.persistence.file);
+                match &dry_run {
+                    Some(recorder) => Arc::new(DryRunBalanceRepository::new(
+                        repository,
+                        Arc::clone(recorder),
+                    )),
+                    None => Arc::new(repository),
+                }
+            }
+        };
+
+        let mut snapshot_repositories: Vec<Arc<dyn SnapshotRepository>> = Vec::new();
+        if let Some(path) = &CONFIG.persistence.snapshots.sqlite {
+            snapshot_repositories.push(match &dry_run {
+                Some(recorder) => Arc::new(DryRunSnapshotRepository::new(
+                    path.as_ref(),
+                    Arc::clone(recorder),
+                )),
+                None => Arc::new(
+                    SqliteSnapshotRepository::open(path.as_ref())
+                        .map_err(|e| format!("Failed to open snapshot database: {:?}", e))?,
+                ),
+            });
+        }
+        if let Some(sheet) = &CONFIG.persistence.snapshots.sheet {
+            snapshot_repositories.push(Arc::new(SpreadsheetSnapshotRepository::new(
+                spreadsheet_manager
+                    .clone()
+                    .ok_or("[persistence.snapshots.sheet] needs [sheets] to be configured")?,
+                sheet.range.as_ref(),
+            )));
+        }
+        let snapshots = FanOutSnapshotRepository::from_repositories(snapshot_repositories);
+        // Snapshots are valued at the prices the token prices routine keeps on the spreadsheet
+        let asset_prices = spreadsheet_manager.clone().map(|spreadsheet_manager| {
+            Arc::new(SpreadsheetAssetPrices::new(spreadsheet_manager)) as Arc<dyn AssetPriceSource>
+        });
 
-        let balance_repository: Arc<dyn BalanceRepository> = Arc::new(
-            SpreadsheetBalanceRepository::new(Arc::clone(&spreadsheet_manager)),
+        let mut routines: Vec<Box<dyn Routine>> = vec![
+            Box::new(
+                ExchangeBalancesRoutine::new(
+                    BinanceUseCases::new(BinanceAccountFactory::new(CONFIG.binance.clone())),
+                    Arc::clone(&balance_repository),
+                )
+                .with_snapshots(snapshots.clone())
+                .with_asset_prices(asset_prices.clone()),
+            ),
+            Box::new(
+                ExchangeBalancesRoutine::new(
+                    KrakenUseCases::new(KrakenFactory::new(CONFIG.kraken.clone())),
+                    Arc::clone(&balance_repository),
+                )
+                .with_snapshots(snapshots.clone())
+                .with_asset_prices(asset_prices.clone()),
+            ),
+            Box::new(
+                ExchangeBalancesRoutine::new(
+                    SolanaUseCases::new(&CONFIG.blockchain.airdrops.solana)
+                        .with_token_list_cache(&CONFIG.blockchain.token_cache_dir),
+                    Arc::clone(&balance_repository),
+                )
+                .with_snapshots(snapshots.clone())
+                .with_asset_prices(asset_prices.clone()),
+            ),
+            Box::new(
+                ExchangeBalancesRoutine::new(
+                    CosmosUseCases::new(&CONFIG.blockchain.airdrops.cosmos),
+                    Arc::clone(&balance_repository),
+                )
+                .with_snapshots(snapshots.clone())
+                .with_asset_prices(asset_prices.clone()),
+            ),
+        ];
+
+        let Some(spreadsheet_manager) = spreadsheet_manager else {
+            tracing::info!("No [sheets] configured, skipping the spreadsheet-only routines");
+            return Ok(routines);
+        };
+
+        let hold_balance_repository: Arc<dyn HoldBalanceRepository> = Arc::new(
+            SpreadsheetHoldBalanceRepository::new(Arc::clone(&spreadsheet_manager)),
         );
 
-        vec![
-            Box::new(DebankRoutine::new(
-                CONFIG.blockchain.airdrops.evm.clone(),
-                Arc::clone(&spreadsheet_manager),
-            )),
-            Box::new(TokenPricesRoutine::new(Arc::clone(&spreadsheet_manager))),
-            Box::new(ExchangeBalancesRoutine::new(
-                BinanceUseCases::new(BinanceAccountFactory::new(CONFIG.binance.clone())),
-                Arc::clone(&balance_repository),
-            )),
-            Box::new(ExchangeBalancesRoutine::new(
-                KrakenUseCases::new(KrakenFactory::new(CONFIG.kraken.clone())),
-                Arc::clone(&balance_repository),
-            )),
-        ]
+        let chain_registry = Arc::new(
+            ChainRegistry::from_config(&CONFIG.blockchain)
+                .map_err(|e| format!("Failed to build chain registry: {:?}", e))?,
+        );
+
+        routines.push(Box::new(DebankRoutine::new(
+            CONFIG.blockchain.airdrops.evm.clone(),
+            Arc::clone(&spreadsheet_manager),
+        )));
+        routines.push(Box::new(
+            TokenPricesRoutine::new(Arc::clone(&spreadsheet_manager))
+                .with_price_source(Arc::new(CoinGeckoApi::new(&CONFIG.coingecko.base_url))),
+        ));
+        routines.push(Box::new(
+            UpdateHoldBalanceOnSheetsRoutine::new(
+                &CONFIG.blockchain,
+                &CONFIG.sheets.hold,
+                chain_registry,
+                hold_balance_repository,
+            )
+            .with_snapshots(snapshots)
+            .with_asset_prices(asset_prices),
+        ));
+
+        Ok(routines)
+    }
+
+    /// Checks the built-in ranges plus every range and sheet the configuration points routines at
+    fn create_schema_validator(
+        spreadsheet_manager: Arc<SpreadsheetManager>,
+    ) -> SpreadsheetSchemaValidator {
+        let hold_sheets = CONFIG
+            .blockchain
+            .wallet_groups
+            .iter()
+            .filter_map(|group| group.sheet.as_ref())
+            .chain([&CONFIG.sheets.hold]);
+
+        let mut validator = SpreadsheetSchemaValidator::new(spreadsheet_manager);
+        for sheet in hold_sheets {
+            validator = validator.expect(SchemaExpectation::NamedRange {
+                name: sheet.range.to_string(),
+                shape: RangeShape::Table { min_rows: 3 },
+                sheet_title: sheet.sheet_title.as_deref().map(str::to_owned),
+            });
+        }
+        if let Some(title) = Self::history_sheet_title() {
+            validator = validator.expect(SchemaExpectation::Sheet { title });
+        }
+
+        validator
+    }
+
+    /// Hold tables missing from the default layout get a sheet of their own
+    fn create_schema_bootstrapper(
+        spreadsheet_manager: Arc<SpreadsheetManager>,
+    ) -> SpreadsheetSchemaBootstrapper {
+        let hold_sheets = CONFIG
+            .blockchain
+            .wallet_groups
+            .iter()
+            .filter_map(|group| group.sheet.as_ref())
+            .chain([&CONFIG.sheets.hold]);
+
+        let mut bootstrapper = SpreadsheetSchemaBootstrapper::new(spreadsheet_manager);
+        for sheet in hold_sheets {
+            let sheet_title = sheet.sheet_title.as_deref().unwrap_or(&sheet.range);
+            bootstrapper = bootstrapper.with_range(RangeLayout::new(
+                sheet.range.as_ref(),
+                sheet_title,
+                HOLD_TABLE_LAYOUT,
+            ));
+            if let Some(sheet_title) = sheet.sheet_title.as_deref() {
+                bootstrapper = bootstrapper.with_sheet(sheet_title);
+            }
+        }
+        if let Some(title) = Self::history_sheet_title() {
+            bootstrapper = bootstrapper.with_sheet(title);
+        }
+
+        bootstrapper
+    }
+
+    /// Sheet of the configured history range, e.g. `History` for `'History'!A:F`
+    fn history_sheet_title() -> Option<String> {
+        let history = CONFIG.persistence.snapshots.sheet.as_ref()?;
+        let (title, _) = history.range.rsplit_once('!')?;
+        Some(title.trim_matches('\'').to_owned())
+    }
+
+    /// With `dry_run`, writes go to the recorder instead of the spreadsheet
+    async fn create_spreadsheet_manager(
+        dry_run: Option<Arc<DryRunRecorder>>,
+    ) -> Result<SpreadsheetManager, Box<dyn std::error::Error>> {
+        let sheets_backend: Arc<dyn SheetsBackend> = Arc::new(
+            GoogleSheetsBackend::new(&CONFIG.sheets)
+                .await
+                .map_err(|e| format!("Failed to authenticate with Google Sheets: {:?}", e))?,
+        );
+        let sheets_backend: Arc<dyn SheetsBackend> = match dry_run {
+            Some(recorder) => Arc::new(DryRunBackend::new(sheets_backend, recorder)),
+            None => sheets_backend,
+        };
+
+        Ok(SpreadsheetManager::with_backend(
+            CONFIG.sheets.clone(),
+            sheets_backend,
+        ))
     }
 }
@@ -1,4 +1,8 @@
+use crypto_balance_core::adapters::config::app_config::CONFIG;
 use crypto_balance_core::adapters::kafka_publisher::KafkaEventPublisher;
+use crypto_balance_core::adapters::persistence::file_schedule_state_repository::FileScheduleStateRepository;
+use crypto_balance_core::adapters::sheets::backend::dry_run::DryRunRecorder;
+use crypto_balance_core::application::scheduler::Scheduler;
 use crypto_balance_core::ports::application_service::ApplicationService;
 use crypto_balance_core::ports::command_handler::{Command, CommandError, CommandHandler};
 use crypto_balance_core::ports::event_handler::{CryptoEvent, EventPublisher};
@@ -8,6 +12,7 @@ use tracing::{error, info, instrument};
 pub struct CliAdapter {
     application_service: Arc<dyn ApplicationService>,
     kafka_publisher: Option<Arc<KafkaEventPublisher>>,
+    dry_run: Option<(Arc<DryRunRecorder>, Option<Box<str>>)>,
 }
 
 impl std::fmt::Debug for CliAdapter {
@@ -33,9 +38,17 @@ impl CliAdapter {
         Self {
             application_service,
             kafka_publisher,
+            dry_run: None,
         }
     }
 
+    /// Has `serve` export the writes recorded by `recorder` after every scheduled run, either to
+    /// `output` or to stdout.
+    pub fn with_dry_run(mut self, recorder: Arc<DryRunRecorder>, output: Option<Box<str>>) -> Self {
+        self.dry_run = Some((recorder, output));
+        self
+    }
+
     #[instrument]
     pub async fn run(&self, args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
         let command = self.parse_args(args)?;
@@ -84,6 +97,9 @@ impl CliAdapter {
             }
             Some("list") => Ok(Command::ListRoutines),
             Some("health") => Ok(Command::HealthCheck),
+            Some("validate") => Ok(Command::ValidateSchema),
+            Some("bootstrap") => Ok(Command::BootstrapSchema),
+            Some("serve") => Ok(Command::Serve),
             _ => Ok(Command::RunRoutines { parallel: true }), // Default behavior
         }
     }
@@ -150,6 +166,98 @@ impl CommandHandler for CliAdapter {
 
                 Ok(health)
             }
+            Command::ValidateSchema => {
+                let report = self
+                    .application_service
+                    .validate_schema()
+                    .await
+                    .map_err(|e| CommandError::ExecutionFailed {
+                        details: format!("Schema validation failed: {:?}", e),
+                    })?;
+
+                if report.is_valid() {
+                    return Ok(format!(
+                        "✅ Spreadsheet schema is valid ({} ranges checked)",
+                        report.checked
+                    ));
+                }
+
+                let problems = report
+                    .problems
+                    .iter()
+                    .map(|problem| format!("❌ {}", problem))
+                    .collect::<Vec<_>>();
+
+                Err(error_stack::report!(CommandError::ExecutionFailed {
+                    details: format!(
+                        "{} problems found in {} ranges:\n{}",
+                        problems.len(),
+                        report.checked,
+                        problems.join("\n")
+                    ),
+                }))
+            }
+            Command::BootstrapSchema => {
+                let report = self
+                    .application_service
+                    .bootstrap_schema()
+                    .await
+                    .map_err(|e| CommandError::ExecutionFailed {
+                        details: format!("Schema bootstrap failed: {:?}", e),
+                    })?;
+
+                if report.is_empty() {
+                    return Ok(format!(
+                        "✅ Nothing to create ({} named ranges already exist)",
+                        report.existing_ranges.len()
+                    ));
+                }
+
+                let created = report
+                    .created_sheets
+                    .iter()
+                    .map(|sheet| format!("➕ sheet '{}'", sheet))
+                    .chain(
+                        report
+                            .created_ranges
+                            .iter()
+                            .map(|range| format!("➕ named range {}", range)),
+                    )
+                    .collect::<Vec<_>>();
+
+                Ok(format!(
+                    "✅ Created {} sheets and {} named ranges ({} already existed):\n{}",
+                    report.created_sheets.len(),
+                    report.created_ranges.len(),
+                    report.existing_ranges.len(),
+                    created.join("\n")
+                ))
+            }
+            Command::Serve => {
+                let state = Arc::new(FileScheduleStateRepository::new(
+                    CONFIG.scheduler.state_file.as_ref(),
+                ));
+                let mut scheduler = Scheduler::from_config(
+                    Arc::clone(&self.application_service),
+                    state,
+                    &CONFIG.scheduler,
+                )
+                .map_err(|e| CommandError::InvalidCommand {
+                    details: format!("Invalid [scheduler] config: {:?}", e),
+                })?;
+                if let Some((recorder, output)) = &self.dry_run {
+                    scheduler = scheduler.with_dry_run(Arc::clone(recorder), output.clone());
+                }
+
+                scheduler
+                    .serve()
+                    .await
+                    .map_err(|e| CommandError::ExecutionFailed {
+                        details: format!("Scheduler failed: {:?}", e),
+                    })?;
+
+                Ok("Scheduler stopped".to_string())
+            }
         }
     }
 }
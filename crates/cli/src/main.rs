@@ -1,3 +1,6 @@
+use crypto_balance_core::adapters::config::app_config::CONFIG;
+use crypto_balance_core::adapters::config::sheets_config::DryRunConfig;
+use crypto_balance_core::adapters::sheets::backend::dry_run::DryRunRecorder;
 use crypto_balance_core::prettyprint::prettyprint::PrettyFormatter;
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
@@ -22,14 +25,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     setup_tracing()?;
     setup_panic_hook();
 
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let dry_run = take_dry_run_args(&mut args, CONFIG.sheets.dry_run.clone());
 
     info!("Starting crypto-balance CLI");
 
-    let app_service = ApplicationServiceFactory::create().await?;
-    let cli_adapter = Arc::new(CliAdapter::new(app_service));
+    let recorder = dry_run.enabled.then(|| Arc::new(DryRunRecorder::default()));
+    if recorder.is_some() {
+        info!("Dry run: spreadsheet writes will be recorded, not sent");
+    }
+
+    let app_service = ApplicationServiceFactory::create(recorder.clone()).await?;
+    let mut cli_adapter = CliAdapter::new(app_service);
+    if let Some(recorder) = &recorder {
+        cli_adapter = cli_adapter.with_dry_run(Arc::clone(recorder), dry_run.output.clone());
+    }
+    let cli_adapter = Arc::new(cli_adapter);
+
+    let result = cli_adapter.run(args).await;
+
+    if let Some(recorder) = recorder {
+        match recorder.export(dry_run.output.as_deref()) {
+            Ok(count) => info!("Dry run: {} writes recorded", count),
+            Err(e) => error!("Failed to export dry run writes: {:?}", e),
+        }
+    }
 
-    match cli_adapter.run(args).await {
+    match result {
         Ok(_) => {
             info!("CLI execution completed successfully");
             Ok(())
@@ -41,6 +63,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
+/// Removes the global `--dry-run` and `--dry-run-output <file>` flags from `args`, applying them
+/// on top of the `[sheets.dry_run]` config. An output file implies a dry run.
+fn take_dry_run_args(args: &mut Vec<String>, mut dry_run: DryRunConfig) -> DryRunConfig {
+    let mut iter = std::mem::take(args).into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run.enabled = true,
+            "--dry-run-output" => {
+                dry_run.enabled = true;
+                dry_run.output = iter.next().map(Into::into);
+            }
+            _ => args.push(arg),
+        }
+    }
+
+    dry_run
+}
+
 fn setup_tracing() -> Result<(), Box<dyn std::error::Error>> {
     let indicatif_layer = IndicatifLayer::new();
 
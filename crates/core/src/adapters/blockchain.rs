@@ -1,3 +1,6 @@
+pub mod bitcoin;
 pub mod chains;
+pub mod cosmos;
 pub mod explorers;
+pub mod solana;
 pub mod token;
@@ -0,0 +1,3 @@
+pub mod balance_fetcher;
+pub mod descriptor;
+pub mod esplora_client;
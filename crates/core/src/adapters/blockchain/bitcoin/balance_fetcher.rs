@@ -0,0 +1,135 @@
+use std::str::FromStr;
+
+use bitcoin::secp256k1::{Secp256k1, VerifyOnly};
+use error_stack::ResultExt;
+use futures::future::try_join_all;
+use tracing::instrument;
+
+use crate::adapters::config::blockchain_config::BitcoinWalletConfig;
+use crate::domain::blockchain::{
+    explorer::FetchBalanceError, token::NativeTokenSymbol, token_balance::TokenBalance,
+};
+
+use super::{
+    descriptor::{DescriptorError, WatchOnlyDescriptor},
+    esplora_client::EsploraClient,
+};
+
+const SATOSHIS_PER_BTC: f64 = 100_000_000f64;
+
+/// Watch-only balance of an xpub/descriptor wallet, found by scanning each chain until
+/// `gap_limit` consecutive addresses have never been used
+#[derive(Debug)]
+pub struct BitcoinBalanceFetcher {
+    descriptor: WatchOnlyDescriptor,
+    gap_limit: u32,
+    esplora: EsploraClient,
+    secp: Secp256k1<VerifyOnly>,
+}
+
+impl BitcoinBalanceFetcher {
+    pub fn new(config: &BitcoinWalletConfig) -> error_stack::Result<Self, DescriptorError> {
+        Ok(Self {
+            descriptor: WatchOnlyDescriptor::from_str(&config.descriptor)?,
+            gap_limit: config.gap_limit.max(1),
+            esplora: EsploraClient::new(&config.esplora_url),
+            secp: Secp256k1::verification_only(),
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn fetch_balance(&self) -> error_stack::Result<TokenBalance, FetchBalanceError> {
+        let mut used_addresses = Vec::new();
+        for chain in self.descriptor.scan_chains() {
+            used_addresses.extend(self.scan_chain(chain).await?);
+        }
+
+        tracing::debug!("Used bitcoin addresses: {:?}", used_addresses);
+
+        let satoshis: u64 = try_join_all(
+            used_addresses
+                .iter()
+                .map(|address| self.esplora.utxo_sum(address)),
+        )
+        .await?
+        .into_iter()
+        .sum();
+
+        Ok(TokenBalance {
+            symbol: NativeTokenSymbol::BTC.to_string(),
+            balance: satoshis as f64 / SATOSHIS_PER_BTC,
+        })
+    }
+
+    /// Queries addresses one gap-sized window at a time and returns those with any history
+    async fn scan_chain(
+        &self,
+        chain: Option<u32>,
+    ) -> error_stack::Result<Vec<String>, FetchBalanceError> {
+        let mut used_addresses = Vec::new();
+        let mut unused_streak = 0;
+        let mut window_start = 0;
+
+        while unused_streak < self.gap_limit {
+            let addresses = (window_start..window_start + self.gap_limit)
+                .map(|index| {
+                    self.descriptor
+                        .address(&self.secp, chain, index)
+                        .map(|address| address.to_string())
+                        .change_context(FetchBalanceError::ApiRequestError)
+                        .attach_printable_lazy(|| {
+                            format!("Failed to derive address {:?}/{}", chain, index)
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let tx_counts = try_join_all(
+                addresses
+                    .iter()
+                    .map(|address| self.esplora.tx_count(address)),
+            )
+            .await?;
+
+            for (address, tx_count) in addresses.into_iter().zip(tx_counts) {
+                if tx_count > 0 {
+                    used_addresses.push(address);
+                    unused_streak = 0;
+                } else {
+                    unused_streak += 1;
+                }
+            }
+
+            window_start += self.gap_limit;
+        }
+
+        Ok(used_addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapters::http::fixture_server::FixtureServer;
+
+    use super::*;
+
+    const BIP84_ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    #[tokio::test]
+    async fn test_fetch_balance_scans_up_to_gap_limit() {
+        // Receive addresses 0/0 and 0/2 hold 0.0007 and 0.0003 BTC, change address 1/0 holds
+        // 0.001 BTC. 0/6 holds 0.01 BTC, but comes after 0/3, 0/4 and 0/5 are unused, so it is
+        // past the gap limit and never asked for.
+        let server = FixtureServer::start("esplora_wallet", "https://blockstream.info/api").await;
+        let fetcher = BitcoinBalanceFetcher::new(&BitcoinWalletConfig {
+            descriptor: BIP84_ZPUB.into(),
+            gap_limit: 3,
+            esplora_url: server.base_url().into(),
+        })
+        .unwrap();
+
+        let balance = fetcher.fetch_balance().await.unwrap();
+
+        assert_eq!(balance.symbol, "BTC");
+        assert_eq!(balance.balance, 0.002);
+    }
+}
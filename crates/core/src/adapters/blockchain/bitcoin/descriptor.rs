@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+use bitcoin::base58;
+use bitcoin::bip32::{ChildNumber, Xpub};
+use bitcoin::secp256k1::{Secp256k1, Verification};
+use bitcoin::{Address, KnownHrp, NetworkKind};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DescriptorError {
+    #[error("Unsupported descriptor: {0}")]
+    UnsupportedDescriptor(String),
+    #[error("Invalid extended public key: {0}")]
+    InvalidKey(String),
+    #[error("Failed to derive address")]
+    DerivationError,
+}
+
+/// Output script used for every address derived from the key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// Legacy `1...` addresses (BIP44)
+    Pkh,
+    /// Wrapped segwit `3...` addresses (BIP49)
+    ShWpkh,
+    /// Native segwit `bc1q...` addresses (BIP84)
+    Wpkh,
+    /// Taproot `bc1p...` addresses (BIP86)
+    Tr,
+}
+
+/// SLIP-132 version bytes, normalized to plain xpub/tpub before decoding
+const MAINNET_XPUB: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+const TESTNET_TPUB: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+const SLIP132_VERSIONS: &[([u8; 4], [u8; 4], ScriptKind)] = &[
+    ([0x04, 0x88, 0xb2, 0x1e], MAINNET_XPUB, ScriptKind::Pkh), // xpub
+    ([0x04, 0x9d, 0x7c, 0xb2], MAINNET_XPUB, ScriptKind::ShWpkh), // ypub
+    ([0x04, 0xb2, 0x47, 0x46], MAINNET_XPUB, ScriptKind::Wpkh), // zpub
+    ([0x04, 0x35, 0x87, 0xcf], TESTNET_TPUB, ScriptKind::Pkh), // tpub
+    ([0x04, 0x4a, 0x52, 0x62], TESTNET_TPUB, ScriptKind::ShWpkh), // upub
+    ([0x04, 0x5f, 0x1c, 0xf6], TESTNET_TPUB, ScriptKind::Wpkh), // vpub
+];
+
+/// Watch-only subset of output descriptors: single-key `pkh`, `sh(wpkh)`, `wpkh` and `tr`
+/// over an account-level extended public key.
+///
+/// Accepted forms:
+/// - a bare `xpub`/`ypub`/`zpub` (or testnet `tpub`/`upub`/`vpub`), whose prefix picks the
+///   script kind and which scans both the receive (`0`) and change (`1`) chains
+/// - `wpkh([fingerprint/84h/0h/0h]xpub.../<0;1>/*)`, optionally with a `#checksum`;
+///   `/0/*` scans a single chain
+#[derive(Debug, Clone)]
+pub struct WatchOnlyDescriptor {
+    pub kind: ScriptKind,
+    pub xpub: Xpub,
+    /// Child indexes of the chains scanned below the account key, usually `[0, 1]`
+    pub chains: Vec<u32>,
+}
+
+impl FromStr for WatchOnlyDescriptor {
+    type Err = DescriptorError;
+
+    fn from_str(descriptor: &str) -> Result<Self, Self::Err> {
+        let descriptor = descriptor
+            .split_once('#')
+            .map_or(descriptor, |(descriptor, _checksum)| descriptor)
+            .trim();
+
+        const WRAPPERS: &[(&str, &str, ScriptKind)] = &[
+            ("sh(wpkh(", "))", ScriptKind::ShWpkh),
+            ("wpkh(", ")", ScriptKind::Wpkh),
+            ("pkh(", ")", ScriptKind::Pkh),
+            ("tr(", ")", ScriptKind::Tr),
+        ];
+
+        let wrapped = WRAPPERS.iter().find_map(|(prefix, suffix, kind)| {
+            descriptor
+                .strip_prefix(prefix)
+                .and_then(|inner| inner.strip_suffix(suffix))
+                .map(|inner| (inner, *kind))
+        });
+
+        match wrapped {
+            Some((key_expression, kind)) => parse_key_expression(key_expression, Some(kind)),
+            None if descriptor.contains('(') => Err(DescriptorError::UnsupportedDescriptor(
+                descriptor.to_owned(),
+            )),
+            None => parse_key_expression(descriptor, None),
+        }
+    }
+}
+
+fn parse_key_expression(
+    expression: &str,
+    kind: Option<ScriptKind>,
+) -> Result<WatchOnlyDescriptor, DescriptorError> {
+    // Key origin (`[fingerprint/path]`) is informative only for a watch-only scan
+    let expression = match expression.strip_prefix('[') {
+        Some(rest) => rest
+            .split_once(']')
+            .map(|(_origin, key)| key)
+            .ok_or_else(|| DescriptorError::UnsupportedDescriptor(expression.to_owned()))?,
+        None => expression,
+    };
+
+    let (key, path) = expression.split_once('/').unwrap_or((expression, ""));
+    let (xpub, implied_kind) = decode_extended_key(key)?;
+
+    let chains = match path {
+        "" | "<0;1>/*" => vec![0, 1],
+        "*" => vec![],
+        path => {
+            let chain = path
+                .strip_suffix("/*")
+                .and_then(|chain| chain.parse::<u32>().ok())
+                .ok_or_else(|| DescriptorError::UnsupportedDescriptor(expression.to_owned()))?;
+            vec![chain]
+        }
+    };
+
+    Ok(WatchOnlyDescriptor {
+        kind: kind.unwrap_or(implied_kind),
+        xpub,
+        chains,
+    })
+}
+
+fn decode_extended_key(key: &str) -> Result<(Xpub, ScriptKind), DescriptorError> {
+    let mut data =
+        base58::decode_check(key).map_err(|_| DescriptorError::InvalidKey(key.to_owned()))?;
+
+    let (_, normalized, kind) = SLIP132_VERSIONS
+        .iter()
+        .find(|(version, _, _)| data.starts_with(version))
+        .ok_or_else(|| DescriptorError::InvalidKey(key.to_owned()))?;
+    data[..4].copy_from_slice(normalized);
+
+    let xpub = Xpub::decode(&data).map_err(|_| DescriptorError::InvalidKey(key.to_owned()))?;
+    Ok((xpub, *kind))
+}
+
+impl WatchOnlyDescriptor {
+    /// Address at `<chain>/<index>` below the account key; `chain` is ignored for `/*` keys
+    pub fn address<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        chain: Option<u32>,
+        index: u32,
+    ) -> Result<Address, DescriptorError> {
+        let path = chain
+            .into_iter()
+            .chain(std::iter::once(index))
+            .map(ChildNumber::from_normal_idx)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| DescriptorError::DerivationError)?;
+
+        let child = self
+            .xpub
+            .derive_pub(secp, &path)
+            .map_err(|_| DescriptorError::DerivationError)?;
+
+        let network = self.xpub.network;
+        let hrp = match network {
+            NetworkKind::Main => KnownHrp::Mainnet,
+            NetworkKind::Test => KnownHrp::Testnets,
+        };
+        let address = match self.kind {
+            ScriptKind::Pkh => Address::p2pkh(child.to_pub(), network),
+            ScriptKind::ShWpkh => Address::p2shwpkh(&child.to_pub(), network),
+            ScriptKind::Wpkh => Address::p2wpkh(&child.to_pub(), hrp),
+            ScriptKind::Tr => Address::p2tr(secp, child.to_x_only_pub(), None, hrp),
+        };
+
+        Ok(address)
+    }
+
+    /// Chains to scan; `[None]` when the key derives addresses directly
+    pub fn scan_chains(&self) -> Vec<Option<u32>> {
+        if self.chains.is_empty() {
+            vec![None]
+        } else {
+            self.chains.iter().copied().map(Some).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP84 test vector, account m/84'/0'/0' of "abandon ... about"
+    const BIP84_ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    fn address(descriptor: &WatchOnlyDescriptor, chain: u32, index: u32) -> String {
+        descriptor
+            .address(&Secp256k1::verification_only(), Some(chain), index)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_zpub_derives_bip84_addresses() {
+        let descriptor = WatchOnlyDescriptor::from_str(BIP84_ZPUB).unwrap();
+
+        assert_eq!(descriptor.kind, ScriptKind::Wpkh);
+        assert_eq!(descriptor.chains, vec![0, 1]);
+        assert_eq!(
+            address(&descriptor, 0, 0),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(
+            address(&descriptor, 0, 1),
+            "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g"
+        );
+        assert_eq!(
+            address(&descriptor, 1, 0),
+            "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el"
+        );
+    }
+
+    #[test]
+    fn test_wpkh_descriptor_with_origin_and_checksum() {
+        let (xpub, _) = decode_extended_key(BIP84_ZPUB).unwrap();
+        let descriptor = WatchOnlyDescriptor::from_str(&format!(
+            "wpkh([73c5da0a/84h/0h/0h]{}/0/*)#checksum",
+            xpub
+        ))
+        .unwrap();
+
+        assert_eq!(descriptor.kind, ScriptKind::Wpkh);
+        assert_eq!(descriptor.chains, vec![0]);
+        assert_eq!(
+            address(&descriptor, 0, 0),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+    }
+
+    #[test]
+    fn test_rejects_unsupported_descriptors() {
+        assert!(WatchOnlyDescriptor::from_str("wsh(multi(2,xpub1,xpub2))").is_err());
+        assert!(WatchOnlyDescriptor::from_str("not-a-key").is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use error_stack::ResultExt;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::instrument;
+
+use crate::adapters::http::send_rate_limited;
+use crate::domain::blockchain::explorer::FetchBalanceError;
+
+#[derive(Deserialize, Debug)]
+struct AddressInfo {
+    chain_stats: TxStats,
+    mempool_stats: TxStats,
+}
+
+#[derive(Deserialize, Debug)]
+struct TxStats {
+    tx_count: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct Utxo {
+    value: u64,
+}
+
+/// Client for Esplora-compatible APIs (blockstream.info, mempool.space or a self-hosted
+/// electrs)
+#[derive(Debug)]
+pub struct EsploraClient {
+    client: Client,
+    base_url: String,
+}
+
+impl EsploraClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> error_stack::Result<T, FetchBalanceError> {
+        let url = format!("{}{}", self.base_url, path);
+
+        let text = send_rate_limited(|| self.client.get(&url), &url, None)
+            .await
+            .change_context(FetchBalanceError::ApiRequestError)
+            .attach_printable_lazy(|| format!("URL: {}", url))?;
+
+        serde_json::from_str(&text)
+            .change_context(FetchBalanceError::ResponseParsingError)
+            .attach_printable_lazy(|| format!("URL: {}\nResponse: {}", url, text))
+    }
+
+    /// Number of confirmed and unconfirmed transactions touching `address`
+    pub async fn tx_count(&self, address: &str) -> error_stack::Result<u64, FetchBalanceError> {
+        let info: AddressInfo = self.get(&format!("/address/{}", address)).await?;
+        Ok(info.chain_stats.tx_count + info.mempool_stats.tx_count)
+    }
+
+    /// Sum of the unspent outputs of `address`, in satoshis
+    pub async fn utxo_sum(&self, address: &str) -> error_stack::Result<u64, FetchBalanceError> {
+        let utxos: Vec<Utxo> = self.get(&format!("/address/{}/utxo", address)).await?;
+        Ok(utxos.iter().map(|utxo| utxo.value).sum())
+    }
+}
@@ -1,28 +1,115 @@
-use std::sync::LazyLock;
-
-use crate::domain::blockchain::{
-    chain::Chain,
-    token::{NativeTokenSymbol, Token},
-};
-
-use super::explorers::etherscan::{
-    arbiscan::ARBISCAN, optimistic_etherscan::OPTIMISTIC_ETHERSCAN, polygonscan::POLYGONSCAN,
-};
-
-pub static ARBITRUM: LazyLock<Chain> = LazyLock::new(|| Chain {
-    name: "Arbitrum",
-    native_token: Token::Native(NativeTokenSymbol::ETH).into(),
-    explorer: &*ARBISCAN,
-});
-
-pub static OPTIMISM: LazyLock<Chain> = LazyLock::new(|| Chain {
-    name: "Optimism",
-    native_token: Token::Native(NativeTokenSymbol::ETH).into(),
-    explorer: &*OPTIMISTIC_ETHERSCAN,
-});
-
-pub static POLYGON: LazyLock<Chain> = LazyLock::new(|| Chain {
-    name: "Polygon",
-    native_token: Token::Native(NativeTokenSymbol::MATIC).into(),
-    explorer: &*POLYGONSCAN,
-});
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use error_stack::{report, ResultExt};
+use thiserror::Error;
+
+use crate::adapters::config::blockchain_config::{BlockchainConfig, ChainConfig, ExplorerConfig};
+use crate::domain::blockchain::{chain::Chain, explorer::BlockExplorer, token::Token};
+
+use super::explorers::etherscan::etherscan_implementation::EtherscanImplementation;
+use super::token::spam_filter::SpamFilter;
+use super::token::token_cache::TokenCache;
+
+#[derive(Error, Debug)]
+pub enum ChainRegistryError {
+    #[error("Chain '{0}' is declared more than once")]
+    DuplicateChain(String),
+    #[error("Invalid spam filter configuration")]
+    InvalidSpamFilter,
+}
+
+/// Chains declared under `[[blockchain.chains]]`, built once at startup. Disabled chains are
+/// skipped entirely, so routines only ever see chains they should scan.
+#[derive(Debug)]
+pub struct ChainRegistry {
+    chains: Vec<Arc<Chain>>,
+    /// Routines start a [`SpamFilter::scan`] of it for each run
+    spam_filter: Arc<SpamFilter>,
+}
+
+impl ChainRegistry {
+    pub fn from_config(config: &BlockchainConfig) -> error_stack::Result<Self, ChainRegistryError> {
+        let spam_filter = Arc::new(
+            SpamFilter::from_config(&config.spam_filter)
+                .change_context(ChainRegistryError::InvalidSpamFilter)?,
+        );
+
+        let mut seen = HashSet::new();
+        let mut chains = Vec::new();
+
+        for chain_config in config.chains.iter().filter(|chain| chain.enabled) {
+            if !seen.insert(chain_config.name.to_lowercase()) {
+                return Err(report!(ChainRegistryError::DuplicateChain(
+                    chain_config.name.to_string()
+                )));
+            }
+
+            chains.push(Arc::new(build_chain(config, chain_config)));
+        }
+
+        tracing::debug!(
+            chains = ?chains.iter().map(|chain| chain.name.as_str()).collect::<Vec<_>>(),
+            "Chain registry built"
+        );
+
+        Ok(Self {
+            chains,
+            spam_filter,
+        })
+    }
+
+    pub fn chains(&self) -> &[Arc<Chain>] {
+        &self.chains
+    }
+
+    pub fn spam_filter(&self) -> &Arc<SpamFilter> {
+        &self.spam_filter
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Chain>> {
+        self.chains
+            .iter()
+            .find(|chain| chain.name.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+fn build_chain(config: &BlockchainConfig, chain_config: &ChainConfig) -> Chain {
+    let native_token: Arc<Token> = Token::Native(chain_config.native_token.clone()).into();
+    let token_cache = TokenCache::new(config.token_cache_dir.as_ref(), &chain_config.name);
+
+    let explorer: Arc<dyn BlockExplorer> = match &chain_config.explorer {
+        ExplorerConfig::EtherscanV2 {
+            chain_id,
+            endpoint,
+            api_key,
+        } => {
+            let api_key = api_key
+                .clone()
+                .unwrap_or_else(|| config.etherscan_api_key.clone());
+            let mut explorer =
+                EtherscanImplementation::v2(api_key, *chain_id, Arc::clone(&native_token));
+            if let Some(endpoint) = endpoint {
+                explorer.base_url = endpoint.to_string();
+            }
+            explorer.watchlist = chain_config.watchlist.clone();
+            explorer.token_cache = Some(token_cache);
+            Arc::new(explorer)
+        }
+        ExplorerConfig::Etherscan { endpoint, api_key } => Arc::new(EtherscanImplementation {
+            api_key: api_key.clone(),
+            base_url: endpoint.to_string(),
+            chain_id: None,
+            native_token: Arc::clone(&native_token),
+            watchlist: chain_config.watchlist.clone(),
+            token_cache: Some(token_cache),
+        }),
+    };
+
+    Chain {
+        name: chain_config.name.to_string(),
+        native_token,
+        explorer,
+    }
+}
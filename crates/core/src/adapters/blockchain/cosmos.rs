@@ -0,0 +1,3 @@
+pub mod balance_fetcher;
+pub mod denom;
+pub mod lcd_client;
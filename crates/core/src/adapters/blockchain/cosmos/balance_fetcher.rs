@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use error_stack::ResultExt;
+use futures::future::try_join_all;
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use crate::adapters::config::blockchain_config::{CosmosBlockchainConfig, CosmosDenomConfig};
+use crate::domain::blockchain::{explorer::FetchBalanceError, token_balance::TokenBalance};
+
+use super::{
+    denom::{known_denom, to_display_amount, DenomInfo},
+    lcd_client::{Coin, CosmosLcdClient},
+};
+
+#[derive(Debug)]
+struct CosmosChain {
+    name: &'static str,
+    address: Box<str>,
+    staking_denom: &'static str,
+    client: CosmosLcdClient,
+}
+
+#[derive(Debug)]
+pub struct CosmosBalanceFetcher {
+    chains: Vec<CosmosChain>,
+    denoms: HashMap<Box<str>, CosmosDenomConfig>,
+    /// IBC hashes are stable, so resolved traces are kept for the lifetime of the fetcher
+    ibc_denoms: Mutex<HashMap<String, String>>,
+    /// Bank metadata lookups by chain and denom, `None` when the chain has none
+    denom_infos: Mutex<HashMap<(&'static str, String), Option<DenomInfo>>>,
+}
+
+impl CosmosBalanceFetcher {
+    pub fn new(config: &CosmosBlockchainConfig) -> Self {
+        let chain = |name, address: &str, staking_denom, lcd_url: &str| CosmosChain {
+            name,
+            address: address.into(),
+            staking_denom,
+            client: CosmosLcdClient::new(lcd_url),
+        };
+
+        Self {
+            chains: vec![
+                chain(
+                    "Cosmos Hub",
+                    &config.cosmos_address,
+                    "uatom",
+                    &config.lcd.cosmos,
+                ),
+                chain(
+                    "Osmosis",
+                    &config.osmosis_address,
+                    "uosmo",
+                    &config.lcd.osmosis,
+                ),
+                chain(
+                    "Celestia",
+                    &config.celestia_address,
+                    "utia",
+                    &config.lcd.celestia,
+                ),
+                chain(
+                    "Injective",
+                    &config.injective_address,
+                    "inj",
+                    &config.lcd.injective,
+                ),
+            ],
+            denoms: config.denoms.clone(),
+            ibc_denoms: Mutex::new(HashMap::new()),
+            denom_infos: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Liquid, staked, unbonding and pending reward balances of every configured chain,
+    /// summed per symbol
+    #[instrument(skip(self))]
+    pub async fn fetch_balances(
+        &self,
+    ) -> error_stack::Result<Vec<TokenBalance>, FetchBalanceError> {
+        let coins_per_chain = try_join_all(
+            self.chains
+                .iter()
+                .map(|chain| self.fetch_chain_coins(chain)),
+        )
+        .await?;
+
+        let mut by_symbol: HashMap<String, f64> = HashMap::new();
+        for (chain, coins) in self.chains.iter().zip(coins_per_chain) {
+            for coin in coins {
+                let raw_amount = coin
+                    .amount
+                    .parse::<f64>()
+                    .change_context(FetchBalanceError::ResponseParsingError)
+                    .attach_printable_lazy(|| {
+                        format!(
+                            "Invalid amount '{}' of {} on {}",
+                            coin.amount, coin.denom, chain.name
+                        )
+                    })?;
+                if raw_amount <= 0.0 {
+                    continue;
+                }
+
+                let Some(DenomInfo { symbol, decimals }) =
+                    self.denom_info(chain, &coin.denom).await?
+                else {
+                    tracing::warn!(
+                        "Skipping {} {} on {}: unknown decimals, add it to [blockchain.airdrops.cosmos.denoms]",
+                        coin.amount,
+                        coin.denom,
+                        chain.name
+                    );
+                    continue;
+                };
+                *by_symbol.entry(symbol).or_default() += to_display_amount(raw_amount, decimals);
+            }
+        }
+
+        Ok(by_symbol
+            .into_iter()
+            .map(|(symbol, balance)| TokenBalance { symbol, balance })
+            .collect())
+    }
+
+    #[instrument(skip(self, chain), fields(chain = chain.name))]
+    async fn fetch_chain_coins(
+        &self,
+        chain: &CosmosChain,
+    ) -> error_stack::Result<Vec<Coin>, FetchBalanceError> {
+        let (balances, delegations, unbonding, rewards) = tokio::try_join!(
+            chain.client.bank_balances(&chain.address),
+            chain.client.delegations(&chain.address),
+            chain.client.unbonding(&chain.address, chain.staking_denom),
+            chain.client.pending_rewards(&chain.address),
+        )
+        .attach_printable_lazy(|| format!("Chain: {}", chain.name))?;
+
+        Ok(balances
+            .into_iter()
+            .chain(delegations)
+            .chain(unbonding)
+            .chain(rewards)
+            .collect())
+    }
+
+    /// Symbol and decimals of `denom`: configured or a staking denom, else from the bank metadata
+    /// of the chain the coin is held on. IBC coins the holding chain has no metadata for are
+    /// looked up by their base denom on the other chains, one of which it may come from.
+    async fn denom_info(
+        &self,
+        chain: &CosmosChain,
+        denom: &str,
+    ) -> error_stack::Result<Option<DenomInfo>, FetchBalanceError> {
+        let base_denom = self.base_denom(chain, denom).await?;
+        if let Some(info) = known_denom(&base_denom, &self.denoms) {
+            return Ok(Some(info));
+        }
+
+        if let Some(info) = self.metadata_info(chain, denom).await? {
+            return Ok(Some(info));
+        }
+        if base_denom == denom {
+            return Ok(None);
+        }
+
+        for origin in self.chains.iter().filter(|other| other.name != chain.name) {
+            if let Some(info) = self.metadata_info(origin, &base_denom).await? {
+                return Ok(Some(info));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Symbol and decimals of `denom` from the bank metadata of `chain`, if it has any
+    async fn metadata_info(
+        &self,
+        chain: &CosmosChain,
+        denom: &str,
+    ) -> error_stack::Result<Option<DenomInfo>, FetchBalanceError> {
+        let key = (chain.name, denom.to_string());
+        if let Some(info) = self.denom_infos.lock().await.get(&key) {
+            return Ok(info.clone());
+        }
+
+        let info = chain
+            .client
+            .denom_metadata(denom)
+            .await
+            .attach_printable_lazy(|| {
+                format!(
+                    "Failed to fetch the metadata of {} on {}",
+                    denom, chain.name
+                )
+            })?
+            .as_ref()
+            .and_then(DenomInfo::from_metadata);
+
+        self.denom_infos.lock().await.insert(key, info.clone());
+        Ok(info)
+    }
+
+    /// Strips the IBC trace of `ibc/<hash>` denoms, querying the chain the coin is held on
+    async fn base_denom(
+        &self,
+        chain: &CosmosChain,
+        denom: &str,
+    ) -> error_stack::Result<String, FetchBalanceError> {
+        let Some(hash) = denom.strip_prefix("ibc/") else {
+            return Ok(denom.to_string());
+        };
+
+        if let Some(base_denom) = self.ibc_denoms.lock().await.get(hash) {
+            return Ok(base_denom.clone());
+        }
+
+        let trace = chain
+            .client
+            .denom_trace(hash)
+            .await
+            .attach_printable_lazy(|| format!("Failed to resolve {} on {}", denom, chain.name))?;
+
+        tracing::trace!("Resolved {} to {}/{}", denom, trace.path, trace.base_denom);
+
+        self.ibc_denoms
+            .lock()
+            .await
+            .insert(hash.to_string(), trace.base_denom.clone());
+
+        Ok(trace.base_denom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapters::{
+        config::blockchain_config::CosmosLcdConfig, http::fixture_server::FixtureServer,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ibc_denoms_without_local_metadata() {
+        // Both coins are held on the Cosmos Hub, which has bank metadata for neither. KUJI is a
+        // known staking denom; allBTC is only described by Osmosis, where it was minted.
+        let server =
+            FixtureServer::start("cosmos_denoms", "https://cosmos-rest.publicnode.com").await;
+        let fetcher = CosmosBalanceFetcher::new(&CosmosBlockchainConfig {
+            cosmos_address: "cosmos1fixture".into(),
+            osmosis_address: "osmo1fixture".into(),
+            celestia_address: "celestia1fixture".into(),
+            injective_address: "inj1fixture".into(),
+            lcd: CosmosLcdConfig {
+                cosmos: server.base_url().into(),
+                osmosis: server.base_url().into(),
+                celestia: server.base_url().into(),
+                injective: server.base_url().into(),
+            },
+            denoms: HashMap::new(),
+        });
+        let cosmos_hub = &fetcher.chains[0];
+
+        let kuji = fetcher
+            .denom_info(
+                cosmos_hub,
+                "ibc/4CC44260793F84006656DD868E017578F827A492978161DA31D7572BCB3F4289",
+            )
+            .await
+            .unwrap();
+        let all_btc = fetcher
+            .denom_info(
+                cosmos_hub,
+                "ibc/AD5FB1E6A1FBD8E1D3F4B0E5A6C7AC9E1F2B8D2A5C06E3F7B95D1C4A8E0F6B72",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            kuji,
+            Some(DenomInfo {
+                symbol: "KUJI".to_string(),
+                decimals: 6,
+            })
+        );
+        assert_eq!(
+            all_btc,
+            Some(DenomInfo {
+                symbol: "allBTC".to_string(),
+                decimals: 8,
+            })
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use crate::adapters::config::blockchain_config::CosmosDenomConfig;
+
+use super::lcd_client::DenomMetadata;
+
+/// Display symbol and decimals of a base (non-IBC) denom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenomInfo {
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// Staking denoms of the supported chains and of chains their coins commonly come from over
+/// IBC, known without asking any chain
+const STAKING_DENOMS: &[(&str, &str, u32)] = &[
+    ("uatom", "ATOM", 6),
+    ("uosmo", "OSMO", 6),
+    ("utia", "TIA", 6),
+    ("inj", "INJ", 18),
+    ("ukuji", "KUJI", 6),
+];
+
+/// Resolves a base denom (already stripped of its IBC trace) from the configured denoms or the
+/// known staking denoms. Anything else needs a chain's bank metadata: the name of a denom says
+/// nothing reliable about its decimals. Symbols are kept as written (e.g. `stATOM`), the same
+/// as in the sheet.
+pub fn known_denom(
+    base_denom: &str,
+    configured: &HashMap<Box<str>, CosmosDenomConfig>,
+) -> Option<DenomInfo> {
+    if let Some(denom) = configured.get(base_denom) {
+        return Some(DenomInfo {
+            symbol: denom.symbol.to_string(),
+            decimals: denom.decimals,
+        });
+    }
+
+    STAKING_DENOMS
+        .iter()
+        .find(|(denom, _, _)| *denom == base_denom)
+        .map(|(_, symbol, decimals)| DenomInfo {
+            symbol: symbol.to_string(),
+            decimals: *decimals,
+        })
+}
+
+impl DenomInfo {
+    /// Symbol and decimals of the display unit declared in the chain's bank metadata
+    pub fn from_metadata(metadata: &DenomMetadata) -> Option<Self> {
+        let display_unit = metadata
+            .denom_units
+            .iter()
+            .find(|unit| unit.denom == metadata.display)?;
+        let symbol = if metadata.symbol.is_empty() {
+            &metadata.display
+        } else {
+            &metadata.symbol
+        };
+
+        Some(DenomInfo {
+            symbol: symbol.clone(),
+            decimals: display_unit.exponent,
+        })
+    }
+}
+
+/// Converts an on-chain amount (integer for coins, decimal for `DecCoin` rewards) to display units
+pub fn to_display_amount(raw_amount: f64, decimals: u32) -> f64 {
+    raw_amount / 10f64.powi(decimals as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::lcd_client::DenomUnit;
+    use super::*;
+
+    fn metadata(base: &str, display: &str, symbol: &str, exponent: u32) -> DenomMetadata {
+        DenomMetadata {
+            denom_units: vec![
+                DenomUnit {
+                    denom: base.to_string(),
+                    exponent: 0,
+                },
+                DenomUnit {
+                    denom: display.to_string(),
+                    exponent,
+                },
+            ],
+            display: display.to_string(),
+            symbol: symbol.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_staking_denoms() {
+        let configured = HashMap::new();
+
+        assert_eq!(
+            known_denom("uatom", &configured),
+            Some(DenomInfo {
+                symbol: "ATOM".to_string(),
+                decimals: 6
+            })
+        );
+        assert_eq!(known_denom("inj", &configured).unwrap().decimals, 18);
+        assert_eq!(known_denom("ukuji", &configured).unwrap().symbol, "KUJI");
+    }
+
+    #[test]
+    fn test_liquid_staking_denoms_are_not_guessed() {
+        let mut configured = HashMap::new();
+        assert_eq!(known_denom("stuatom", &configured), None);
+
+        let from_chain = metadata("ibc/C140AFD5", "statom", "stATOM", 6);
+        assert_eq!(
+            DenomInfo::from_metadata(&from_chain),
+            Some(DenomInfo {
+                symbol: "stATOM".to_string(),
+                decimals: 6
+            })
+        );
+
+        configured.insert(
+            "stuatom".into(),
+            CosmosDenomConfig {
+                symbol: "stATOM".into(),
+                decimals: 6,
+            },
+        );
+        assert_eq!(
+            known_denom("stuatom", &configured).unwrap().symbol,
+            "stATOM"
+        );
+    }
+
+    #[test]
+    fn test_factory_denoms_keep_their_name() {
+        let denom = "factory/osmo1z6r6qdknhgsc0zeracktgpcxf43j6sekq07nw8sxduc9lg0qjjlqfu25e3/alloyed/allBTC";
+        assert_eq!(known_denom(denom, &HashMap::new()), None);
+
+        let from_chain = metadata(denom, "allBTC", "", 8);
+        assert_eq!(
+            DenomInfo::from_metadata(&from_chain),
+            Some(DenomInfo {
+                symbol: "allBTC".to_string(),
+                decimals: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_metadata_without_display_unit() {
+        let mut incomplete = metadata("uusdc", "usdc", "USDC", 6);
+        incomplete.denom_units.pop();
+
+        assert_eq!(DenomInfo::from_metadata(&incomplete), None);
+    }
+
+    #[test]
+    fn test_to_display_amount() {
+        assert_eq!(to_display_amount(1_500_000f64, 6), 1.5);
+        assert_eq!(to_display_amount("123456.5".parse().unwrap(), 6), 0.1234565);
+    }
+}
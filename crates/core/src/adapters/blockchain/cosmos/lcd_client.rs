@@ -0,0 +1,210 @@
+use error_stack::ResultExt;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::instrument;
+
+use crate::adapters::http::{send_rate_limited, HttpError};
+use crate::domain::blockchain::explorer::FetchBalanceError;
+
+/// Amount of a denom; `amount` is an integer string for coins and a decimal one for `DecCoin`s
+#[derive(Deserialize, Debug, Clone)]
+pub struct Coin {
+    pub denom: String,
+    pub amount: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct BalancesResponse {
+    balances: Vec<Coin>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DelegationsResponse {
+    delegation_responses: Vec<DelegationResponse>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DelegationResponse {
+    balance: Coin,
+}
+
+#[derive(Deserialize, Debug)]
+struct UnbondingResponse {
+    unbonding_responses: Vec<UnbondingDelegation>,
+}
+
+#[derive(Deserialize, Debug)]
+struct UnbondingDelegation {
+    entries: Vec<UnbondingEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+struct UnbondingEntry {
+    balance: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct RewardsResponse {
+    #[serde(default)]
+    total: Vec<Coin>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DenomTraceResponse {
+    denom_trace: DenomTrace,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DenomTrace {
+    pub path: String,
+    pub base_denom: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct DenomMetadataResponse {
+    /// Missing from the "not found" error chains answer for denoms without metadata
+    #[serde(default)]
+    metadata: Option<DenomMetadata>,
+}
+
+/// Bank metadata of a denom: its units, the one shown to users and their symbol
+#[derive(Deserialize, Debug, Clone)]
+pub struct DenomMetadata {
+    #[serde(default)]
+    pub denom_units: Vec<DenomUnit>,
+    #[serde(default)]
+    pub display: String,
+    #[serde(default)]
+    pub symbol: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DenomUnit {
+    pub denom: String,
+    /// Power of 10 of one unit in base units; omitted for the base unit itself
+    #[serde(default)]
+    pub exponent: u32,
+}
+
+#[derive(Debug)]
+pub struct CosmosLcdClient {
+    client: Client,
+    base_url: String,
+}
+
+impl CosmosLcdClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> error_stack::Result<T, FetchBalanceError> {
+        let url = format!("{}{}", self.base_url, path);
+
+        let text = send_rate_limited(|| self.client.get(&url), &url, None)
+            .await
+            .change_context(FetchBalanceError::ApiRequestError)
+            .attach_printable_lazy(|| format!("URL: {}", url))?;
+
+        serde_json::from_str(&text)
+            .change_context(FetchBalanceError::ResponseParsingError)
+            .attach_printable_lazy(|| format!("URL: {}\nResponse: {}", url, text))
+    }
+
+    pub async fn bank_balances(
+        &self,
+        address: &str,
+    ) -> error_stack::Result<Vec<Coin>, FetchBalanceError> {
+        let response: BalancesResponse = self
+            .get(&format!(
+                "/cosmos/bank/v1beta1/balances/{}?pagination.limit=1000",
+                address
+            ))
+            .await?;
+        Ok(response.balances)
+    }
+
+    pub async fn delegations(
+        &self,
+        address: &str,
+    ) -> error_stack::Result<Vec<Coin>, FetchBalanceError> {
+        let response: DelegationsResponse = self
+            .get(&format!("/cosmos/staking/v1beta1/delegations/{}", address))
+            .await?;
+        Ok(response
+            .delegation_responses
+            .into_iter()
+            .map(|delegation| delegation.balance)
+            .collect())
+    }
+
+    /// Unbonding entries carry no denom: they are always in the chain's staking denom
+    pub async fn unbonding(
+        &self,
+        address: &str,
+        staking_denom: &str,
+    ) -> error_stack::Result<Vec<Coin>, FetchBalanceError> {
+        let response: UnbondingResponse = self
+            .get(&format!(
+                "/cosmos/staking/v1beta1/delegators/{}/unbonding_delegations",
+                address
+            ))
+            .await?;
+        Ok(response
+            .unbonding_responses
+            .into_iter()
+            .flat_map(|unbonding| unbonding.entries)
+            .map(|entry| Coin {
+                denom: staking_denom.to_string(),
+                amount: entry.balance,
+            })
+            .collect())
+    }
+
+    pub async fn pending_rewards(
+        &self,
+        address: &str,
+    ) -> error_stack::Result<Vec<Coin>, FetchBalanceError> {
+        let response: RewardsResponse = self
+            .get(&format!(
+                "/cosmos/distribution/v1beta1/delegators/{}/rewards",
+                address
+            ))
+            .await?;
+        Ok(response.total)
+    }
+
+    /// Resolves the hash of an `ibc/<hash>` denom to its origin
+    pub async fn denom_trace(
+        &self,
+        hash: &str,
+    ) -> error_stack::Result<DenomTrace, FetchBalanceError> {
+        let response: DenomTraceResponse = self
+            .get(&format!("/ibc/apps/transfer/v1/denom_traces/{}", hash))
+            .await?;
+        Ok(response.denom_trace)
+    }
+
+    /// Bank metadata of `denom` as registered on this chain, if any
+    pub async fn denom_metadata(
+        &self,
+        denom: &str,
+    ) -> error_stack::Result<Option<DenomMetadata>, FetchBalanceError> {
+        let response: error_stack::Result<DenomMetadataResponse, _> = self
+            .get(&format!("/cosmos/bank/v1beta1/denoms_metadata/{}", denom))
+            .await;
+        match response {
+            Ok(response) => Ok(response.metadata),
+            // Answered for denoms without metadata
+            Err(error) if HttpError::status(&error) == Some(404) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
@@ -1,4 +1 @@
-pub mod arbiscan;
 pub mod etherscan_implementation;
-pub mod optimistic_etherscan;
-pub mod polygonscan;
@@ -1,14 +1,16 @@
 use async_trait::async_trait;
+use futures::future::try_join_all;
 use serde::de::DeserializeOwned;
 
-use crate::adapters::blockchain::token::spam_filter;
-use crate::domain::blockchain::chain::Chain;
+use crate::adapters::blockchain::token::abi;
+use crate::adapters::blockchain::token::token_cache::{DiscoveredTokens, TokenCache};
+use crate::adapters::http::{send_rate_limited, HTTP_CLIENT};
 use crate::domain::blockchain::constants::WEI_CONVERSION;
-use crate::domain::blockchain::explorer::{BlockExplorer, FetchBalanceError};
+use crate::domain::blockchain::explorer::{BlockExplorer, FetchBalanceError, SpamCheck};
 use crate::domain::blockchain::token::{ERC20TokenInfo, Token};
 use crate::domain::blockchain::token_balance::TokenBalance;
 use std::collections::HashMap;
-use std::sync::{Arc, LazyLock};
+use std::sync::Arc;
 
 use error_stack::{Result, ResultExt};
 use tracing::instrument;
@@ -24,29 +26,228 @@ struct FetchBalanceResponse {
 struct FetchTokenTxResponse {
     status: String,
     message: String,
-    result: Vec<ERC20TokenInfo>,
+    result: Vec<TokenTransfer>,
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TokenTransfer {
+    block_number: Box<str>,
+    from: Box<str>,
+    #[serde(flatten)]
+    token: ERC20TokenInfo,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct EthCallResponse {
+    result: String,
+}
+
+pub const ETHERSCAN_V2_BASE_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// Etherscan refuses pages beyond `page * offset > 10000`, so long histories are walked in
+/// windows of `TOKENTX_MAX_PAGES` pages, each starting at the last block seen
+const TOKENTX_PAGE_SIZE: usize = 1000;
+const TOKENTX_MAX_PAGES: usize = 10;
+
 #[derive(Debug)]
 pub struct EtherscanImplementation {
     pub api_key: Box<str>,
     pub base_url: String,
-    pub chain: LazyLock<&'static Chain>,
+    /// Sent as `chainid` on every request when talking to the V2 multichain API
+    pub chain_id: Option<u64>,
+    pub native_token: Arc<Token>,
+    /// ERC-20 contracts always queried, even without transfer history
+    pub watchlist: Vec<Box<str>>,
+    /// Persists discovered tokens so repeat runs only scan new transfers
+    pub token_cache: Option<TokenCache>,
 }
 
-#[instrument]
-async fn fetch_and_deserialize<T: DeserializeOwned>(url: &str) -> Result<T, FetchBalanceError> {
-    let resp = reqwest::get(url)
+impl EtherscanImplementation {
+    pub fn v2(api_key: Box<str>, chain_id: u64, native_token: Arc<Token>) -> Self {
+        Self {
+            api_key,
+            base_url: ETHERSCAN_V2_BASE_URL.to_string(),
+            chain_id: Some(chain_id),
+            native_token,
+            watchlist: Vec::new(),
+            token_cache: None,
+        }
+    }
+
+    /// Base URL with the query string already opened, so callers only append `key=value&...`
+    fn endpoint(&self) -> String {
+        match self.chain_id {
+            Some(chain_id) => format!("{}?chainid={}&", self.base_url, chain_id),
+            None => format!("{}?", self.base_url),
+        }
+    }
+
+    /// Every ERC-20 transfer involving `evm_address` from `start_block` on, oldest first
+    #[instrument(skip(self))]
+    async fn fetch_token_transfers(
+        &self,
+        evm_address: &str,
+        mut start_block: u64,
+    ) -> Result<Vec<TokenTransfer>, FetchBalanceError> {
+        let api_key = self.api_key.as_ref();
+        let endpoint = self.endpoint();
+        let mut transfers = Vec::new();
+
+        'windows: loop {
+            for page in 1..=TOKENTX_MAX_PAGES {
+                let url = format!(
+                    "{endpoint}\
+                    module=account\
+                    &action=tokentx\
+                    &address={evm_address}\
+                    &startblock={start_block}\
+                    &page={page}\
+                    &offset={TOKENTX_PAGE_SIZE}\
+                    &sort=asc\
+                    &apikey={api_key}"
+                );
+
+                let resp: FetchTokenTxResponse = fetch_and_deserialize(&url, api_key).await?;
+                let page_len = resp.result.len();
+                transfers.extend(resp.result);
+
+                if page_len < TOKENTX_PAGE_SIZE {
+                    break 'windows;
+                }
+            }
+
+            // Transfers of the boundary block are fetched twice; tokens are deduplicated later
+            start_block = next_window_start(
+                transfers
+                    .last()
+                    .map(|transfer| transfer.block_number.as_ref()),
+                start_block,
+            )?;
+            tracing::debug!("Continuing token transfer scan from block {}", start_block);
+        }
+
+        Ok(transfers)
+    }
+
+    #[instrument(skip(self))]
+    async fn eth_call(
+        &self,
+        contract_address: &str,
+        data: &str,
+    ) -> Result<String, FetchBalanceError> {
+        let api_key = self.api_key.as_ref();
+        let endpoint = self.endpoint();
+        let url = format!(
+            "{endpoint}\
+            module=proxy\
+            &action=eth_call\
+            &to={contract_address}\
+            &data={data}\
+            &tag=latest\
+            &apikey={api_key}"
+        );
+
+        let resp: EthCallResponse = fetch_and_deserialize(&url, api_key).await?;
+        Ok(resp.result)
+    }
+
+    /// Reads `name()`, `symbol()` and `decimals()` of a contract with no transfer history
+    #[instrument(skip(self))]
+    async fn fetch_token_info(
+        &self,
+        contract_address: &str,
+    ) -> Result<ERC20TokenInfo, FetchBalanceError> {
+        let (name, symbol, decimals) = futures::try_join!(
+            self.eth_call(contract_address, abi::NAME_SELECTOR),
+            self.eth_call(contract_address, abi::SYMBOL_SELECTOR),
+            self.eth_call(contract_address, abi::DECIMALS_SELECTOR),
+        )?;
+
+        let decode_error = |field: &str, value: &str| {
+            error_stack::report!(FetchBalanceError::ResponseParsingError).attach_printable(format!(
+                "Invalid {} for contract {}: {}",
+                field, contract_address, value
+            ))
+        };
+
+        Ok(ERC20TokenInfo {
+            token_name: abi::decode_string(&name)
+                .ok_or_else(|| decode_error("name", &name))?
+                .into(),
+            token_symbol: abi::decode_string(&symbol)
+                .ok_or_else(|| decode_error("symbol", &symbol))?
+                .into(),
+            contract_address: contract_address.into(),
+            token_decimal: abi::decode_uint(&decimals)
+                .ok_or_else(|| decode_error("decimals", &decimals))?
+                .to_string()
+                .into(),
+        })
+    }
+
+    /// Cached tokens, plus those revealed by transfers since the last scan and the watchlist
+    #[instrument(skip(self))]
+    async fn discover_tokens(
+        &self,
+        evm_address: &str,
+    ) -> Result<DiscoveredTokens, FetchBalanceError> {
+        let mut discovered = match &self.token_cache {
+            Some(cache) => cache.load(evm_address).await,
+            None => DiscoveredTokens::default(),
+        };
+
+        let start_block = discovered.last_block.map_or(0, |block| block + 1);
+        let transfers = self.fetch_token_transfers(evm_address, start_block).await?;
+        tracing::debug!(
+            "{} new token transfers since block {}",
+            transfers.len(),
+            start_block
+        );
+
+        for transfer in transfers {
+            if let Ok(block) = transfer.block_number.parse::<u64>() {
+                discovered.last_block = discovered.last_block.max(Some(block));
+            }
+            if transfer.from.eq_ignore_ascii_case(evm_address) {
+                discovered
+                    .sent_contracts
+                    .insert(transfer.token.contract_address.to_lowercase());
+            }
+            discovered.insert(transfer.token);
+        }
+
+        for contract_address in &self.watchlist {
+            if !discovered.contains(contract_address) {
+                discovered.insert(self.fetch_token_info(contract_address).await?);
+            }
+        }
+
+        if let Some(cache) = &self.token_cache {
+            cache.store(evm_address, &discovered).await;
+        }
+
+        Ok(discovered)
+    }
+
+    fn is_watchlisted(&self, contract_address: &str) -> bool {
+        self.watchlist
+            .iter()
+            .any(|watched| watched.eq_ignore_ascii_case(contract_address))
+    }
+}
+
+/// Requests are paced by the bucket of the API key, which Etherscan V2 shares across chains
+#[instrument(skip(api_key))]
+async fn fetch_and_deserialize<T: DeserializeOwned>(
+    url: &str,
+    api_key: &str,
+) -> Result<T, FetchBalanceError> {
+    let resp = send_rate_limited(|| HTTP_CLIENT.get(url), url, Some(api_key))
         .await
         .change_context(FetchBalanceError::ApiRequestError)
         .attach_printable("Failed to make GET request")
         .attach_printable_lazy(|| format!("URL: {}", url))?;
-    let resp = resp
-        .text()
-        .await
-        .change_context(FetchBalanceError::ApiRequestError)
-        .attach_printable("Failed to get response text")
-        .attach_printable_lazy(|| format!("URL: {}", url))?;
     let resp = serde_json::from_str(resp.as_str())
         .change_context(FetchBalanceError::ResponseParsingError)
         .attach_printable("Failed to parse balance response as json")
@@ -73,21 +274,21 @@ impl BlockExplorer for EtherscanImplementation {
         evm_address: &str,
     ) -> Result<TokenBalance, FetchBalanceError> {
         let api_key = self.api_key.as_ref();
-        let base_url = self.base_url.as_str();
+        let endpoint = self.endpoint();
         let url = format!(
-            "{base_url}\
-                ?module=account\
+            "{endpoint}\
+                module=account\
                 &action=balance\
                 &address={evm_address}\
                 &tag=latest\
                 &apikey={api_key}"
         );
 
-        let resp = fetch_and_deserialize(&url).await?;
+        let resp = fetch_and_deserialize(&url, api_key).await?;
         let balance = parse_balance_from_response(resp).await? / WEI_CONVERSION;
 
         Ok(TokenBalance {
-            symbol: self.chain().native_token.symbol(),
+            symbol: self.native_token.symbol(),
             balance,
         })
     }
@@ -99,19 +300,20 @@ impl BlockExplorer for EtherscanImplementation {
         token_info: ERC20TokenInfo,
     ) -> Result<TokenBalance, FetchBalanceError> {
         let api_key = self.api_key.as_ref();
-        let base_url = self.base_url.as_str();
+        let endpoint = self.endpoint();
         let contract_address = &token_info.contract_address;
         let url = format!(
-            "{base_url}\
-                ?module=account\
+            "{endpoint}\
+                module=account\
                 &action=tokenbalance\
                 &contractaddress={contract_address}\
                 &address={evm_address}\
                 &tag=latest&apikey={api_key}"
         );
 
-        let resp = fetch_and_deserialize(&url).await?;
-        let balance = parse_balance_from_response(resp).await? / WEI_CONVERSION;
+        let resp = fetch_and_deserialize(&url, api_key).await?;
+        let balance =
+            parse_balance_from_response(resp).await? / decimals_divisor(&token_info.token_decimal)?;
 
         Ok(TokenBalance {
             symbol: token_info.token_symbol.into_string(),
@@ -119,62 +321,147 @@ impl BlockExplorer for EtherscanImplementation {
         })
     }
 
-    #[instrument(skip(self))]
+    #[instrument(skip(self, spam_check))]
     async fn fetch_erc20_balances(
         &self,
         evm_address: &str,
+        spam_check: &dyn SpamCheck,
     ) -> Result<HashMap<Arc<Token>, TokenBalance>, FetchBalanceError> {
-        // Step 1. Fetch all ERC20 token transfers for the given address
+        // Step 1. Discover the tokens held by the address (cache, new transfers, watchlist)
         // Step 2. For each token, fetch the balance of the token for the given address
-        // Attention: wait for 0.25 seconds between each request to avoid rate limiting
-
-        // TODO: Create functions for step 1 and step 2
-        let api_key = self.api_key.as_ref();
-        let base_url = self.base_url.as_str();
-        let url: String = format!(
-            "{base_url}\
-            ?module=account\
-            &action=tokentx\
-            &address={evm_address}\
-            &tag=latest&apikey={api_key}"
-        );
+        // Balance requests run concurrently; the rate limiter keeps them within the API limits
 
-        let resp: FetchTokenTxResponse = fetch_and_deserialize(&url).await?;
+        let discovered = self.discover_tokens(evm_address).await?;
 
-        let tokens: Vec<_> = resp
-            .result
+        // Watchlisted tokens were chosen explicitly, so they skip the spam filter
+        let tokens: Vec<_> = discovered
+            .tokens
             .into_iter()
-            .filter(|info| !spam_filter::check_spam(&info.token_symbol, &info.token_name))
+            .filter(|info| {
+                let received_never_sent = !discovered
+                    .sent_contracts
+                    .contains(&info.contract_address.to_lowercase());
+                self.is_watchlisted(&info.contract_address)
+                    || !spam_check.is_spam(info, received_never_sent)
+            })
             .map(Token::ERC20)
             .collect();
 
-        let mut balances = HashMap::new();
-        for token in tokens {
-            if let Token::ERC20(token_info) = &token {
-                if balances.contains_key(&token) {
-                    continue; // Skip if we already fetched the balance for this token
-                }
-
-                let balance = self
-                    .fetch_erc20_balance(evm_address, token_info.clone())
-                    .await
-                    .attach_printable_lazy(|| {
-                        format!("Failed to fetch balance for token: {:?}", token)
-                    })?;
+        let balances = try_join_all(tokens.into_iter().map(|token| async move {
+            let Token::ERC20(token_info) = &token else {
+                unreachable!("Token should be ERC20 since we just converted it")
+            };
 
-                // Wait for 0.25 seconds between each request to avoid rate limiting
-                std::thread::sleep(std::time::Duration::from_millis(250));
+            let balance = self
+                .fetch_erc20_balance(evm_address, token_info.clone())
+                .await
+                .attach_printable_lazy(|| {
+                    format!("Failed to fetch balance for token: {:?}", token)
+                })?;
 
-                balances.insert(token.into(), balance);
-            } else {
-                unreachable!("Token should be ERC20 since we just converted it")
-            }
-        }
+            Ok::<_, error_stack::Report<FetchBalanceError>>((Arc::new(token), balance))
+        }))
+        .await?
+        .into_iter()
+        .collect();
 
         Ok(balances)
     }
+}
+
+/// What a raw token amount is divided by to get whole tokens, e.g. 1e6 for USDC
+fn decimals_divisor(token_decimal: &str) -> Result<f64, FetchBalanceError> {
+    let decimals = token_decimal
+        .parse::<i32>()
+        .change_context(FetchBalanceError::ResponseParsingError)
+        .attach_printable_lazy(|| format!("Invalid token decimals '{}'", token_decimal))?;
+    Ok(10f64.powi(decimals))
+}
+
+/// Block the next `tokentx` window starts at: the last one seen, which must be past the start
+/// of the current window, or the scan would request the same window forever
+fn next_window_start(last_block: Option<&str>, start_block: u64) -> Result<u64, FetchBalanceError> {
+    let last_block = last_block.unwrap_or_default();
+    let next_block = last_block
+        .parse::<u64>()
+        .change_context(FetchBalanceError::ResponseParsingError)
+        .attach_printable_lazy(|| format!("Invalid transfer block number '{}'", last_block))?;
+
+    if next_block <= start_block {
+        return Err(error_stack::report!(FetchBalanceError::ApiRequestError)).attach_printable(
+            format!(
+                "More than {} token transfers in block {}, the scan cannot move past it",
+                TOKENTX_PAGE_SIZE * TOKENTX_MAX_PAGES,
+                start_block
+            ),
+        );
+    }
+
+    Ok(next_block)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapters::blockchain::token::spam_filter::SpamFilter;
+    use crate::adapters::config::blockchain_config::SpamFilterConfig;
+    use crate::adapters::http::fixture_server::{credential, FixtureServer};
+    use crate::domain::blockchain::token::NativeTokenSymbol;
+
+    use super::*;
 
-    fn chain(&self) -> &'static Chain {
-        *self.chain
+    const ADDRESS: &str = "0x5a52e96bacdabb82fd05763e25335261b270efcb";
+
+    fn explorer(server: &FixtureServer) -> EtherscanImplementation {
+        let mut explorer = EtherscanImplementation::v2(
+            credential("ETHERSCAN_API_KEY"),
+            1,
+            Arc::new(Token::Native(NativeTokenSymbol::ETH)),
+        );
+        explorer.base_url = format!("{}/v2/api", server.base_url());
+        explorer
+    }
+
+    #[test]
+    fn test_next_window_start_must_advance() {
+        assert_eq!(
+            next_window_start(Some("19000123"), 18000000).unwrap(),
+            19000123
+        );
+
+        // A whole window inside one block, or an unreadable block number, would loop forever
+        assert!(next_window_start(Some("18000000"), 18000000).is_err());
+        assert!(next_window_start(Some("0x1122"), 18000000).is_err());
+        assert!(next_window_start(None, 18000000).is_err());
+    }
+
+    #[test]
+    fn test_decimals_divisor() {
+        assert_eq!(decimals_divisor("18").unwrap(), WEI_CONVERSION);
+        assert_eq!(decimals_divisor("6").unwrap(), 1e6);
+        assert_eq!(decimals_divisor("0").unwrap(), 1.0);
+        assert!(decimals_divisor("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_balances_skipping_spam_tokens() {
+        let server = FixtureServer::start("etherscan_balances", ETHERSCAN_V2_BASE_URL).await;
+        let explorer = explorer(&server);
+
+        let native = explorer.fetch_native_balance(ADDRESS).await.unwrap();
+        assert_eq!((native.symbol.as_str(), native.balance), ("ETH", 1.5));
+
+        // The "Visit eth-rewards.io" token is never queried
+        let spam_filter = SpamFilter::from_config(&SpamFilterConfig::default()).unwrap();
+        let balances = explorer
+            .fetch_erc20_balances(ADDRESS, &spam_filter.scan(None))
+            .await
+            .unwrap()
+            .into_values()
+            .map(|balance| (balance.symbol, balance.balance))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(
+            balances,
+            HashMap::from([("DAI".to_string(), 250.0), ("USDC".to_string(), 1234.56)])
+        );
     }
 }
@@ -0,0 +1,3 @@
+pub mod balance_fetcher;
+pub mod rpc_client;
+pub mod token_list;
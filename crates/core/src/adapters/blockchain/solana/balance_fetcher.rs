@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use tracing::instrument;
+
+use crate::adapters::config::blockchain_config::SolanaBlockchainConfig;
+use crate::domain::blockchain::{
+    explorer::FetchBalanceError, token::NativeTokenSymbol, token_balance::TokenBalance,
+};
+
+use super::{
+    rpc_client::{
+        SolanaRpcClient, SplTokenAmount, SPL_TOKEN_2022_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID,
+    },
+    token_list::{SolanaTokenList, TokenListCache},
+};
+
+#[derive(Debug)]
+pub struct SolanaBalanceFetcher {
+    rpc_client: SolanaRpcClient,
+    token_list_url: Box<str>,
+    token_list_cache: Option<TokenListCache>,
+}
+
+impl SolanaBalanceFetcher {
+    pub fn new(config: &SolanaBlockchainConfig) -> Self {
+        Self {
+            rpc_client: SolanaRpcClient::new(config.rpc_url.to_string()),
+            token_list_url: config.token_list_url.clone(),
+            token_list_cache: None,
+        }
+    }
+
+    /// Keeps the token list under `dir` between runs instead of downloading it every time
+    pub fn with_token_list_cache(mut self, dir: &str) -> Self {
+        self.token_list_cache = Some(TokenListCache::new(dir));
+        self
+    }
+
+    /// Fetches SOL plus every SPL and Token-2022 balance held by `address`.
+    /// Mints missing from the token list are reported under their mint address.
+    #[instrument(skip(self))]
+    pub async fn fetch_balances(
+        &self,
+        address: &str,
+    ) -> error_stack::Result<Vec<TokenBalance>, FetchBalanceError> {
+        let (sol_balance, spl_accounts, spl_2022_accounts, token_list) = tokio::try_join!(
+            self.rpc_client.get_sol_balance(address),
+            self.rpc_client
+                .get_token_accounts(address, SPL_TOKEN_PROGRAM_ID),
+            self.rpc_client
+                .get_token_accounts(address, SPL_TOKEN_2022_PROGRAM_ID),
+            SolanaTokenList::fetch(&self.token_list_url, self.token_list_cache.as_ref()),
+        )?;
+
+        let mut balances = vec![TokenBalance {
+            symbol: NativeTokenSymbol::SOL.to_string(),
+            balance: sol_balance,
+        }];
+
+        balances.extend(aggregate_by_symbol(
+            spl_accounts.into_iter().chain(spl_2022_accounts),
+            &token_list,
+        ));
+
+        Ok(balances)
+    }
+}
+
+/// A wallet may hold several accounts of the same mint, so amounts are summed per symbol
+fn aggregate_by_symbol(
+    accounts: impl IntoIterator<Item = SplTokenAmount>,
+    token_list: &SolanaTokenList,
+) -> Vec<TokenBalance> {
+    let mut by_symbol: HashMap<String, f64> = HashMap::new();
+    for account in accounts.into_iter().filter(|account| account.amount > 0.0) {
+        let symbol = token_list
+            .symbol(&account.mint)
+            .map(str::to_owned)
+            .unwrap_or(account.mint);
+        *by_symbol.entry(symbol).or_default() += account.amount;
+    }
+
+    by_symbol
+        .into_iter()
+        .map(|(symbol, balance)| TokenBalance { symbol, balance })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const BONK_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+    const UNLISTED_MINT: &str = "unlisted1111111111111111111111111111111111";
+
+    fn account(mint: &str, amount: f64) -> SplTokenAmount {
+        SplTokenAmount {
+            mint: mint.to_owned(),
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_by_symbol() {
+        let token_list = SolanaTokenList::parse(&format!(
+            r#"[{{"address": "{USDC_MINT}", "symbol": "USDC"}}, {{"address": "{BONK_MINT}", "symbol": "Bonk"}}]"#
+        ))
+        .unwrap();
+
+        let mut balances = aggregate_by_symbol(
+            [
+                account(USDC_MINT, 12.5),
+                account(USDC_MINT, 7.5),
+                account(BONK_MINT, 0.0),
+                account(UNLISTED_MINT, 3.0),
+            ],
+            &token_list,
+        )
+        .into_iter()
+        .map(|balance| (balance.symbol, balance.balance))
+        .collect::<Vec<_>>();
+        balances.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Accounts of one mint are summed, empty accounts dropped and unlisted mints kept under
+        // their address
+        assert_eq!(
+            balances,
+            vec![("USDC".to_owned(), 20.0), (UNLISTED_MINT.to_owned(), 3.0),]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use error_stack::{report, ResultExt};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::instrument;
+
+use crate::adapters::http::send_rate_limited;
+use crate::domain::blockchain::explorer::FetchBalanceError;
+
+pub const SPL_TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const SPL_TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000f64;
+
+#[derive(Deserialize, Debug)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcErrorBody>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RpcErrorBody {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct WithContext<T> {
+    value: T,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenAccount {
+    account: TokenAccountData,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenAccountData {
+    data: ParsedAccountData,
+}
+
+#[derive(Deserialize, Debug)]
+struct ParsedAccountData {
+    parsed: ParsedTokenAccount,
+}
+
+#[derive(Deserialize, Debug)]
+struct ParsedTokenAccount {
+    info: TokenAccountInfo,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TokenAccountInfo {
+    mint: String,
+    token_amount: TokenAmount,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TokenAmount {
+    ui_amount_string: String,
+}
+
+/// Amount held in a single SPL token account, already scaled by the mint decimals
+#[derive(Debug, Clone)]
+pub struct SplTokenAmount {
+    pub mint: String,
+    pub amount: f64,
+}
+
+#[derive(Debug)]
+pub struct SolanaRpcClient {
+    client: Client,
+    rpc_url: String,
+}
+
+impl SolanaRpcClient {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            client: Client::new(),
+            rpc_url,
+        }
+    }
+
+    #[instrument(skip(self, params))]
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> error_stack::Result<T, FetchBalanceError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let text = send_rate_limited(
+            || self.client.post(&self.rpc_url).json(&body),
+            &self.rpc_url,
+            None,
+        )
+        .await
+        .change_context(FetchBalanceError::ApiRequestError)
+        .attach_printable_lazy(|| format!("Solana RPC method: {}", method))?;
+
+        let response: RpcResponse<T> = serde_json::from_str(&text)
+            .change_context(FetchBalanceError::ResponseParsingError)
+            .attach_printable_lazy(|| format!("Response: {}", text))?;
+
+        if let Some(error) = response.error {
+            return Err(report!(FetchBalanceError::ApiRequestError)).attach_printable(format!(
+                "Solana RPC error {} on '{}': {}",
+                error.code, method, error.message
+            ));
+        }
+
+        response
+            .result
+            .ok_or(report!(FetchBalanceError::ResponseParsingError))
+            .attach_printable_lazy(|| format!("Missing result for '{}': {}", method, text))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_sol_balance(
+        &self,
+        address: &str,
+    ) -> error_stack::Result<f64, FetchBalanceError> {
+        let lamports: WithContext<u64> = self.call("getBalance", json!([address])).await?;
+        Ok(lamports.value as f64 / LAMPORTS_PER_SOL)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_token_accounts(
+        &self,
+        owner: &str,
+        program_id: &str,
+    ) -> error_stack::Result<Vec<SplTokenAmount>, FetchBalanceError> {
+        let accounts: WithContext<Vec<TokenAccount>> = self
+            .call(
+                "getTokenAccountsByOwner",
+                json!([owner, { "programId": program_id }, { "encoding": "jsonParsed" }]),
+            )
+            .await?;
+
+        accounts
+            .value
+            .into_iter()
+            .map(|account| {
+                let info = account.account.data.parsed.info;
+                let amount = info
+                    .token_amount
+                    .ui_amount_string
+                    .parse::<f64>()
+                    .change_context(FetchBalanceError::ResponseParsingError)
+                    .attach_printable_lazy(|| {
+                        format!(
+                            "Invalid amount '{}' for mint {}",
+                            info.token_amount.ui_amount_string, info.mint
+                        )
+                    })?;
+
+                Ok(SplTokenAmount {
+                    mint: info.mint,
+                    amount,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapters::http::fixture_server::FixtureServer;
+
+    use super::*;
+
+    const OWNER: &str = "5oNDL3swdJJF1g9DzJiZ4ynHXgszjAEpUkxVYejchzrY";
+
+    #[tokio::test]
+    async fn test_parses_balances_and_token_accounts() {
+        // The cassette answers getBalance, then getTokenAccountsByOwner, then an RPC error
+        let server =
+            FixtureServer::start("solana_rpc", "https://api.mainnet-beta.solana.com").await;
+        let client = SolanaRpcClient::new(server.base_url().to_owned());
+
+        assert_eq!(client.get_sol_balance(OWNER).await.unwrap(), 1.5);
+
+        let accounts = client
+            .get_token_accounts(OWNER, SPL_TOKEN_PROGRAM_ID)
+            .await
+            .unwrap();
+        assert_eq!(
+            accounts
+                .iter()
+                .map(|account| (account.mint.as_str(), account.amount))
+                .collect::<Vec<_>>(),
+            vec![
+                ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 12.5),
+                ("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", 0.0),
+            ]
+        );
+
+        let error = client
+            .get_token_accounts(OWNER, SPL_TOKEN_2022_PROGRAM_ID)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.current_context(),
+            FetchBalanceError::ApiRequestError
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::Utc;
+use error_stack::ResultExt;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::adapters::http::{send_rate_limited, HTTP_CLIENT};
+use crate::domain::blockchain::explorer::FetchBalanceError;
+
+/// A cached token list younger than this (in seconds) is used without downloading it again
+const TOKEN_LIST_MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Deserialize, Debug)]
+struct TokenListEntry {
+    address: String,
+    symbol: String,
+}
+
+/// Both the solana-labs `{ "tokens": [...] }` format and plain arrays (e.g. Jupiter) are accepted
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum TokenListResponse {
+    Wrapped { tokens: Vec<TokenListEntry> },
+    Plain(Vec<TokenListEntry>),
+}
+
+/// Token list as stored in the cache, with where and when it was downloaded
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedTokenList {
+    url: String,
+    /// Unix timestamp, in seconds
+    fetched_at: i64,
+    symbols_by_mint: HashMap<String, String>,
+}
+
+/// JSON file under the configured token cache directory, so the list (several megabytes) is
+/// downloaded once a day rather than on every run. Like the ERC-20
+/// [`TokenCache`](crate::adapters::blockchain::token::token_cache::TokenCache), it is
+/// best-effort: unreadable or unwritable files only cost a download.
+#[derive(Debug, Clone)]
+pub struct TokenListCache {
+    path: PathBuf,
+}
+
+impl TokenListCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            path: dir.into().join("solana_token_list.json"),
+        }
+    }
+
+    /// The cached list, unless it was downloaded from another URL
+    async fn load(&self, url: &str) -> Option<CachedTokenList> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(error) => {
+                tracing::warn!(
+                    "Failed to read token list cache {}: {}",
+                    self.path.display(),
+                    error
+                );
+                return None;
+            }
+        };
+
+        match serde_json::from_str::<CachedTokenList>(&contents) {
+            Ok(cached) if cached.url == url => Some(cached),
+            Ok(_) => None,
+            Err(error) => {
+                tracing::warn!(
+                    "Ignoring corrupt token list cache {}: {}",
+                    self.path.display(),
+                    error
+                );
+                None
+            }
+        }
+    }
+
+    async fn store(&self, cached: &CachedTokenList) {
+        let result = async {
+            if let Some(dir) = self.path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            let contents = serde_json::to_string(cached)?;
+            tokio::fs::write(&self.path, contents).await
+        }
+        .await;
+
+        if let Err(error) = result {
+            tracing::warn!(
+                "Failed to write token list cache {}: {}",
+                self.path.display(),
+                error
+            );
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SolanaTokenList {
+    symbols_by_mint: HashMap<String, String>,
+}
+
+impl SolanaTokenList {
+    /// The list at `url`, from `cache` while it is less than a day old. An older cached list
+    /// still stands in when the download fails.
+    #[instrument(skip(cache))]
+    pub async fn fetch(
+        url: &str,
+        cache: Option<&TokenListCache>,
+    ) -> error_stack::Result<Self, FetchBalanceError> {
+        let cached = match cache {
+            Some(cache) => cache.load(url).await,
+            None => None,
+        };
+
+        if let Some(cached) = cached
+            .as_ref()
+            .filter(|cached| Utc::now().timestamp() - cached.fetched_at < TOKEN_LIST_MAX_AGE_SECS)
+        {
+            return Ok(Self {
+                symbols_by_mint: cached.symbols_by_mint.clone(),
+            });
+        }
+
+        let list = match Self::download(url).await {
+            Ok(list) => list,
+            Err(error) => {
+                let Some(cached) = cached else {
+                    return Err(error);
+                };
+                tracing::warn!(
+                    "Using the token list cached {} seconds ago: {:?}",
+                    Utc::now().timestamp() - cached.fetched_at,
+                    error
+                );
+                return Ok(Self {
+                    symbols_by_mint: cached.symbols_by_mint,
+                });
+            }
+        };
+
+        if let Some(cache) = cache {
+            cache
+                .store(&CachedTokenList {
+                    url: url.to_owned(),
+                    fetched_at: Utc::now().timestamp(),
+                    symbols_by_mint: list.symbols_by_mint.clone(),
+                })
+                .await;
+        }
+
+        Ok(list)
+    }
+
+    async fn download(url: &str) -> error_stack::Result<Self, FetchBalanceError> {
+        let text = send_rate_limited(|| HTTP_CLIENT.get(url), url, None)
+            .await
+            .change_context(FetchBalanceError::ApiRequestError)
+            .attach_printable_lazy(|| format!("Failed to download token list from {}", url))?;
+
+        Self::parse(&text)
+    }
+
+    pub fn parse(json: &str) -> error_stack::Result<Self, FetchBalanceError> {
+        let response: TokenListResponse = serde_json::from_str(json)
+            .change_context(FetchBalanceError::ResponseParsingError)
+            .attach_printable("Token list is neither a token-list document nor an array")?;
+
+        let entries = match response {
+            TokenListResponse::Wrapped { tokens } => tokens,
+            TokenListResponse::Plain(tokens) => tokens,
+        };
+
+        Ok(Self {
+            symbols_by_mint: entries
+                .into_iter()
+                .map(|entry| (entry.address, entry.symbol))
+                .collect(),
+        })
+    }
+
+    pub fn symbol(&self, mint: &str) -> Option<&str> {
+        self.symbols_by_mint.get(mint).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapters::{http::fixture_server::FixtureServer, persistence::temp_dir};
+
+    use super::*;
+
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const TOKEN_LIST_PATH: &str = "/solana-labs/token-list/main/src/tokens/solana.tokenlist.json";
+
+    async fn token_list_server() -> FixtureServer {
+        FixtureServer::start("solana_token_list", "https://raw.githubusercontent.com").await
+    }
+
+    const HOUR: i64 = 60 * 60;
+
+    fn cached_list(url: &str, age_secs: i64, symbol: &str) -> CachedTokenList {
+        CachedTokenList {
+            url: url.to_owned(),
+            fetched_at: Utc::now().timestamp() - age_secs,
+            symbols_by_mint: HashMap::from([(USDC_MINT.to_owned(), symbol.to_owned())]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_stores_the_downloaded_list() {
+        let dir = temp_dir("solana_token_list_store");
+        let cache = TokenListCache::new(&dir);
+        let server = token_list_server().await;
+        let url = format!("{}{}", server.base_url(), TOKEN_LIST_PATH);
+
+        let list = SolanaTokenList::fetch(&url, Some(&cache)).await.unwrap();
+        assert_eq!(list.symbol(USDC_MINT), Some("USDC"));
+
+        let cached = cache.load(&url).await.unwrap();
+        assert_eq!(cached.symbols_by_mint, list.symbols_by_mint);
+        // A list downloaded from elsewhere is not reused
+        assert!(cache.load("https://token.jup.ag/strict").await.is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_uses_the_cache_while_fresh() {
+        let dir = temp_dir("solana_token_list_fresh");
+        let cache = TokenListCache::new(&dir);
+        let server = token_list_server().await;
+        let url = format!("{}{}", server.base_url(), TOKEN_LIST_PATH);
+
+        // Fresh: not downloaded again
+        cache.store(&cached_list(&url, HOUR, "CACHED")).await;
+        let list = SolanaTokenList::fetch(&url, Some(&cache)).await.unwrap();
+        assert_eq!(list.symbol(USDC_MINT), Some("CACHED"));
+
+        // Stale: downloaded again and replaced
+        cache.store(&cached_list(&url, 48 * HOUR, "CACHED")).await;
+        let list = SolanaTokenList::fetch(&url, Some(&cache)).await.unwrap();
+        assert_eq!(list.symbol(USDC_MINT), Some("USDC"));
+        assert!(Utc::now().timestamp() - cache.load(&url).await.unwrap().fetched_at < HOUR);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_falls_back_to_a_stale_cache() {
+        let dir = temp_dir("solana_token_list_stale");
+        let cache = TokenListCache::new(&dir);
+        let server = token_list_server().await;
+        // Not in the cassette, so the download fails
+        let url = format!("{}/missing.json", server.base_url());
+
+        assert!(SolanaTokenList::fetch(&url, Some(&cache)).await.is_err());
+
+        cache.store(&cached_list(&url, 48 * HOUR, "CACHED")).await;
+        let list = SolanaTokenList::fetch(&url, Some(&cache)).await.unwrap();
+        assert_eq!(list.symbol(USDC_MINT), Some("CACHED"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_token_list_document() {
+        let list = SolanaTokenList::parse(&format!(
+            r#"{{"name": "Solana Token List", "tokens": [{{"chainId": 101, "address": "{USDC_MINT}", "symbol": "USDC", "name": "USD Coin", "decimals": 6}}]}}"#
+        ))
+        .unwrap();
+
+        assert_eq!(list.symbol(USDC_MINT), Some("USDC"));
+        assert_eq!(list.symbol("unknown"), None);
+    }
+
+    #[test]
+    fn test_parse_plain_array() {
+        let list = SolanaTokenList::parse(&format!(
+            r#"[{{"address": "{USDC_MINT}", "symbol": "USDC", "decimals": 6}}]"#
+        ))
+        .unwrap();
+
+        assert_eq!(list.symbol(USDC_MINT), Some("USDC"));
+    }
+}
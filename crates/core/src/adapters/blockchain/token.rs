@@ -1 +1,3 @@
+pub mod abi;
 pub mod spam_filter;
+pub mod token_cache;
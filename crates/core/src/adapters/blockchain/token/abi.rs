@@ -0,0 +1,98 @@
+// Just enough ABI decoding to read ERC-20 metadata from `eth_call` results
+
+pub const SYMBOL_SELECTOR: &str = "0x95d89b41";
+pub const NAME_SELECTOR: &str = "0x06fdde03";
+pub const DECIMALS_SELECTOR: &str = "0x313ce567";
+
+const WORD_HEX_LEN: usize = 64;
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn word_as_usize(word: &[u8]) -> Option<usize> {
+    let (high, low) = word.split_at(word.len() - 8);
+    if high.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    usize::try_from(u64::from_be_bytes(low.try_into().ok()?)).ok()
+}
+
+/// Decodes a `uint` return value such as `decimals()`
+pub fn decode_uint(hex: &str) -> Option<u64> {
+    let data = decode_hex(hex)?;
+    if data.len() != WORD_HEX_LEN / 2 {
+        return None;
+    }
+    word_as_usize(&data).map(|value| value as u64)
+}
+
+/// Decodes a `string` return value, falling back to the `bytes32` used by some older tokens
+/// (e.g. MKR)
+pub fn decode_string(hex: &str) -> Option<String> {
+    let data = decode_hex(hex)?;
+
+    if data.len() == 32 {
+        let end = data.iter().position(|byte| *byte == 0).unwrap_or(32);
+        return String::from_utf8(data[..end].to_vec()).ok();
+    }
+
+    // Offsets and lengths come from the contract, so they may point anywhere
+    let offset = word_as_usize(data.get(..32)?)?;
+    let start = offset.checked_add(32)?;
+    let length = word_as_usize(data.get(offset..start)?)?;
+    let bytes = data.get(start..start.checked_add(length)?)?;
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_uint() {
+        assert_eq!(
+            decode_uint("0x0000000000000000000000000000000000000000000000000000000000000012"),
+            Some(18)
+        );
+        assert_eq!(decode_uint("0x"), None);
+    }
+
+    #[test]
+    fn test_decode_string() {
+        // symbol() of USDC
+        let usdc = "0x\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            0000000000000000000000000000000000000000000000000000000000000004\
+            5553444300000000000000000000000000000000000000000000000000000000";
+        assert_eq!(decode_string(usdc), Some("USDC".to_owned()));
+    }
+
+    #[test]
+    fn test_decode_bytes32_string() {
+        // symbol() of MKR
+        let mkr = "0x4d4b520000000000000000000000000000000000000000000000000000000000";
+        assert_eq!(decode_string(mkr), Some("MKR".to_owned()));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(decode_string("0x"), None);
+        assert_eq!(decode_string("0xzz"), None);
+
+        // An offset or length of u64::MAX must not overflow
+        let max_word = format!("{:0>64}", "f".repeat(16));
+        let huge_offset = format!("0x{}{}", max_word, "0".repeat(64));
+        assert_eq!(decode_string(&huge_offset), None);
+        let huge_length = format!("0x{:064x}{}{}", 32, max_word, "0".repeat(64));
+        assert_eq!(decode_string(&huge_length), None);
+    }
+}
@@ -1,36 +1,270 @@
-pub fn check_spam(token_symbol: &str, token_name: &str) -> bool {
-    let has_visit = |s: &str| s.to_uppercase().contains("VISIT");
-    let has_access =
-        |s: &str| s.to_uppercase().contains("ACCES") || s.to_uppercase().contains("ACESS");
-
-    let has_www = |s: &str| s.to_uppercase().contains("WWW");
-    let has_com = |s: &str| s.to_uppercase().contains(".COM");
-    let has_co = |s: &str| s.to_uppercase().contains(".CO");
-    let has_net = |s: &str| s.to_uppercase().contains(".NET");
-    let has_io = |s: &str| s.to_uppercase().contains(".IO");
-    let has_eligible = |s: &str| s.to_uppercase().contains("ELIGIBLE");
-    let has_airdrop = |s: &str| s.to_uppercase().contains("AIRDROP");
-    let has_claim =
-        |s: &str| s.to_uppercase().contains("CLAIM") || s.to_uppercase().contains("СLАLМ");
-    let has_free = |s: &str| s.to_uppercase().contains("FREE");
-    let has_voucher = |s: &str| s.to_uppercase().contains("VOUCHER");
-    let has_non_ascii = |s: &str| s.chars().any(|c| !c.is_ascii());
-
-    let has_spam = |s: &str| {
-        has_visit(s)
-            || has_access(s)
-            || has_www(s)
-            || has_com(s)
-            || has_co(s)
-            || has_net(s)
-            || has_io(s)
-            || has_eligible(s)
-            || has_airdrop(s)
-            || has_claim(s)
-            || has_free(s)
-            || has_voucher(s)
-            || has_non_ascii(s)
-    };
-
-    has_spam(token_symbol) || has_spam(token_name)
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use error_stack::ResultExt;
+use regex::{Regex, RegexBuilder};
+use thiserror::Error;
+
+use crate::adapters::config::blockchain_config::{
+    SpamFilterConfig, SpamFilterMode, SpamScoringConfig,
+};
+use crate::domain::blockchain::{explorer::SpamCheck, token::ERC20TokenInfo};
+
+#[derive(Error, Debug)]
+pub enum SpamFilterError {
+    #[error("Invalid spam deny pattern '{0}'")]
+    InvalidPattern(String),
+}
+
+/// A token dropped by the filter, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilteredToken {
+    pub symbol: String,
+    pub contract_address: String,
+    pub reasons: Vec<String>,
+}
+
+/// Tokens filtered during one scan
+#[derive(Debug, Default, Clone)]
+pub struct SpamReport {
+    pub filtered: Vec<FilteredToken>,
+}
+
+/// Decides whether a discovered ERC-20 token is spam.
+///
+/// Contract allow/deny lists always win. Otherwise, in [`SpamFilterMode::Patterns`] any deny
+/// pattern match is enough, while [`SpamFilterMode::Scoring`] adds up the weights of the
+/// signals present (pattern match, no known price, received but never sent) and compares them
+/// to a threshold. The filter only holds the configuration: each run checks tokens through its
+/// own [`SpamScan`].
+#[derive(Debug)]
+pub struct SpamFilter {
+    mode: SpamFilterMode,
+    deny_patterns: Vec<Regex>,
+    allow_contracts: HashSet<String>,
+    deny_contracts: HashSet<String>,
+    scoring: SpamScoringConfig,
+}
+
+/// Tells whether a token symbol has a known price
+pub type PriceLookup<'a> = &'a (dyn Fn(&str) -> bool + Sync);
+
+/// One run of a [`SpamFilter`], recording the tokens it filters
+pub struct SpamScan<'a> {
+    filter: &'a SpamFilter,
+    /// `None` disables the "no price" signal
+    has_price: Option<PriceLookup<'a>>,
+    report: Mutex<SpamReport>,
+}
+
+impl SpamFilter {
+    pub fn from_config(config: &SpamFilterConfig) -> error_stack::Result<Self, SpamFilterError> {
+        let deny_patterns = config
+            .deny_patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .change_context_lazy(|| SpamFilterError::InvalidPattern(pattern.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let normalize = |contracts: &[Box<str>]| {
+            contracts
+                .iter()
+                .map(|contract| contract.to_lowercase())
+                .collect::<HashSet<_>>()
+        };
+
+        Ok(Self {
+            mode: config.mode,
+            deny_patterns,
+            allow_contracts: normalize(&config.allow_contracts),
+            deny_contracts: normalize(&config.deny_contracts),
+            scoring: config.scoring.clone(),
+        })
+    }
+
+    /// Starts a run, `has_price` telling which symbols are priced, if known
+    pub fn scan<'a>(&'a self, has_price: Option<PriceLookup<'a>>) -> SpamScan<'a> {
+        SpamScan {
+            filter: self,
+            has_price,
+            report: Mutex::new(SpamReport::default()),
+        }
+    }
+
+    /// Empty when the token is not spam
+    fn spam_reasons(
+        &self,
+        token: &ERC20TokenInfo,
+        received_never_sent: bool,
+        has_price: Option<PriceLookup<'_>>,
+    ) -> Vec<String> {
+        let contract = token.contract_address.to_lowercase();
+        if self.allow_contracts.contains(&contract) {
+            return Vec::new();
+        }
+        if self.deny_contracts.contains(&contract) {
+            return vec!["denylisted contract".to_owned()];
+        }
+
+        let matched_pattern = self.deny_patterns.iter().find(|pattern| {
+            pattern.is_match(&token.token_symbol) || pattern.is_match(&token.token_name)
+        });
+
+        match self.mode {
+            SpamFilterMode::Patterns => matched_pattern
+                .map(|pattern| vec![format!("matches '{}'", pattern)])
+                .unwrap_or_default(),
+            SpamFilterMode::Scoring => {
+                let has_no_price =
+                    has_price.is_some_and(|has_price| !has_price(&token.token_symbol));
+
+                let signals = [
+                    (
+                        matched_pattern.map(|pattern| format!("matches '{}'", pattern)),
+                        self.scoring.pattern_weight,
+                    ),
+                    (
+                        has_no_price.then(|| "no price available".to_owned()),
+                        self.scoring.no_price_weight,
+                    ),
+                    (
+                        received_never_sent.then(|| "received but never sent".to_owned()),
+                        self.scoring.received_never_sent_weight,
+                    ),
+                ];
+
+                let score: f64 = signals
+                    .iter()
+                    .filter(|(reason, _)| reason.is_some())
+                    .map(|(_, weight)| weight)
+                    .sum();
+
+                if score < self.scoring.threshold {
+                    return Vec::new();
+                }
+
+                signals
+                    .into_iter()
+                    .filter_map(|(reason, _)| reason)
+                    .chain(std::iter::once(format!("score {:.2}", score)))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl SpamScan<'_> {
+    /// The tokens filtered during this run
+    pub fn into_report(self) -> SpamReport {
+        self.report.into_inner().expect("Lock poisoned")
+    }
+}
+
+impl SpamCheck for SpamScan<'_> {
+    /// Checks `token` and records it in the report when it is filtered out
+    fn is_spam(&self, token: &ERC20TokenInfo, received_never_sent: bool) -> bool {
+        let reasons = self
+            .filter
+            .spam_reasons(token, received_never_sent, self.has_price);
+        if reasons.is_empty() {
+            return false;
+        }
+
+        tracing::debug!(
+            "Filtered spam token {} ({}): {:?}",
+            token.token_symbol,
+            token.contract_address,
+            reasons
+        );
+
+        self.report
+            .lock()
+            .expect("Lock poisoned")
+            .filtered
+            .push(FilteredToken {
+                symbol: token.token_symbol.to_string(),
+                contract_address: token.contract_address.to_string(),
+                reasons,
+            });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(symbol: &str, name: &str, contract: &str) -> ERC20TokenInfo {
+        ERC20TokenInfo {
+            token_name: name.into(),
+            token_symbol: symbol.into(),
+            contract_address: contract.into(),
+            token_decimal: "18".into(),
+        }
+    }
+
+    fn filter(config: SpamFilterConfig) -> SpamFilter {
+        SpamFilter::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_default_patterns() {
+        let filter = filter(SpamFilterConfig::default());
+        let scan = filter.scan(None);
+
+        assert!(scan.is_spam(&token("VISIT", "Claim rewards", "0x1"), false));
+        assert!(scan.is_spam(&token("$ETH", "eth-drop.co", "0x2"), false));
+        assert!(scan.is_spam(&token("USDC", "USD Сoin", "0x3"), false)); // Cyrillic "С"
+        assert!(!scan.is_spam(&token("USDC", "USD Coin", "0x4"), false));
+        // `.CO` only matches as a whole domain suffix
+        assert!(!scan.is_spam(&token("cbBTC", "Coinbase.Coin BTC", "0x5"), false));
+        assert!(!scan.is_spam(&token("COMP", "Compound", "0x6"), false));
+    }
+
+    #[test]
+    fn test_contract_lists() {
+        let filter = filter(SpamFilterConfig {
+            allow_contracts: vec!["0xAAA".into()],
+            deny_contracts: vec!["0xbbb".into()],
+            ..SpamFilterConfig::default()
+        });
+        let scan = filter.scan(None);
+
+        assert!(!scan.is_spam(&token("FREE", "Free airdrop", "0xaaa"), false));
+        assert!(scan.is_spam(&token("USDC", "USD Coin", "0xBBB"), false));
+    }
+
+    #[test]
+    fn test_scoring_mode() {
+        let filter = filter(SpamFilterConfig {
+            mode: SpamFilterMode::Scoring,
+            ..SpamFilterConfig::default()
+        });
+        let has_price = |symbol: &str| symbol == "USDC";
+        let scan = filter.scan(Some(&has_price));
+
+        assert!(!scan.is_spam(&token("NEW", "New Token", "0x1"), false));
+        assert!(!scan.is_spam(&token("USDC", "USD Coin", "0x2"), true));
+        assert!(scan.is_spam(&token("NEW", "New Token", "0x3"), true));
+        assert!(scan.is_spam(&token("USDC", "Visit usdc.io", "0x4"), false));
+    }
+
+    #[test]
+    fn test_report_per_scan() {
+        let filter = filter(SpamFilterConfig::default());
+        let scan = filter.scan(None);
+        scan.is_spam(&token("AIRDROP", "Airdrop", "0x1"), false);
+        scan.is_spam(&token("USDC", "USD Coin", "0x2"), false);
+
+        let report = scan.into_report();
+        assert_eq!(report.filtered.len(), 1);
+        assert_eq!(report.filtered[0].symbol, "AIRDROP");
+        assert_eq!(report.filtered[0].contract_address, "0x1");
+
+        assert!(filter.scan(None).into_report().filtered.is_empty());
+    }
 }
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::blockchain::token::ERC20TokenInfo;
+
+/// Tokens discovered so far for one address on one chain
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DiscoveredTokens {
+    /// Last block covered by the transfer history scan; the next scan starts after it
+    pub last_block: Option<u64>,
+    pub tokens: Vec<ERC20TokenInfo>,
+    /// Lower-case contracts the address has sent at least once (feeds the spam filter)
+    pub sent_contracts: HashSet<String>,
+}
+
+impl DiscoveredTokens {
+    pub fn contains(&self, contract_address: &str) -> bool {
+        self.tokens.iter().any(|token| {
+            token
+                .contract_address
+                .eq_ignore_ascii_case(contract_address)
+        })
+    }
+
+    /// Adds `token` unless its contract is already known
+    pub fn insert(&mut self, token: ERC20TokenInfo) {
+        if !self.contains(&token.contract_address) {
+            self.tokens.push(token);
+        }
+    }
+}
+
+/// JSON files under the configured cache directory, one per chain and address.
+/// The cache is best-effort: unreadable or unwritable files only cost a full rescan.
+#[derive(Debug, Clone)]
+pub struct TokenCache {
+    dir: PathBuf,
+    chain: String,
+}
+
+impl TokenCache {
+    pub fn new(dir: impl Into<PathBuf>, chain: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            chain: chain.to_lowercase(),
+        }
+    }
+
+    fn path(&self, address: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}_{}.json", self.chain, address.to_lowercase()))
+    }
+
+    pub async fn load(&self, address: &str) -> DiscoveredTokens {
+        let path = self.path(address);
+        let contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return DiscoveredTokens::default()
+            }
+            Err(error) => {
+                tracing::warn!("Failed to read token cache {}: {}", path.display(), error);
+                return DiscoveredTokens::default();
+            }
+        };
+
+        serde_json::from_str(&contents).unwrap_or_else(|error| {
+            tracing::warn!("Ignoring corrupt token cache {}: {}", path.display(), error);
+            DiscoveredTokens::default()
+        })
+    }
+
+    pub async fn store(&self, address: &str, discovered: &DiscoveredTokens) {
+        let path = self.path(address);
+        let result = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            let contents = serde_json::to_string_pretty(discovered)?;
+            tokio::fs::write(&path, contents).await
+        }
+        .await;
+
+        if let Err(error) = result {
+            tracing::warn!("Failed to write token cache {}: {}", path.display(), error);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 pub mod app_config;
 pub mod binance_config;
 pub mod blockchain_config;
+pub mod http_config;
 pub mod kraken_config;
+pub mod persistence_config;
 pub mod price_config;
+pub mod scheduler_config;
 pub mod sheets_config;
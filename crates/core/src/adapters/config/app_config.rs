@@ -7,9 +7,18 @@ use serde_path_to_error::{Deserializer as PathDeserializer, Segment, Track};
 #[derive(serde::Deserialize, Debug, Clone)]
 pub struct AppConfig {
     pub blockchain: super::blockchain_config::BlockchainConfig,
+    #[serde(default)]
     pub sheets: super::sheets_config::SpreadsheetConfig,
     pub binance: super::binance_config::BinanceConfig,
     pub kraken: super::kraken_config::KrakenConfig,
+    #[serde(default)]
+    pub http: super::http_config::HttpConfig,
+    #[serde(default)]
+    pub coingecko: super::price_config::CoingeckoConfig,
+    #[serde(default)]
+    pub persistence: super::persistence_config::PersistenceConfig,
+    #[serde(default)]
+    pub scheduler: super::scheduler_config::SchedulerConfig,
 }
 
 pub static CONFIG: LazyLock<AppConfig> = LazyLock::new(|| {
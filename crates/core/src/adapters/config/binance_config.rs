@@ -2,4 +2,11 @@
 pub struct BinanceConfig {
     pub api_key: Box<str>,
     pub secret_key: Box<str>,
+    /// Spot REST API, e.g. `https://api1.binance.com` or a local stand-in
+    #[serde(default = "default_rest_api_endpoint")]
+    pub rest_api_endpoint: Box<str>,
+}
+
+fn default_rest_api_endpoint() -> Box<str> {
+    "https://api.binance.com".into()
 }
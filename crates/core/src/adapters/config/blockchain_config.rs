@@ -1,21 +1,44 @@
+use std::collections::HashMap;
+
+use crate::adapters::config::sheets_config::HoldSheetConfig;
+use crate::domain::blockchain::token::NativeTokenSymbol;
+
 #[allow(unused)]
 #[derive(serde::Deserialize, Debug, Clone)]
 pub struct BlockchainConfig {
+    /// Etherscan V2 key, valid for every chain served by the multichain API
     pub etherscan_api_key: Box<str>,
-    pub scrollscan_api_key: Box<str>,
-    pub lineascan_api_key: Box<str>,
-    pub basescan_api_key: Box<str>,
-    pub arbiscan_api_key: Box<str>,
-    pub optimistic_etherscan_api_key: Box<str>,
-    pub polygonscan_api_key: Box<str>,
-    pub hold: HoldBlockchainConfig,
-    pub hold_sc: HoldBlockchainConfig,
+    #[serde(default = "default_chains")]
+    pub chains: Vec<ChainConfig>,
+    #[serde(default)]
+    pub spam_filter: SpamFilterConfig,
+    /// Directory where discovered token metadata is cached between runs
+    #[serde(default = "default_token_cache_dir")]
+    pub token_cache_dir: Box<str>,
+    /// Wallets tracked by the hold routine, one balance column per group on every source
+    #[serde(default)]
+    pub wallet_groups: Vec<WalletGroupConfig>,
     pub airdrops: AirdropsBlockchainConfig,
 }
 
+/// A named set of wallets, e.g. a hardware wallet and its smart contract wallets.
+/// Every wallet is optional; sources a group has no wallet on are left blank in its column.
 #[derive(serde::Deserialize, Debug, Clone)]
-pub struct HoldBlockchainConfig {
-    pub evm: EvmBlockchainConfig,
+pub struct WalletGroupConfig {
+    /// Label written above the group's balance columns
+    pub name: Box<str>,
+    #[serde(default)]
+    pub evm: Option<EvmBlockchainConfig>,
+    #[serde(default)]
+    pub solana: Option<SolanaBlockchainConfig>,
+    #[serde(default)]
+    pub cosmos: Option<CosmosBlockchainConfig>,
+    #[serde(default)]
+    pub bitcoin: Option<BitcoinWalletConfig>,
+    /// Where the group is written; defaults to `[sheets.hold]`. Groups sharing a range are laid
+    /// out side by side under each source.
+    #[serde(default)]
+    pub sheet: Option<HoldSheetConfig>,
 }
 
 #[allow(unused)]
@@ -34,19 +57,254 @@ pub struct EvmBlockchainConfig {
 #[derive(serde::Deserialize, Debug, Clone)]
 pub struct MultiEvmBlockchainConfig {
     pub addresses: Vec<Box<str>>,
+    /// Debank scraper API the portfolios are fetched from
+    #[serde(default = "default_debank_api_url")]
+    pub debank_api_url: Box<str>,
+}
+
+fn default_debank_api_url() -> Box<str> {
+    "http://localhost:8000".into()
+}
+
+/// Watch-only Bitcoin wallet, scanned through an Esplora-compatible API
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct BitcoinWalletConfig {
+    /// Account xpub/ypub/zpub or a single-key descriptor such as `wpkh([...]xpub.../<0;1>/*)`
+    pub descriptor: Box<str>,
+    #[serde(default = "default_bitcoin_gap_limit")]
+    pub gap_limit: u32,
+    #[serde(default = "default_esplora_url")]
+    pub esplora_url: Box<str>,
+}
+
+fn default_bitcoin_gap_limit() -> u32 {
+    20
+}
+
+fn default_esplora_url() -> Box<str> {
+    "https://blockstream.info/api".into()
 }
 
-#[allow(unused)]
 #[derive(serde::Deserialize, Debug, Clone)]
 pub struct SolanaBlockchainConfig {
     pub address: Box<str>,
+    #[serde(default = "default_solana_rpc_url")]
+    pub rpc_url: Box<str>,
+    /// Token list used to resolve SPL mints to symbols (solana-labs format or a plain array)
+    #[serde(default = "default_solana_token_list_url")]
+    pub token_list_url: Box<str>,
+}
+
+fn default_solana_rpc_url() -> Box<str> {
+    "https://api.mainnet-beta.solana.com".into()
+}
+
+fn default_solana_token_list_url() -> Box<str> {
+    "https://raw.githubusercontent.com/solana-labs/token-list/main/src/tokens/solana.tokenlist.json"
+        .into()
 }
 
-#[allow(unused)]
 #[derive(serde::Deserialize, Debug, Clone)]
 pub struct CosmosBlockchainConfig {
     pub cosmos_address: Box<str>,
     pub osmosis_address: Box<str>,
     pub celestia_address: Box<str>,
     pub injective_address: Box<str>,
+    #[serde(default)]
+    pub lcd: CosmosLcdConfig,
+    /// Denoms the chains have no bank metadata for, by base denom (e.g. `stuatom`); coins of
+    /// denoms found in neither are skipped
+    #[serde(default)]
+    pub denoms: HashMap<Box<str>, CosmosDenomConfig>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct CosmosDenomConfig {
+    pub symbol: Box<str>,
+    pub decimals: u32,
+}
+
+/// LCD (REST) endpoints of each Cosmos chain, defaulting to the publicnode ones
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct CosmosLcdConfig {
+    #[serde(default = "default_cosmos_lcd")]
+    pub cosmos: Box<str>,
+    #[serde(default = "default_osmosis_lcd")]
+    pub osmosis: Box<str>,
+    #[serde(default = "default_celestia_lcd")]
+    pub celestia: Box<str>,
+    #[serde(default = "default_injective_lcd")]
+    pub injective: Box<str>,
+}
+
+impl Default for CosmosLcdConfig {
+    fn default() -> Self {
+        Self {
+            cosmos: default_cosmos_lcd(),
+            osmosis: default_osmosis_lcd(),
+            celestia: default_celestia_lcd(),
+            injective: default_injective_lcd(),
+        }
+    }
+}
+
+fn default_cosmos_lcd() -> Box<str> {
+    "https://cosmos-rest.publicnode.com".into()
+}
+
+fn default_osmosis_lcd() -> Box<str> {
+    "https://osmosis-rest.publicnode.com".into()
+}
+
+fn default_celestia_lcd() -> Box<str> {
+    "https://celestia-rest.publicnode.com".into()
+}
+
+fn default_injective_lcd() -> Box<str> {
+    "https://injective-rest.publicnode.com".into()
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ChainConfig {
+    pub name: Box<str>,
+    pub native_token: NativeTokenSymbol,
+    pub explorer: ExplorerConfig,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// ERC-20 contracts always queried, even when the transfer history doesn't reveal them
+    #[serde(default)]
+    pub watchlist: Vec<Box<str>>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExplorerConfig {
+    /// Etherscan V2 multichain API. Falls back to `etherscan_api_key` when no key is given.
+    EtherscanV2 {
+        chain_id: u64,
+        #[serde(default)]
+        endpoint: Option<Box<str>>,
+        #[serde(default)]
+        api_key: Option<Box<str>>,
+    },
+    /// Any standalone Etherscan-compatible API (e.g. a V1 explorer for a chain V2 does not cover)
+    Etherscan {
+        endpoint: Box<str>,
+        api_key: Box<str>,
+    },
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Chains used when the config file does not declare `[[blockchain.chains]]`
+fn default_chains() -> Vec<ChainConfig> {
+    [
+        ("Ethereum", 1, NativeTokenSymbol::ETH, false),
+        ("Optimism", 10, NativeTokenSymbol::ETH, true),
+        ("Polygon", 137, NativeTokenSymbol::MATIC, true),
+        ("Base", 8453, NativeTokenSymbol::ETH, false),
+        ("Arbitrum", 42161, NativeTokenSymbol::ETH, true),
+        ("Linea", 59144, NativeTokenSymbol::ETH, false),
+        ("Scroll", 534352, NativeTokenSymbol::ETH, false),
+    ]
+    .into_iter()
+    .map(|(name, chain_id, native_token, enabled)| ChainConfig {
+        name: name.into(),
+        native_token,
+        explorer: ExplorerConfig::EtherscanV2 {
+            chain_id,
+            endpoint: None,
+            api_key: None,
+        },
+        enabled,
+        watchlist: Vec::new(),
+    })
+    .collect()
+}
+
+fn default_token_cache_dir() -> Box<str> {
+    "cache/tokens".into()
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct SpamFilterConfig {
+    #[serde(default)]
+    pub mode: SpamFilterMode,
+    /// Case-insensitive regexes matched against token symbols and names
+    #[serde(default = "default_spam_deny_patterns")]
+    pub deny_patterns: Vec<Box<str>>,
+    /// Contracts never treated as spam, whatever their name looks like
+    #[serde(default)]
+    pub allow_contracts: Vec<Box<str>>,
+    /// Contracts always treated as spam
+    #[serde(default)]
+    pub deny_contracts: Vec<Box<str>>,
+    #[serde(default)]
+    pub scoring: SpamScoringConfig,
+}
+
+impl Default for SpamFilterConfig {
+    fn default() -> Self {
+        Self {
+            mode: SpamFilterMode::default(),
+            deny_patterns: default_spam_deny_patterns(),
+            allow_contracts: Vec::new(),
+            deny_contracts: Vec::new(),
+            scoring: SpamScoringConfig::default(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpamFilterMode {
+    /// Any deny pattern match marks the token as spam
+    #[default]
+    Patterns,
+    /// Signals are weighted and the token is spam once the score reaches the threshold
+    Scoring,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SpamScoringConfig {
+    pub threshold: f64,
+    pub pattern_weight: f64,
+    pub no_price_weight: f64,
+    pub received_never_sent_weight: f64,
+}
+
+impl Default for SpamScoringConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            pattern_weight: 1.0,
+            no_price_weight: 0.5,
+            received_never_sent_weight: 0.5,
+        }
+    }
+}
+
+fn default_spam_deny_patterns() -> Vec<Box<str>> {
+    [
+        "VISIT",
+        "ACCES|ACESS",
+        "WWW",
+        r"\.COM\b",
+        r"\.CO\b",
+        r"\.NET\b",
+        r"\.IO\b",
+        "ELIGIBLE",
+        "AIRDROP",
+        "CLAIM",
+        "FREE",
+        "VOUCHER",
+        // Homoglyph tricks such as a Cyrillic "СLАLМ" and any other non-ASCII text
+        r"[^\x00-\x7F]",
+    ]
+    .into_iter()
+    .map(Into::into)
+    .collect()
 }
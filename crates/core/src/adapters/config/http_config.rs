@@ -0,0 +1,13 @@
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct HttpConfig {
+    /// Overrides the built-in per-host limits, e.g. for a paid API plan
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimitConfig>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct RateLimitConfig {
+    pub host: Box<str>,
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
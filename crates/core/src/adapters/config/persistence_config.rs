@@ -0,0 +1,88 @@
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct PersistenceConfig {
+    /// Where token lists and exchange balances are kept
+    #[serde(default)]
+    pub backend: PersistenceBackend,
+    #[serde(default)]
+    pub file: FilePersistenceConfig,
+    /// History kept in addition to the overwritten balances
+    #[serde(default)]
+    pub snapshots: SnapshotsConfig,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct SnapshotsConfig {
+    /// SQLite database every run's balances are appended to; no history is kept when omitted
+    #[serde(default)]
+    pub sqlite: Option<Box<str>>,
+    /// History tab of the spreadsheet every run's balances are appended to
+    #[serde(default)]
+    pub sheet: Option<HistorySheetConfig>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct HistorySheetConfig {
+    /// Columns rows are appended to: timestamp, source, account, asset, amount and USD value
+    #[serde(default = "default_history_range")]
+    pub range: Box<str>,
+}
+
+impl Default for HistorySheetConfig {
+    fn default() -> Self {
+        Self {
+            range: default_history_range(),
+        }
+    }
+}
+
+fn default_history_range() -> Box<str> {
+    "'History'!A:F".into()
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistenceBackend {
+    /// The named ranges of the configured Google spreadsheet
+    #[default]
+    Sheets,
+    /// Plain files under `[persistence.file].dir`, no Google account needed
+    File,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct FilePersistenceConfig {
+    #[serde(default = "default_file_dir")]
+    pub dir: Box<str>,
+    #[serde(default)]
+    pub format: FileFormat,
+}
+
+impl Default for FilePersistenceConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_file_dir(),
+            format: FileFormat::default(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl FileFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Csv => "csv",
+            FileFormat::Json => "json",
+        }
+    }
+}
+
+fn default_file_dir() -> Box<str> {
+    "data".into()
+}
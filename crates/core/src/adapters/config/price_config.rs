@@ -1,2 +1,18 @@
 #[derive(serde::Deserialize, Debug, Clone)]
-pub struct CoingeckoConfig {}
+pub struct CoingeckoConfig {
+    /// Public API, or e.g. `https://pro-api.coingecko.com` or a local stand-in
+    #[serde(default = "default_base_url")]
+    pub base_url: Box<str>,
+}
+
+impl Default for CoingeckoConfig {
+    fn default() -> Self {
+        Self {
+            base_url: default_base_url(),
+        }
+    }
+}
+
+fn default_base_url() -> Box<str> {
+    "https://api.coingecko.com".into()
+}
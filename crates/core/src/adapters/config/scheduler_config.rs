@@ -0,0 +1,34 @@
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Where the time each routine last started is kept across restarts
+    #[serde(default = "default_state_file")]
+    pub state_file: Box<str>,
+    /// Routines run by `serve`; routines without an entry are not scheduled
+    #[serde(default)]
+    pub routines: Vec<RoutineScheduleConfig>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            state_file: default_state_file(),
+            routines: Vec::new(),
+        }
+    }
+}
+
+/// When one routine runs: either every `interval_secs`, or on a `cron` expression
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct RoutineScheduleConfig {
+    /// Name of the routine, as shown by `list`
+    pub routine: Box<str>,
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// In UTC, e.g. `0 */6 * * *`; a leading seconds field is optional
+    #[serde(default)]
+    pub cron: Option<Box<str>>,
+}
+
+fn default_state_file() -> Box<str> {
+    "scheduler_state.json".into()
+}
@@ -1,5 +1,185 @@
-#[derive(serde::Deserialize, Debug, Clone)]
+use std::collections::HashMap;
+
+use crate::domain::sheets::ranges;
+
+/// Optional when `[persistence]` uses files and no routine writes to the spreadsheet
+#[derive(serde::Deserialize, Debug, Clone, Default)]
 pub struct SpreadsheetConfig {
+    /// Service account key file, used when `auth` is `service_account_file`
+    #[serde(default)]
     pub priv_key: Box<str>, // https://console.cloud.google.com/iam-admin/serviceaccounts/details/106085307439944090164;edit=true/keys?project=cryptosheets-355223
     pub spreadsheet_id: Box<str>,
+    #[serde(default)]
+    pub hold: HoldSheetConfig,
+    #[serde(default)]
+    pub dry_run: DryRunConfig,
+    #[serde(default)]
+    pub overflow: OverflowConfig,
+    #[serde(default)]
+    pub metadata_cache: MetadataCacheConfig,
+    #[serde(default)]
+    pub auth: SheetsAuthConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+}
+
+impl SpreadsheetConfig {
+    /// Whether credentials are configured at all; without them the spreadsheet routines are
+    /// skipped rather than failing
+    pub fn has_credentials(&self) -> bool {
+        match self.auth {
+            SheetsAuthConfig::ServiceAccountFile => !self.priv_key.is_empty(),
+            SheetsAuthConfig::ServiceAccountEnv { .. } | SheetsAuthConfig::InstalledApp { .. } => {
+                true
+            }
+        }
+    }
+}
+
+/// How failed Sheets API calls are retried: quota errors (429), server errors (5xx) and dropped
+/// connections back off exponentially, with jitter, up to `max_attempts`
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct RetryConfig {
+    /// Attempts per call, the first one included
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_max_backoff_ms() -> u64 {
+    32000
+}
+
+/// How the spreadsheet is authenticated against
+#[derive(serde::Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SheetsAuthConfig {
+    /// Service account key read from the file at `priv_key`
+    #[default]
+    ServiceAccountFile,
+    /// Service account key JSON held in an environment variable, e.g. a deployment secret
+    ServiceAccountEnv {
+        #[serde(default = "default_service_account_var")]
+        var: Box<str>,
+    },
+    /// A Google account of one's own, approved once in the browser. The OAuth client secret
+    /// is a "Desktop app" client downloaded from the Cloud console; tokens are kept in
+    /// `token_file` between runs.
+    InstalledApp {
+        client_secret: Box<str>,
+        #[serde(default = "default_token_file")]
+        token_file: Box<str>,
+    },
+}
+
+fn default_service_account_var() -> Box<str> {
+    "GOOGLE_SERVICE_ACCOUNT_KEY".into()
+}
+
+fn default_token_file() -> Box<str> {
+    "cache/sheets_token.json".into()
+}
+
+/// How long the sheets and named ranges of the spreadsheet are cached. A lookup that misses
+/// refreshes them right away regardless.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct MetadataCacheConfig {
+    #[serde(default = "default_metadata_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+impl Default for MetadataCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_metadata_ttl_secs(),
+        }
+    }
+}
+
+fn default_metadata_ttl_secs() -> u64 {
+    300
+}
+
+/// What to do when data has more rows than the named range it is written to
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct OverflowConfig {
+    #[serde(default)]
+    pub default: OverflowPolicy,
+    /// Policy of specific named ranges, overriding `default`
+    #[serde(default)]
+    pub ranges: HashMap<Box<str>, OverflowPolicy>,
+}
+
+impl OverflowConfig {
+    pub fn policy_for(&self, range: &str) -> OverflowPolicy {
+        self.ranges.get(range).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Refuse the write, leaving the range as it was
+    #[default]
+    Fail,
+    /// Write the rows that fit, the last one replaced by a "+N more" row
+    Truncate,
+    /// Grow the named range downwards until every row fits. Cells below it are overwritten.
+    Extend,
+}
+
+/// Records the writes routines would make instead of sending them to the spreadsheet
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct DryRunConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// JSON file the recorded writes are exported to; printed to stdout when omitted
+    #[serde(default)]
+    pub output: Option<Box<str>>,
+}
+
+/// Where the hold routine writes the balances of a wallet group
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct HoldSheetConfig {
+    /// Named range covering the whole hold table: a title row, a wallet label row, then one row
+    /// per token
+    #[serde(default = "default_hold_range")]
+    pub range: Box<str>,
+    /// Overrides the sheet the named range belongs to
+    #[serde(default)]
+    pub sheet_title: Option<Box<str>>,
+}
+
+impl Default for HoldSheetConfig {
+    fn default() -> Self {
+        Self {
+            range: default_hold_range(),
+            sheet_title: None,
+        }
+    }
+}
+
+fn default_hold_range() -> Box<str> {
+    ranges::balances::hold::RW_DATA.into()
 }
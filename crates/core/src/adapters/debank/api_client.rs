@@ -1,12 +1,15 @@
 use std::time::Duration;
 
 use error_stack::ResultExt;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{event, instrument, Level};
 
+use crate::adapters::http::{send_rate_limited, HttpError};
 use crate::domain::debank::{Chain, DebankResponse};
+use crate::ports::debank_portfolio_source::{DebankPortfolioSource, DebankPortfolioSourceError};
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 const JOB_POLL_INTERVAL: Duration = Duration::from_secs(5);
@@ -265,6 +268,7 @@ impl From<SpotTokenInfo> for crate::domain::debank::SpotTokenInfo {
 pub struct DebankApiClient {
     client: Client,
     base_url: String,
+    poll_interval: Duration,
 }
 
 impl DebankApiClient {
@@ -274,7 +278,17 @@ impl DebankApiClient {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            poll_interval: JOB_POLL_INTERVAL,
+        }
+    }
+
+    /// How long to wait between checks of a scrape job's status
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
     }
 
     #[instrument(skip(self))]
@@ -313,35 +327,8 @@ impl DebankApiClient {
     ) -> error_stack::Result<ScrapeResponse, ApiClientError> {
         let url = format!("{}/api/scrape", self.base_url);
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .send()
+        self.send(|| self.client.post(&url).json(&request), &url)
             .await
-            .change_context(ApiClientError::HttpError)?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(ApiClientError::HttpStatusError(format!(
-                "HTTP error {}: {}",
-                status, error_text
-            ))
-            .into());
-        }
-
-        let text = response.text().await.unwrap();
-
-        tracing::debug!(response = text, "Response OK 200");
-
-        let scrape_response: ScrapeResponse =
-            serde_json::from_str(text.as_str()).change_context(ApiClientError::JsonError)?;
-
-        Ok(scrape_response)
     }
 
     #[instrument(skip(self))]
@@ -377,7 +364,7 @@ impl DebankApiClient {
                     }
 
                     // Wait before polling again
-                    tokio::time::sleep(JOB_POLL_INTERVAL).await;
+                    tokio::time::sleep(self.poll_interval).await;
                 }
             }
         }
@@ -390,29 +377,7 @@ impl DebankApiClient {
     ) -> error_stack::Result<JobStatusResponse, ApiClientError> {
         let url = format!("{}/api/jobs/{}", self.base_url, job_id);
 
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .change_context(ApiClientError::HttpError)?;
-
-        if !response.status().is_success() {
-            return Err(ApiClientError::HttpStatusError(format!(
-                "HTTP error {}",
-                response.status()
-            ))
-            .into());
-        }
-
-        let text = response.text().await.unwrap();
-
-        tracing::debug!(response = text, "Response OK 200");
-
-        let status_response: JobStatusResponse =
-            serde_json::from_str(text.as_str()).change_context(ApiClientError::JsonError)?;
-
-        Ok(status_response)
+        self.send(|| self.client.get(&url), &url).await
     }
 
     #[instrument(skip(self))]
@@ -422,26 +387,91 @@ impl DebankApiClient {
     ) -> error_stack::Result<JobResultResponse, ApiClientError> {
         let url = format!("{}/api/results/{}", self.base_url, job_id);
 
-        let response = self
-            .client
-            .get(&url)
-            .send()
+        self.send(|| self.client.get(&url), &url).await
+    }
+
+    /// Sends through the shared rate limiter and parses the JSON body
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+        url: &str,
+    ) -> error_stack::Result<T, ApiClientError> {
+        let text = send_rate_limited(request, url, None)
             .await
-            .change_context(ApiClientError::HttpError)?;
-
-        if !response.status().is_success() {
-            return Err(ApiClientError::HttpStatusError(format!(
-                "HTTP error {}",
-                response.status()
-            ))
-            .into());
-        }
+            .map_err(|error| {
+                let context = match HttpError::status(&error) {
+                    Some(status) => {
+                        ApiClientError::HttpStatusError(format!("HTTP error {}", status))
+                    }
+                    None => ApiClientError::HttpError,
+                };
+                error.change_context(context)
+            })?;
 
-        let result_response: JobResultResponse = response
-            .json()
+        tracing::debug!(response = text, "Response OK 200");
+
+        serde_json::from_str(&text)
+            .change_context(ApiClientError::JsonError)
+            .attach_printable_lazy(|| format!("Response: {}", text))
+    }
+}
+
+#[async_trait::async_trait]
+impl DebankPortfolioSource for DebankApiClient {
+    async fn portfolio(
+        &self,
+        wallet_address: &str,
+    ) -> error_stack::Result<DebankResponse, DebankPortfolioSourceError> {
+        let scrape_request = ScrapeRequest {
+            wallet_address: wallet_address.to_string(),
+            chain: None, // No chain filter by default
+            save_html: false,
+            save_screenshot: false,
+            headless: true,
+        };
+
+        self.scrape_wallet(scrape_request)
             .await
-            .change_context(ApiClientError::JsonError)?;
+            .change_context(DebankPortfolioSourceError::FetchPortfolioError)
+    }
+}
 
-        Ok(result_response)
+#[cfg(test)]
+mod tests {
+    use crate::adapters::http::fixture_server::FixtureServer;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_portfolio_waits_for_the_scrape_job() {
+        let server = FixtureServer::start("debank_scrape", "http://localhost:8000").await;
+        let client =
+            DebankApiClient::new(server.base_url().to_string()).with_poll_interval(Duration::ZERO);
+
+        let portfolio = client
+            .portfolio("0x5a52e96bacdabb82fd05763e25335261b270efcb")
+            .await
+            .unwrap();
+
+        assert_eq!(portfolio.total_usd_value, "$12,345");
+        assert_eq!(
+            portfolio
+                .chains
+                .iter()
+                .map(|chain| chain.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Arbitrum", "Ethereum"]
+        );
+
+        let wallet = portfolio.chains[0].wallet_info.as_ref().unwrap();
+        assert_eq!(wallet.tokens[0].name, "ETH");
+        assert_eq!(wallet.tokens[0].amount, "1.5");
+
+        let tracking = &portfolio.chains[1].project_info[0].trackings[0];
+        assert_eq!(tracking.tracking_type, "Lending");
+        assert_eq!(
+            tracking.token_sections[0].tokens[0].balance.as_deref(),
+            Some("7,833")
+        );
     }
 }
@@ -16,7 +16,7 @@ impl BinanceAccountFactory {
             Some(self.binance_config.api_key.to_string()),
             Some(self.binance_config.secret_key.to_string()),
             &Config {
-                rest_api_endpoint: "https://api.binance.com".into(),
+                rest_api_endpoint: self.binance_config.rest_api_endpoint.to_string(),
                 ws_endpoint: "wss://stream.binance.com:9443".into(),
 
                 futures_rest_api_endpoint: "https://fapi.binance.com".into(),
@@ -6,6 +6,7 @@ use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
 use crate::adapters::sheets::spreadsheet_read::SpreadsheetRead;
 use crate::adapters::sheets::spreadsheet_write::SpreadsheetWrite;
 use crate::domain::exchange::{BalanceRepository, BalanceRepositoryError, BalanceUpdateTarget};
+use crate::domain::sheets::cell_value::CellValue;
 use crate::domain::sheets::ranges;
 
 pub struct SpreadsheetBalanceRepository {
@@ -34,13 +35,13 @@ impl BalanceRepository for SpreadsheetBalanceRepository {
         target: BalanceUpdateTarget,
         balances: &[f64],
     ) -> error_stack::Result<(), BalanceRepositoryError> {
-        let balances_str = balances
+        let balances = balances
             .iter()
-            .map(|x| format!("${}", x))
+            .map(|balance| CellValue::Number(*balance))
             .collect::<Vec<_>>();
 
         self.spreadsheet_manager
-            .write_named_column(target.range(), &balances_str)
+            .write_named_typed_column(target.range(), &balances)
             .await
             .change_context(BalanceRepositoryError::UpdateBalancesError)
     }
@@ -0,0 +1 @@
+pub mod spreadsheet_hold_balance_repository;
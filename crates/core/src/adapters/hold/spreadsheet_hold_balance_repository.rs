@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use error_stack::ResultExt;
+
+use crate::adapters::sheets::cell_range::CellRange;
+use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
+use crate::adapters::sheets::spreadsheet_read::SpreadsheetRead;
+use crate::adapters::sheets::spreadsheet_write::SpreadsheetWrite;
+use crate::adapters::sheets::spreadsheet_write_batch::SpreadsheetWriteBatch;
+use crate::domain::sheets::a1_notation::ToA1Notation;
+use crate::domain::sheets::cell_position::CellPosition;
+use crate::domain::sheets::cell_value::CellValue;
+use crate::domain::sheets::column::Column;
+use crate::domain::sheets::ranges;
+use crate::domain::sheets::row::Row;
+use crate::ports::hold_balance_repository::{
+    HoldBalanceRepository, HoldBalanceRepositoryError, HoldBalanceTable, HoldBalanceTarget,
+};
+
+/// Writes hold tables: for each source, its title on the first row of the target range,
+/// the wallet group labels on the second row, and one balance column per group below them.
+/// Every table of a run is sent in a single batch, so they are either all updated or all left
+/// as they were.
+pub struct SpreadsheetHoldBalanceRepository {
+    pub spreadsheet_manager: Arc<SpreadsheetManager>,
+}
+
+impl SpreadsheetHoldBalanceRepository {
+    pub fn new(spreadsheet_manager: Arc<SpreadsheetManager>) -> Self {
+        Self {
+            spreadsheet_manager,
+        }
+    }
+
+    async fn data_range(
+        &self,
+        target: &HoldBalanceTarget,
+    ) -> error_stack::Result<CellRange, HoldBalanceRepositoryError> {
+        let grid_range = self
+            .spreadsheet_manager
+            .get_named_range(&target.range)
+            .await
+            .change_context(HoldBalanceRepositoryError::UpdateBalancesError)?;
+
+        let cell_range = CellRange::try_from_grid_range_with_sheet_manager(
+            grid_range,
+            &self.spreadsheet_manager,
+        )
+        .await
+        .change_context(HoldBalanceRepositoryError::UpdateBalancesError)
+        .attach_printable_lazy(|| format!("Failed to parse named range '{}'", target.range))?;
+
+        Ok(match &target.sheet_title {
+            Some(sheet_title) => cell_range.with_sheet_title(sheet_title.to_string()),
+            None => cell_range,
+        })
+    }
+
+    /// Adds the writes of `table` to `batch`
+    async fn write_table(
+        &self,
+        batch: &SpreadsheetWriteBatch<'_>,
+        table: &HoldBalanceTable,
+    ) -> error_stack::Result<(), HoldBalanceRepositoryError> {
+        let data_range = self.data_range(&table.target).await?;
+        let sheet_title = data_range.sheet_title.as_deref();
+
+        let mut source_title_cell = data_range.start;
+        for source in &table.sources {
+            Self::write_cell(batch, &source_title_cell, sheet_title, &source.title).await?;
+
+            let mut label_cell = source_title_cell + Row::from_index(1);
+            for column in &source.columns {
+                Self::write_cell(batch, &label_cell, sheet_title, &column.label).await?;
+
+                let balances_range = CellRange {
+                    start: label_cell + Row::from_index(1),
+                    end: CellPosition {
+                        col: label_cell.col,
+                        row: data_range.end.row,
+                    },
+                    sheet_title: data_range.sheet_title.clone(),
+                };
+
+                let balances = column
+                    .balances
+                    .iter()
+                    .map(|balance| balance.map_or(CellValue::Empty, CellValue::Number))
+                    .collect::<Vec<_>>();
+
+                batch
+                    .write_typed_column(&balances_range, &balances)
+                    .await
+                    .change_context(HoldBalanceRepositoryError::UpdateBalancesError)
+                    .attach_printable_lazy(|| {
+                        format!(
+                            "Failed to write '{}' balances of '{}'",
+                            column.label, source.title
+                        )
+                    })?;
+
+                label_cell = label_cell + Column::from_index(1);
+            }
+
+            source_title_cell = source_title_cell + Column::from_index(source.columns.len() as u32);
+        }
+
+        Ok(())
+    }
+
+    async fn write_cell(
+        batch: &SpreadsheetWriteBatch<'_>,
+        position: &CellPosition,
+        sheet_title: Option<&str>,
+        value: &str,
+    ) -> error_stack::Result<(), HoldBalanceRepositoryError> {
+        batch
+            .write_value(&position.to_a1_notation(sheet_title), value)
+            .await
+            .change_context(HoldBalanceRepositoryError::UpdateBalancesError)
+            .attach_printable_lazy(|| format!("Failed to write '{}'", value))
+    }
+}
+
+#[async_trait::async_trait]
+impl HoldBalanceRepository for SpreadsheetHoldBalanceRepository {
+    async fn get_token_names(
+        &self,
+    ) -> error_stack::Result<Vec<String>, HoldBalanceRepositoryError> {
+        self.spreadsheet_manager
+            .read_named_range(ranges::tokens::RO_NAMES)
+            .await
+            .change_context(HoldBalanceRepositoryError::FetchTokenNamesError)
+    }
+
+    async fn update_hold_balances(
+        &self,
+        tables: &[HoldBalanceTable],
+    ) -> error_stack::Result<(), HoldBalanceRepositoryError> {
+        let batch = SpreadsheetWriteBatch::new(&self.spreadsheet_manager);
+        for table in tables {
+            self.write_table(&batch, table)
+                .await
+                .attach_printable_lazy(|| {
+                    format!("Failed to update hold balances in '{}'", table.target.range)
+                })?;
+        }
+
+        batch
+            .flush()
+            .await
+            .change_context(HoldBalanceRepositoryError::UpdateBalancesError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::adapters::config::sheets_config::SpreadsheetConfig;
+    use crate::adapters::sheets::backend::in_memory::InMemorySheetsBackend;
+    use crate::domain::sheets::ranges::DEFAULT_LAYOUT;
+    use crate::ports::hold_balance_repository::{HoldBalanceColumn, HoldBalanceSource};
+
+    fn table(range: &str) -> HoldBalanceTable {
+        HoldBalanceTable {
+            target: HoldBalanceTarget {
+                range: range.to_owned(),
+                sheet_title: None,
+            },
+            sources: vec![HoldBalanceSource {
+                title: "Bitcoin".to_owned(),
+                columns: vec![HoldBalanceColumn {
+                    label: "Cold".to_owned(),
+                    balances: vec![Some(0.5), None],
+                }],
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tables_are_written_together() {
+        let backend = Arc::new(
+            InMemorySheetsBackend::from_layout(DEFAULT_LAYOUT)
+                .with_named_range("Balance_Cold__mData", "'Cold'!A1:B4"),
+        );
+        let repository = SpreadsheetHoldBalanceRepository::new(Arc::new(
+            backend.spreadsheet_manager(SpreadsheetConfig::default()),
+        ));
+        let hold = table(ranges::balances::hold::RW_DATA);
+
+        // The second target does not exist, so the first one is not written either
+        let result = repository
+            .update_hold_balances(&[hold.clone(), table("Balance_Missing__mData")])
+            .await;
+        assert!(result.is_err());
+        assert!(backend.values("'Hold'!A1:Z202").is_empty());
+
+        repository
+            .update_hold_balances(&[hold, table("Balance_Cold__mData")])
+            .await
+            .unwrap();
+        let written = vec![
+            vec![json!("Bitcoin")],
+            vec![json!("Cold")],
+            vec![json!(0.5)],
+        ];
+        assert_eq!(backend.values("'Hold'!A1:Z202"), written);
+        assert_eq!(backend.values("'Cold'!A1:B4"), written);
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+pub mod fixture_server;
+pub mod rate_limiter;
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use error_stack::{report, ResultExt};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use thiserror::Error;
+
+use rate_limiter::RATE_LIMITERS;
+
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+const INITIAL_RATE_LIMIT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Client shared by adapters that don't need their own configuration
+pub static HTTP_CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpError {
+    #[error("HTTP request failed")]
+    RequestError,
+    #[error("HTTP status {0}")]
+    StatusError(u16),
+}
+
+impl HttpError {
+    /// Status the server answered a failed request with, found anywhere in `report` so it
+    /// survives `change_context`
+    pub fn status<C>(report: &error_stack::Report<C>) -> Option<u16> {
+        match report.downcast_ref::<HttpError>()? {
+            HttpError::StatusError(status) => Some(*status),
+            HttpError::RequestError => None,
+        }
+    }
+}
+
+/// Etherscan-family APIs answer `200 OK` with this message when throttling
+fn is_rate_limited(status: StatusCode, body: &str) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || body.contains("Max rate limit reached")
+}
+
+/// Sends the request built by `request` once the bucket for `url`'s host (and `api_key`)
+/// allows it, and returns the body of a successful response. Rate-limited responses are
+/// retried with exponential backoff. Any other status, or a 429 the provider keeps answering,
+/// fails with [`HttpError::StatusError`], the body attached.
+pub async fn send_rate_limited(
+    request: impl Fn() -> RequestBuilder,
+    url: &str,
+    api_key: Option<&str>,
+) -> error_stack::Result<String, HttpError> {
+    let bucket = RATE_LIMITERS.bucket(url, api_key);
+    let mut backoff = INITIAL_RATE_LIMIT_BACKOFF;
+    let mut attempt = 0;
+
+    loop {
+        bucket.acquire().await;
+        let response = request()
+            .send()
+            .await
+            .change_context(HttpError::RequestError)?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .change_context(HttpError::RequestError)?;
+
+        if !is_rate_limited(status, &body) || attempt == MAX_RATE_LIMIT_RETRIES {
+            if !status.is_success() {
+                return Err(report!(HttpError::StatusError(status.as_u16())))
+                    .attach_printable(format!("Response: {}", body));
+            }
+            return Ok(body);
+        }
+
+        attempt += 1;
+        tracing::warn!(
+            "Rate limited by {}, retrying in {:?} ({}/{})",
+            reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_owned))
+                .unwrap_or_default(),
+            backoff,
+            attempt,
+            MAX_RATE_LIMIT_RETRIES
+        );
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::blockchain::explorer::FetchBalanceError;
+
+    use super::fixture_server::FixtureServer;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_statuses_are_errors() {
+        let server = FixtureServer::start("esplora_wallet", "https://blockstream.info/api").await;
+        let url = format!("{}/address/unknown", server.base_url());
+
+        let error = send_rate_limited(|| HTTP_CLIENT.get(&url), &url, None)
+            .await
+            .unwrap_err();
+        assert_eq!(error.current_context(), &HttpError::StatusError(404));
+
+        // Still visible once the caller changed the context
+        let error = error.change_context(FetchBalanceError::ApiRequestError);
+        assert_eq!(HttpError::status(&error), Some(404));
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Set to record the cassettes again from the real services instead of replaying them
+const RECORD_ENV_VAR: &str = "RECORD_FIXTURES";
+
+/// Query parameters that change on every request or carry credentials: never recorded, and
+/// ignored when matching
+const VOLATILE_PARAMS: &[&str] = &["apikey", "signature", "timestamp", "recvWindow"];
+
+/// Request headers forwarded to the real service when recording
+const FORWARDED_HEADERS: &[&str] = &["content-type", "x-mbx-apikey", "api-key", "api-sign"];
+
+/// One recorded request and the response served back for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub path: String,
+    /// Parameters the request must carry; any other parameter is ignored
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    query: BTreeMap<String, String>,
+    headers: Vec<(String, String)>,
+    raw_target: String,
+    body: Vec<u8>,
+}
+
+impl Interaction {
+    fn matches(&self, request: &Request) -> bool {
+        self.method == request.method
+            && self.path == request.path
+            && self
+                .query
+                .iter()
+                .all(|(key, value)| request.query.get(key) == Some(value))
+    }
+}
+
+#[derive(Debug)]
+enum Mode {
+    /// Answers from the cassette; how many times each interaction was served
+    Replay(Vec<usize>),
+    /// Forwards to this URL and appends what it answers to the cassette
+    Record(String),
+}
+
+#[derive(Debug)]
+struct Cassette {
+    path: PathBuf,
+    interactions: Vec<Interaction>,
+    mode: Mode,
+}
+
+impl Cassette {
+    /// The first matching interaction not served yet, or the last matching one once they all
+    /// were, so a polled endpoint can answer "pending" then "completed"
+    fn replay(&mut self, request: &Request) -> Option<Interaction> {
+        let Mode::Replay(served) = &mut self.mode else {
+            return None;
+        };
+
+        let matching = (0..self.interactions.len())
+            .filter(|index| self.interactions[*index].matches(request))
+            .collect::<Vec<_>>();
+        let index = matching
+            .iter()
+            .copied()
+            .find(|index| served[*index] == 0)
+            .or(matching.last().copied())?;
+
+        served[index] += 1;
+        Some(self.interactions[index].clone())
+    }
+}
+
+/// Local HTTP server standing in for an external API in adapter tests. It replays the
+/// interactions of a cassette (`fixtures/http/<name>.json`), or records them from the real
+/// service when `RECORD_FIXTURES` is set, saving the cassette once the server is dropped.
+///
+/// Point the adapter's base URL at [`FixtureServer::base_url`].
+#[derive(Debug)]
+pub struct FixtureServer {
+    base_url: String,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl FixtureServer {
+    /// Serves the cassette `name`, recorded from `upstream` (e.g. `https://api.binance.com`)
+    pub async fn start(name: &str, upstream: &str) -> Self {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/http")
+            .join(format!("{}.json", name));
+
+        let cassette = if std::env::var_os(RECORD_ENV_VAR).is_some() {
+            Cassette {
+                path,
+                interactions: Vec::new(),
+                mode: Mode::Record(upstream.trim_end_matches('/').to_owned()),
+            }
+        } else {
+            let content = std::fs::read_to_string(&path)
+                .unwrap_or_else(|error| panic!("Missing cassette {}: {}", path.display(), error));
+            let interactions: Vec<Interaction> = serde_json::from_str(&content)
+                .unwrap_or_else(|error| panic!("Invalid cassette {}: {}", path.display(), error));
+            Cassette {
+                path,
+                mode: Mode::Replay(vec![0; interactions.len()]),
+                interactions,
+            }
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let cassette = Arc::new(Mutex::new(cassette));
+
+        let served = Arc::clone(&cassette);
+        tokio::spawn(async move {
+            loop {
+                let Ok((socket, _)) = listener.accept().await else {
+                    return;
+                };
+                tokio::spawn(serve(socket, Arc::clone(&served)));
+            }
+        });
+
+        Self { base_url, cassette }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+/// Credential read from `var` when recording, where the real service checks it, or a
+/// placeholder when replaying
+pub fn credential(var: &str) -> Box<str> {
+    match std::env::var_os(RECORD_ENV_VAR) {
+        Some(_) => std::env::var(var)
+            .unwrap_or_else(|_| panic!("{} must be set to record fixtures", var))
+            .into(),
+        None => "fixture".into(),
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        let Ok(cassette) = self.cassette.lock() else {
+            return;
+        };
+        if let Mode::Record(_) = cassette.mode {
+            let content = serde_json::to_string_pretty(&cassette.interactions).unwrap();
+            std::fs::write(&cassette.path, content + "\n").unwrap();
+        }
+    }
+}
+
+async fn serve(mut socket: TcpStream, cassette: Arc<Mutex<Cassette>>) {
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+
+    let upstream = match &cassette.lock().unwrap().mode {
+        Mode::Record(upstream) => Some(upstream.clone()),
+        Mode::Replay(_) => None,
+    };
+    let (status, body) = match upstream {
+        Some(upstream) => {
+            let interaction = forward(&upstream, &request).await;
+            let response = (interaction.status, interaction.body.to_string());
+            cassette.lock().unwrap().interactions.push(interaction);
+            response
+        }
+        None => match cassette.lock().unwrap().replay(&request) {
+            Some(interaction) => (interaction.status, interaction.body.to_string()),
+            None => {
+                eprintln!(
+                    "No recorded interaction for {} {}",
+                    request.method, request.raw_target
+                );
+                (
+                    404,
+                    serde_json::json!({ "error": "no recorded interaction" }).to_string(),
+                )
+            }
+        },
+    };
+
+    let response = format!(
+        "HTTP/1.1 {} Fixture\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+}
+
+/// Reads one HTTP/1.1 request, its body included when it has a `Content-Length`
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_owned();
+    let raw_target = request_line.next()?.to_owned();
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_owned()))
+        .collect::<Vec<_>>();
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + content_length {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let (path, query) = raw_target.split_once('?').unwrap_or((&raw_target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(key, _)| !VOLATILE_PARAMS.contains(key))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+
+    Some(Request {
+        method,
+        path: path.to_owned(),
+        query,
+        headers,
+        body: buffer[header_end..].to_vec(),
+        raw_target,
+    })
+}
+
+/// Sends `request` to the real service and records its answer
+async fn forward(upstream: &str, request: &Request) -> Interaction {
+    let method = reqwest::Method::from_bytes(request.method.as_bytes()).unwrap();
+    let mut builder = super::HTTP_CLIENT
+        .request(method, format!("{}{}", upstream, request.raw_target))
+        .body(request.body.clone());
+    for (name, value) in &request.headers {
+        if FORWARDED_HEADERS.contains(&name.as_str()) {
+            builder = builder.header(name, value);
+        }
+    }
+
+    let response = builder.send().await.unwrap();
+    let status = response.status().as_u16();
+    let text = response.text().await.unwrap();
+
+    Interaction {
+        method: request.method.clone(),
+        path: request.path.clone(),
+        query: request.query.clone(),
+        status,
+        body: serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, target: &str) -> Request {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        Request {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            query: query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+            headers: Vec::new(),
+            raw_target: target.to_owned(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_replays_matching_interactions_in_order() {
+        let interaction =
+            |path: &str, query: &[(&str, &str)], body: serde_json::Value| Interaction {
+                method: "GET".to_owned(),
+                path: path.to_owned(),
+                query: query
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+                status: 200,
+                body,
+            };
+        let interactions = vec![
+            interaction("/api", &[("action", "balance")], serde_json::json!(1)),
+            interaction("/jobs/1", &[], serde_json::json!("pending")),
+            interaction("/jobs/1", &[], serde_json::json!("completed")),
+        ];
+        let mut cassette = Cassette {
+            path: PathBuf::new(),
+            mode: Mode::Replay(vec![0; interactions.len()]),
+            interactions,
+        };
+        let mut replay = |method: &str, target: &str| {
+            cassette
+                .replay(&request(method, target))
+                .map(|interaction| interaction.body)
+        };
+
+        assert_eq!(
+            replay("GET", "/api?action=balance&address=0x1"),
+            Some(serde_json::json!(1))
+        );
+        assert_eq!(replay("GET", "/api?action=tokentx"), None);
+        assert_eq!(replay("POST", "/api?action=balance"), None);
+
+        assert_eq!(replay("GET", "/jobs/1"), Some(serde_json::json!("pending")));
+        assert_eq!(
+            replay("GET", "/jobs/1"),
+            Some(serde_json::json!("completed"))
+        );
+        assert_eq!(
+            replay("GET", "/jobs/1"),
+            Some(serde_json::json!("completed"))
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::adapters::config::http_config::HttpConfig;
+
+/// Sustained rate and burst size allowed by a provider
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+const DEFAULT_RATE_LIMIT: RateLimit = RateLimit {
+    requests_per_second: 5.0,
+    burst: 5,
+};
+
+/// Published free-tier limits of the providers we call
+const KNOWN_RATE_LIMITS: &[(&str, RateLimit)] = &[
+    (
+        "api.etherscan.io",
+        RateLimit {
+            requests_per_second: 5.0,
+            burst: 5,
+        },
+    ),
+    (
+        "api.coingecko.com",
+        RateLimit {
+            requests_per_second: 0.5,
+            burst: 3,
+        },
+    ),
+    (
+        "api.mainnet-beta.solana.com",
+        RateLimit {
+            requests_per_second: 4.0,
+            burst: 10,
+        },
+    ),
+    (
+        // 60 requests per minute per user
+        "sheets.googleapis.com",
+        RateLimit {
+            requests_per_second: 1.0,
+            burst: 20,
+        },
+    ),
+    (
+        "blockstream.info",
+        RateLimit {
+            requests_per_second: 5.0,
+            burst: 10,
+        },
+    ),
+];
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Async token bucket: callers wait (without blocking the runtime) until a token is available
+#[derive(Debug)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    state: tokio::sync::Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: tokio::sync::Mutex::new(BucketState {
+                tokens: limit.burst as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let now = Instant::now();
+                let refilled = now.duration_since(state.last_refill).as_secs_f64()
+                    * self.limit.requests_per_second;
+                state.tokens = (state.tokens + refilled).min(self.limit.burst as f64);
+                state.last_refill = now;
+
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+
+                Duration::from_secs_f64((1.0 - state.tokens) / self.limit.requests_per_second)
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Empties the bucket for at least `wait`, e.g. after the provider reported its quota
+    /// exhausted, so every caller sharing it backs off together
+    pub async fn hold_for(&self, wait: Duration) {
+        let mut state = self.state.lock().await;
+        state.tokens = state
+            .tokens
+            .min(-wait.as_secs_f64() * self.limit.requests_per_second);
+        state.last_refill = Instant::now();
+    }
+}
+
+/// One bucket per host and API key, shared by every adapter in the process
+#[derive(Debug, Default)]
+pub struct RateLimiterRegistry {
+    overrides: Mutex<HashMap<String, RateLimit>>,
+    buckets: Mutex<HashMap<String, Arc<TokenBucket>>>,
+}
+
+pub static RATE_LIMITERS: LazyLock<RateLimiterRegistry> =
+    LazyLock::new(RateLimiterRegistry::default);
+
+impl RateLimiterRegistry {
+    /// Applies the `[[http.rate_limits]]` overrides; only affects buckets created afterwards
+    pub fn configure(&self, config: &HttpConfig) {
+        let mut overrides = self.overrides.lock().expect("Lock poisoned");
+        for rate_limit in &config.rate_limits {
+            overrides.insert(
+                rate_limit.host.to_lowercase(),
+                RateLimit {
+                    requests_per_second: rate_limit.requests_per_second,
+                    burst: rate_limit.burst.max(1),
+                },
+            );
+        }
+    }
+
+    pub fn bucket(&self, url: &str, api_key: Option<&str>) -> Arc<TokenBucket> {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_else(|| url.to_owned());
+        let key = match api_key {
+            Some(api_key) => format!("{}#{}", host, api_key),
+            None => host.clone(),
+        };
+
+        let mut buckets = self.buckets.lock().expect("Lock poisoned");
+        let bucket = buckets
+            .entry(key)
+            .or_insert_with(|| Arc::new(TokenBucket::new(self.limit_for(&host))));
+        Arc::clone(bucket)
+    }
+
+    fn limit_for(&self, host: &str) -> RateLimit {
+        if let Some(limit) = self.overrides.lock().expect("Lock poisoned").get(host) {
+            return *limit;
+        }
+
+        KNOWN_RATE_LIMITS
+            .iter()
+            .find(|(known_host, _)| *known_host == host)
+            .map_or(DEFAULT_RATE_LIMIT, |(_, limit)| *limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bucket_allows_burst_then_paces() {
+        let bucket = TokenBucket::new(RateLimit {
+            requests_per_second: 20.0,
+            burst: 2,
+        });
+
+        let start = Instant::now();
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(20));
+
+        bucket.acquire().await;
+        bucket.acquire().await;
+        // Two extra tokens at 20/s take ~100ms to refill
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn test_hold_delays_every_caller() {
+        let bucket = TokenBucket::new(RateLimit {
+            requests_per_second: 100.0,
+            burst: 10,
+        });
+
+        let start = Instant::now();
+        bucket.hold_for(Duration::from_millis(50)).await;
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_registry_shares_buckets_per_host_and_key() {
+        let registry = RateLimiterRegistry::default();
+
+        let a = registry.bucket("https://api.etherscan.io/v2/api?chainid=1", Some("key"));
+        let b = registry.bucket("https://api.etherscan.io/v2/api?chainid=10", Some("key"));
+        let other_key = registry.bucket("https://api.etherscan.io/v2/api", Some("other"));
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &other_key));
+        assert_eq!(a.limit, KNOWN_RATE_LIMITS[0].1);
+        assert_eq!(
+            registry.bucket("https://example.com", None).limit,
+            DEFAULT_RATE_LIMIT
+        );
+    }
+}
@@ -2,6 +2,9 @@ pub mod blockchain;
 pub mod config;
 pub mod debank;
 pub mod exchange;
+pub mod hold;
+pub mod http;
 pub mod kafka_publisher;
+pub mod persistence;
 pub mod price;
 pub mod sheets;
@@ -0,0 +1,28 @@
+use std::path::Path;
+
+pub mod dry_run;
+pub mod fan_out_snapshot_repository;
+pub mod file_balance_repository;
+pub mod file_schedule_state_repository;
+pub mod spreadsheet_snapshot_repository;
+pub mod sqlite_snapshot_repository;
+
+/// Writes to a sibling temporary file first, so readers never see a half-written file
+async fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, contents).await?;
+    tokio::fs::rename(&tmp_path, path).await
+}
+
+/// Empty directory under the system temp dir, unique to `name` and the test process
+#[cfg(test)]
+pub(crate) fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("crypto_balance_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
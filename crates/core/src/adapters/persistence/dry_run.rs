@@ -0,0 +1,251 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use error_stack::{report, ResultExt};
+use serde_json::{json, Value};
+use tracing::instrument;
+
+use crate::adapters::sheets::backend::dry_run::{DryRunRecorder, RecordedWrite};
+use crate::domain::exchange::{BalanceRepository, BalanceRepositoryError, BalanceUpdateTarget};
+use crate::ports::snapshot_repository::{
+    BalanceSnapshot, SnapshotQuery, SnapshotRepository, SnapshotRepositoryError,
+};
+
+use super::file_balance_repository::{BalanceRecord, FileBalanceRepository};
+
+/// Stands in for the SQLite snapshot database during a dry run. The database is never opened,
+/// so it cannot even be created; the snapshots it would have stored are recorded as appends to
+/// its path.
+#[derive(Debug)]
+pub struct DryRunSnapshotRepository {
+    path: PathBuf,
+    recorder: Arc<DryRunRecorder>,
+}
+
+impl DryRunSnapshotRepository {
+    pub fn new(path: impl Into<PathBuf>, recorder: Arc<DryRunRecorder>) -> Self {
+        Self {
+            path: path.into(),
+            recorder,
+        }
+    }
+}
+
+/// Same columns as the `balance_snapshots` table
+fn snapshot_row(snapshot: &BalanceSnapshot) -> Vec<Value> {
+    vec![
+        json!(snapshot.timestamp.to_rfc3339()),
+        json!(snapshot.source),
+        json!(snapshot.account),
+        json!(snapshot.asset),
+        json!(snapshot.amount),
+        json!(snapshot.price),
+        json!(snapshot.usd_value),
+    ]
+}
+
+#[async_trait::async_trait]
+impl SnapshotRepository for DryRunSnapshotRepository {
+    #[instrument(skip(self, snapshots), fields(count = snapshots.len()))]
+    async fn save_snapshots(
+        &self,
+        snapshots: &[BalanceSnapshot],
+    ) -> error_stack::Result<(), SnapshotRepositoryError> {
+        tracing::info!("Dry run: skipping snapshots to {}", self.path.display());
+        self.recorder.record(RecordedWrite {
+            named_range: None,
+            range: self.path.display().to_string(),
+            old_values: Vec::new(),
+            new_values: snapshots.iter().map(snapshot_row).collect(),
+            append: true,
+        });
+
+        Ok(())
+    }
+
+    /// Nothing is stored, so there is nothing to read back
+    async fn get_snapshots(
+        &self,
+        _query: &SnapshotQuery,
+    ) -> error_stack::Result<Vec<BalanceSnapshot>, SnapshotRepositoryError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Reads the token list through the wrapped [`FileBalanceRepository`], but only records the
+/// balance files it would write (along with the balances they would replace)
+#[derive(Debug)]
+pub struct DryRunBalanceRepository {
+    inner: FileBalanceRepository,
+    recorder: Arc<DryRunRecorder>,
+}
+
+impl DryRunBalanceRepository {
+    pub fn new(inner: FileBalanceRepository, recorder: Arc<DryRunRecorder>) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+fn record_row(record: BalanceRecord) -> Vec<Value> {
+    vec![json!(record.token), json!(record.balance)]
+}
+
+#[async_trait::async_trait]
+impl BalanceRepository for DryRunBalanceRepository {
+    async fn get_token_names(&self) -> error_stack::Result<Vec<String>, BalanceRepositoryError> {
+        self.inner.get_token_names().await
+    }
+
+    #[instrument(skip(self, balances), fields(count = balances.len()))]
+    async fn update_balances(
+        &self,
+        target: BalanceUpdateTarget,
+        balances: &[f64],
+    ) -> error_stack::Result<(), BalanceRepositoryError> {
+        let token_names = self
+            .inner
+            .get_token_names()
+            .await
+            .change_context(BalanceRepositoryError::UpdateBalancesError)?;
+
+        // Fails like the write it stands in for would
+        if token_names.len() != balances.len() {
+            return Err(report!(BalanceRepositoryError::UpdateBalancesError)).attach_printable(
+                format!(
+                    "Got {} balances for {} tokens",
+                    balances.len(),
+                    token_names.len()
+                ),
+            );
+        }
+
+        let path = self.inner.balances_path(target);
+        let old_values = match self.inner.get_balances(target).await {
+            Ok(records) => records.into_iter().map(record_row).collect(),
+            Err(error) => {
+                tracing::warn!("Dry run: could not read {}: {:?}", path.display(), error);
+                Vec::new()
+            }
+        };
+
+        tracing::info!("Dry run: skipping write to {}", path.display());
+        self.recorder.record(RecordedWrite {
+            named_range: None,
+            range: path.display().to_string(),
+            old_values,
+            new_values: token_names
+                .into_iter()
+                .zip(balances)
+                .map(|(token, &balance)| record_row(BalanceRecord { token, balance }))
+                .collect(),
+            append: false,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::super::{sqlite_snapshot_repository::SqliteSnapshotRepository, temp_dir};
+    use super::*;
+    use crate::adapters::config::persistence_config::FileFormat;
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_the_snapshot_database_untouched() {
+        let dir = temp_dir("dry_run_snapshots");
+        let path = dir.join("snapshots.db");
+        let timestamp = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let snapshot = BalanceSnapshot::new("Kraken", "default", "BTC", 0.5, timestamp);
+
+        // Not created when missing
+        let recorder = Arc::new(DryRunRecorder::default());
+        let repository = DryRunSnapshotRepository::new(&path, Arc::clone(&recorder));
+        repository
+            .save_snapshots(std::slice::from_ref(&snapshot))
+            .await
+            .unwrap();
+        assert!(!path.exists());
+
+        // Not written to when present
+        SqliteSnapshotRepository::open(&path)
+            .unwrap()
+            .save_snapshots(std::slice::from_ref(&snapshot))
+            .await
+            .unwrap();
+        repository
+            .save_snapshots(&[snapshot.clone().with_price(60000.0)])
+            .await
+            .unwrap();
+        assert_eq!(
+            SqliteSnapshotRepository::open(&path)
+                .unwrap()
+                .get_snapshots(&SnapshotQuery::default())
+                .await
+                .unwrap(),
+            vec![snapshot]
+        );
+
+        let writes = recorder.take();
+        assert_eq!(writes.len(), 2);
+        assert!(writes[1].append);
+        assert_eq!(writes[1].range, path.display().to_string());
+        assert_eq!(writes[1].new_values[0][6], json!(30000.0));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_the_balance_files_untouched() {
+        let dir = temp_dir("dry_run_balances");
+        std::fs::write(dir.join("tokens.json"), r#"["BTC", "ETH"]"#).unwrap();
+        let files = FileBalanceRepository::new(&dir, FileFormat::Json);
+        files
+            .update_balances(BalanceUpdateTarget::Kraken, &[1.0, 2.0])
+            .await
+            .unwrap();
+        let before = std::fs::read(files.balances_path(BalanceUpdateTarget::Kraken)).unwrap();
+
+        let recorder = Arc::new(DryRunRecorder::default());
+        let repository = DryRunBalanceRepository::new(files.clone(), Arc::clone(&recorder));
+        repository
+            .update_balances(BalanceUpdateTarget::Kraken, &[3.0, 4.0])
+            .await
+            .unwrap();
+        repository
+            .update_balances(BalanceUpdateTarget::Binance, &[5.0, 6.0])
+            .await
+            .unwrap();
+        assert!(repository
+            .update_balances(BalanceUpdateTarget::Kraken, &[1.0])
+            .await
+            .is_err());
+
+        assert_eq!(
+            std::fs::read(files.balances_path(BalanceUpdateTarget::Kraken)).unwrap(),
+            before
+        );
+        assert!(!files.balances_path(BalanceUpdateTarget::Binance).exists());
+
+        let writes = recorder.take();
+        assert_eq!(writes.len(), 2);
+        assert_eq!(
+            writes[0].old_values,
+            vec![
+                vec![json!("BTC"), json!(1.0)],
+                vec![json!("ETH"), json!(2.0)]
+            ]
+        );
+        assert_eq!(
+            writes[0].new_values,
+            vec![
+                vec![json!("BTC"), json!(3.0)],
+                vec![json!("ETH"), json!(4.0)]
+            ]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
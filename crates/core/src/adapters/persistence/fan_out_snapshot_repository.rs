@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use error_stack::{report, ResultExt};
+use tracing::instrument;
+
+use crate::ports::snapshot_repository::{
+    BalanceSnapshot, SnapshotQuery, SnapshotRepository, SnapshotRepositoryError,
+};
+
+/// Saves every snapshot to all the wrapped repositories; reads from the first one
+#[derive(Debug)]
+pub struct FanOutSnapshotRepository {
+    repositories: Vec<Arc<dyn SnapshotRepository>>,
+}
+
+impl FanOutSnapshotRepository {
+    pub fn new(repositories: Vec<Arc<dyn SnapshotRepository>>) -> Self {
+        Self { repositories }
+    }
+
+    /// `None` without repositories, the repository itself when there is only one
+    pub fn from_repositories(
+        mut repositories: Vec<Arc<dyn SnapshotRepository>>,
+    ) -> Option<Arc<dyn SnapshotRepository>> {
+        match repositories.len() {
+            0 => None,
+            1 => repositories.pop(),
+            _ => Some(Arc::new(Self::new(repositories))),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SnapshotRepository for FanOutSnapshotRepository {
+    /// Every repository is tried, even after one fails, so a broken sink does not cost the
+    /// history kept by the others
+    #[instrument(skip(self, snapshots), fields(count = snapshots.len()))]
+    async fn save_snapshots(
+        &self,
+        snapshots: &[BalanceSnapshot],
+    ) -> error_stack::Result<(), SnapshotRepositoryError> {
+        let results = futures::future::join_all(
+            self.repositories
+                .iter()
+                .map(|repository| repository.save_snapshots(snapshots)),
+        )
+        .await;
+
+        results
+            .into_iter()
+            .filter_map(Result::err)
+            .reduce(|mut errors, error| {
+                errors.extend_one(error);
+                errors
+            })
+            .map_or(Ok(()), Err)
+    }
+
+    async fn get_snapshots(
+        &self,
+        query: &SnapshotQuery,
+    ) -> error_stack::Result<Vec<BalanceSnapshot>, SnapshotRepositoryError> {
+        match self.repositories.first() {
+            Some(repository) => repository.get_snapshots(query).await,
+            None => Err(report!(SnapshotRepositoryError::FetchSnapshotsError))
+                .attach_printable("No snapshot repository configured"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct FakeRepository {
+        fail: bool,
+        saved: Mutex<Vec<BalanceSnapshot>>,
+    }
+
+    #[async_trait::async_trait]
+    impl SnapshotRepository for FakeRepository {
+        async fn save_snapshots(
+            &self,
+            snapshots: &[BalanceSnapshot],
+        ) -> error_stack::Result<(), SnapshotRepositoryError> {
+            if self.fail {
+                return Err(report!(SnapshotRepositoryError::SaveSnapshotsError));
+            }
+            self.saved.lock().unwrap().extend_from_slice(snapshots);
+            Ok(())
+        }
+
+        async fn get_snapshots(
+            &self,
+            _query: &SnapshotQuery,
+        ) -> error_stack::Result<Vec<BalanceSnapshot>, SnapshotRepositoryError> {
+            Ok(self.saved.lock().unwrap().clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_saves_to_every_repository() {
+        let failing = Arc::new(FakeRepository {
+            fail: true,
+            ..Default::default()
+        });
+        let working = Arc::new(FakeRepository::default());
+        let fan_out = FanOutSnapshotRepository::new(vec![failing, working.clone()]);
+
+        let snapshot = BalanceSnapshot::new("Kraken", "default", "BTC", 0.5, chrono::Utc::now());
+        assert!(fan_out
+            .save_snapshots(std::slice::from_ref(&snapshot))
+            .await
+            .is_err());
+        assert_eq!(*working.saved.lock().unwrap(), vec![snapshot]);
+    }
+}
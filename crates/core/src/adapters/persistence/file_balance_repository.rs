@@ -0,0 +1,257 @@
+use std::path::PathBuf;
+
+use error_stack::{report, ResultExt};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::adapters::config::persistence_config::{FileFormat, FilePersistenceConfig};
+use crate::domain::exchange::{BalanceRepository, BalanceRepositoryError, BalanceUpdateTarget};
+
+use super::write_atomically;
+
+/// One balance of a target, stored next to the token it belongs to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceRecord {
+    pub token: String,
+    pub balance: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenRecord {
+    token: String,
+}
+
+/// Keeps the token list and the balances of each target in a local directory:
+///
+/// - `tokens.csv` / `tokens.json`: the token names, in the order balances are written
+/// - `balances/<target>.csv` / `balances/<target>.json`: one `token, balance` record per token
+#[derive(Debug, Clone)]
+pub struct FileBalanceRepository {
+    dir: PathBuf,
+    format: FileFormat,
+}
+
+impl FileBalanceRepository {
+    pub fn new(dir: impl Into<PathBuf>, format: FileFormat) -> Self {
+        Self {
+            dir: dir.into(),
+            format,
+        }
+    }
+
+    pub fn from_config(config: &FilePersistenceConfig) -> Self {
+        Self::new(config.dir.as_ref(), config.format)
+    }
+
+    pub fn tokens_path(&self) -> PathBuf {
+        self.dir.join(format!("tokens.{}", self.format.extension()))
+    }
+
+    pub fn balances_path(&self, target: BalanceUpdateTarget) -> PathBuf {
+        self.dir
+            .join("balances")
+            .join(format!("{}.{}", target.name(), self.format.extension()))
+    }
+
+    /// Reads back the balances last written for `target`, if any
+    pub async fn get_balances(
+        &self,
+        target: BalanceUpdateTarget,
+    ) -> error_stack::Result<Vec<BalanceRecord>, BalanceRepositoryError> {
+        let path = self.balances_path(target);
+        let contents = match tokio::fs::read(&path).await {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => {
+                return Err(error)
+                    .change_context(BalanceRepositoryError::FetchBalancesError)
+                    .attach_printable_lazy(|| format!("Reading {}", path.display()))
+            }
+        };
+
+        decode::<BalanceRecord>(self.format, &contents)
+            .change_context(BalanceRepositoryError::FetchBalancesError)
+            .attach_printable_lazy(|| format!("Parsing {}", path.display()))
+    }
+}
+
+#[async_trait::async_trait]
+impl BalanceRepository for FileBalanceRepository {
+    #[instrument(skip(self), fields(path = %self.tokens_path().display()))]
+    async fn get_token_names(&self) -> error_stack::Result<Vec<String>, BalanceRepositoryError> {
+        let path = self.tokens_path();
+        let contents = tokio::fs::read(&path)
+            .await
+            .change_context(BalanceRepositoryError::FetchTokenNamesError)
+            .attach_printable_lazy(|| {
+                format!(
+                    "Reading {} (list the tracked tokens there, one per row)",
+                    path.display()
+                )
+            })?;
+
+        let token_names = match self.format {
+            FileFormat::Csv => decode::<TokenRecord>(self.format, &contents)
+                .map(|records| records.into_iter().map(|record| record.token).collect()),
+            FileFormat::Json => {
+                serde_json::from_slice::<Vec<String>>(&contents).change_context(FileCodecError)
+            }
+        };
+
+        token_names
+            .change_context(BalanceRepositoryError::FetchTokenNamesError)
+            .attach_printable_lazy(|| format!("Parsing {}", path.display()))
+    }
+
+    #[instrument(skip(self, balances), fields(count = balances.len()))]
+    async fn update_balances(
+        &self,
+        target: BalanceUpdateTarget,
+        balances: &[f64],
+    ) -> error_stack::Result<(), BalanceRepositoryError> {
+        let token_names = self
+            .get_token_names()
+            .await
+            .change_context(BalanceRepositoryError::UpdateBalancesError)?;
+
+        if token_names.len() != balances.len() {
+            return Err(report!(BalanceRepositoryError::UpdateBalancesError)).attach_printable(
+                format!(
+                    "Got {} balances for {} tokens",
+                    balances.len(),
+                    token_names.len()
+                ),
+            );
+        }
+
+        let records = token_names
+            .into_iter()
+            .zip(balances)
+            .map(|(token, &balance)| BalanceRecord { token, balance })
+            .collect::<Vec<_>>();
+
+        let path = self.balances_path(target);
+        let contents = encode(self.format, &records)
+            .change_context(BalanceRepositoryError::UpdateBalancesError)?;
+
+        write_atomically(&path, &contents)
+            .await
+            .change_context(BalanceRepositoryError::UpdateBalancesError)
+            .attach_printable_lazy(|| format!("Writing {}", path.display()))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to encode or decode a persistence file")]
+struct FileCodecError;
+
+fn decode<T: for<'de> Deserialize<'de>>(
+    format: FileFormat,
+    contents: &[u8],
+) -> error_stack::Result<Vec<T>, FileCodecError> {
+    match format {
+        FileFormat::Csv => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(contents)
+            .deserialize()
+            .collect::<Result<Vec<T>, _>>()
+            .change_context(FileCodecError),
+        FileFormat::Json => serde_json::from_slice(contents).change_context(FileCodecError),
+    }
+}
+
+fn encode<T: Serialize>(
+    format: FileFormat,
+    records: &[T],
+) -> error_stack::Result<Vec<u8>, FileCodecError> {
+    match format {
+        FileFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for record in records {
+                writer.serialize(record).change_context(FileCodecError)?;
+            }
+            writer.into_inner().change_context(FileCodecError)
+        }
+        FileFormat::Json => serde_json::to_vec_pretty(records).change_context(FileCodecError),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::temp_dir;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_csv_round_trip() {
+        let dir = temp_dir("file_repository_csv");
+        std::fs::write(dir.join("tokens.csv"), "token\nBTC\n ETH \n\"USD, Coin\"\n").unwrap();
+        let repository = FileBalanceRepository::new(&dir, FileFormat::Csv);
+
+        assert_eq!(
+            repository.get_token_names().await.unwrap(),
+            vec!["BTC", "ETH", "USD, Coin"]
+        );
+
+        repository
+            .update_balances(BalanceUpdateTarget::Kraken, &[0.5, 0.0, 12.25])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("balances/kraken.csv")).unwrap(),
+            "token,balance\nBTC,0.5\nETH,0.0\n\"USD, Coin\",12.25\n"
+        );
+        assert_eq!(
+            repository
+                .get_balances(BalanceUpdateTarget::Kraken)
+                .await
+                .unwrap()[2],
+            BalanceRecord {
+                token: "USD, Coin".to_owned(),
+                balance: 12.25,
+            }
+        );
+        assert!(repository
+            .get_balances(BalanceUpdateTarget::Binance)
+            .await
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_json_round_trip() {
+        let dir = temp_dir("file_repository_json");
+        std::fs::write(dir.join("tokens.json"), r#"["BTC", "ETH"]"#).unwrap();
+        let repository = FileBalanceRepository::new(&dir, FileFormat::Json);
+
+        repository
+            .update_balances(BalanceUpdateTarget::Solana, &[1.0, 2.0])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repository
+                .get_balances(BalanceUpdateTarget::Solana)
+                .await
+                .unwrap(),
+            vec![
+                BalanceRecord {
+                    token: "BTC".to_owned(),
+                    balance: 1.0,
+                },
+                BalanceRecord {
+                    token: "ETH".to_owned(),
+                    balance: 2.0,
+                },
+            ]
+        );
+        assert!(repository
+            .update_balances(BalanceUpdateTarget::Solana, &[1.0])
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
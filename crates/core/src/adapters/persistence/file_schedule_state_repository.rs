@@ -0,0 +1,140 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use error_stack::ResultExt;
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use crate::ports::schedule_state_repository::{
+    ScheduleStateRepository, ScheduleStateRepositoryError,
+};
+
+use super::write_atomically;
+
+/// Keeps the last start time of each routine in a JSON file, as `{ "<routine>": "<RFC 3339>" }`
+#[derive(Debug)]
+pub struct FileScheduleStateRepository {
+    path: PathBuf,
+    /// Saves read the whole file and write it back, so they must not interleave
+    lock: Mutex<()>,
+}
+
+impl FileScheduleStateRepository {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    async fn read(
+        &self,
+    ) -> error_stack::Result<BTreeMap<String, String>, ScheduleStateRepositoryError> {
+        let contents = match tokio::fs::read(&self.path).await {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(BTreeMap::new())
+            }
+            Err(error) => {
+                return Err(error)
+                    .change_context(ScheduleStateRepositoryError::LoadError)
+                    .attach_printable_lazy(|| format!("Reading {}", self.path.display()))
+            }
+        };
+
+        serde_json::from_slice(&contents)
+            .change_context(ScheduleStateRepositoryError::LoadError)
+            .attach_printable_lazy(|| format!("Parsing {}", self.path.display()))
+    }
+}
+
+#[async_trait::async_trait]
+impl ScheduleStateRepository for FileScheduleStateRepository {
+    #[instrument(skip(self), fields(path = %self.path.display()))]
+    async fn last_runs(
+        &self,
+    ) -> error_stack::Result<HashMap<String, DateTime<Utc>>, ScheduleStateRepositoryError> {
+        let _guard = self.lock.lock().await;
+
+        self.read()
+            .await?
+            .into_iter()
+            .map(|(routine, started_at)| {
+                let started_at = DateTime::parse_from_rfc3339(&started_at)
+                    .change_context(ScheduleStateRepositoryError::LoadError)
+                    .attach_printable_lazy(|| {
+                        format!("Invalid last run of {}: {}", routine, started_at)
+                    })?;
+                Ok((routine, started_at.with_timezone(&Utc)))
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self), fields(path = %self.path.display()))]
+    async fn save_last_run(
+        &self,
+        routine: &str,
+        started_at: DateTime<Utc>,
+    ) -> error_stack::Result<(), ScheduleStateRepositoryError> {
+        let _guard = self.lock.lock().await;
+
+        let mut last_runs = self
+            .read()
+            .await
+            .change_context(ScheduleStateRepositoryError::SaveError)?;
+        last_runs.insert(routine.to_owned(), started_at.to_rfc3339());
+
+        let contents = serde_json::to_vec_pretty(&last_runs)
+            .change_context(ScheduleStateRepositoryError::SaveError)?;
+        write_atomically(&self.path, &contents)
+            .await
+            .change_context(ScheduleStateRepositoryError::SaveError)
+            .attach_printable_lazy(|| format!("Writing {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::super::temp_dir;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_last_runs_round_trip() {
+        let path = temp_dir("schedule_state").join("schedule_state.json");
+        let repository = FileScheduleStateRepository::new(&path);
+
+        assert!(repository.last_runs().await.unwrap().is_empty());
+
+        let first = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let second = Utc.with_ymd_and_hms(2024, 5, 1, 18, 30, 0).unwrap();
+        repository
+            .save_last_run("DebankRoutine", first)
+            .await
+            .unwrap();
+        repository
+            .save_last_run("TokenPricesRoutine", first)
+            .await
+            .unwrap();
+        repository
+            .save_last_run("DebankRoutine", second)
+            .await
+            .unwrap();
+
+        let last_runs = FileScheduleStateRepository::new(&path)
+            .last_runs()
+            .await
+            .unwrap();
+        assert_eq!(
+            last_runs,
+            HashMap::from([
+                ("DebankRoutine".to_owned(), second),
+                ("TokenPricesRoutine".to_owned(), first),
+            ])
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
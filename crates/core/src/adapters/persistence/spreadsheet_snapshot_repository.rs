@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use error_stack::ResultExt;
+use serde_json::{json, Value};
+use tracing::instrument;
+
+use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
+use crate::domain::sheets::cell_value::serial_to_date_time;
+use crate::ports::snapshot_repository::{
+    BalanceSnapshot, SnapshotQuery, SnapshotRepository, SnapshotRepositoryError,
+};
+
+/// Entered as-is, so the sheet stores it as a date-time (in UTC) and reads it back as its serial
+/// number, however the sheet's locale shows it
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Appends one row per snapshot to a history tab, so the sheet itself accumulates a time
+/// series: timestamp, source, account, asset, amount and USD value (empty when unknown).
+/// Rows are never rewritten; a header row, if any, is skipped when reading back.
+#[derive(Debug)]
+pub struct SpreadsheetSnapshotRepository {
+    spreadsheet_manager: Arc<SpreadsheetManager>,
+    range: String,
+}
+
+impl SpreadsheetSnapshotRepository {
+    pub fn new(spreadsheet_manager: Arc<SpreadsheetManager>, range: impl Into<String>) -> Self {
+        Self {
+            spreadsheet_manager,
+            range: range.into(),
+        }
+    }
+}
+
+fn snapshot_to_row(snapshot: &BalanceSnapshot) -> Vec<Value> {
+    vec![
+        json!(snapshot.timestamp.format(TIMESTAMP_FORMAT).to_string()),
+        json!(snapshot.source),
+        json!(snapshot.account),
+        json!(snapshot.asset),
+        json!(snapshot.amount),
+        snapshot.usd_value.map_or(json!(""), |value| json!(value)),
+    ]
+}
+
+/// Numbers are read unformatted; text is only parsed when in plain `1234.5` notation, as
+/// separators depend on the sheet's locale
+fn parse_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    let timestamp = match value {
+        Value::Number(serial) => serial_to_date_time(serial.as_f64()?)?,
+        // Cells formatted as plain text keep the text that was entered
+        Value::String(text) => NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT).ok()?,
+        _ => return None,
+    };
+    Some(timestamp.and_utc())
+}
+
+/// `None` for rows that are not snapshots, such as the header. `row` holds unformatted values.
+fn row_to_snapshot(row: &[Value]) -> Option<BalanceSnapshot> {
+    let text = |index: usize| row.get(index)?.as_str().map(str::to_owned);
+
+    let timestamp = parse_timestamp(row.first()?)?;
+    let amount = parse_number(row.get(4)?)?;
+    let usd_value = row.get(5).and_then(parse_number);
+
+    Some(BalanceSnapshot {
+        source: text(1)?,
+        account: text(2)?,
+        asset: text(3)?,
+        amount,
+        price: usd_value
+            .filter(|_| amount != 0.0)
+            .map(|usd_value| usd_value / amount),
+        usd_value,
+        timestamp,
+    })
+}
+
+fn matches(query: &SnapshotQuery, snapshot: &BalanceSnapshot) -> bool {
+    let equals =
+        |filter: &Option<String>, value: &str| filter.as_deref().is_none_or(|f| f == value);
+    let after =
+        |since: &Option<DateTime<Utc>>| since.is_none_or(|since| snapshot.timestamp >= since);
+    let before =
+        |until: &Option<DateTime<Utc>>| until.is_none_or(|until| snapshot.timestamp < until);
+
+    equals(&query.source, &snapshot.source)
+        && equals(&query.account, &snapshot.account)
+        && equals(&query.asset, &snapshot.asset)
+        && after(&query.since)
+        && before(&query.until)
+}
+
+#[async_trait::async_trait]
+impl SnapshotRepository for SpreadsheetSnapshotRepository {
+    #[instrument(skip(self, snapshots), fields(count = snapshots.len(), range = %self.range))]
+    async fn save_snapshots(
+        &self,
+        snapshots: &[BalanceSnapshot],
+    ) -> error_stack::Result<(), SnapshotRepositoryError> {
+        if snapshots.is_empty() {
+            return Ok(());
+        }
+
+        self.spreadsheet_manager
+            .append_rows(&self.range, snapshots.iter().map(snapshot_to_row).collect())
+            .await
+            .change_context(SnapshotRepositoryError::SaveSnapshotsError)
+    }
+
+    #[instrument(skip(self), fields(range = %self.range))]
+    async fn get_snapshots(
+        &self,
+        query: &SnapshotQuery,
+    ) -> error_stack::Result<Vec<BalanceSnapshot>, SnapshotRepositoryError> {
+        let rows = self
+            .spreadsheet_manager
+            .read_rows(&self.range)
+            .await
+            .change_context(SnapshotRepositoryError::FetchSnapshotsError)?;
+
+        let mut snapshots = rows
+            .iter()
+            .filter_map(|row| row_to_snapshot(row))
+            .filter(|snapshot| matches(query, snapshot))
+            .collect::<Vec<_>>();
+        snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::adapters::config::sheets_config::SpreadsheetConfig;
+    use crate::adapters::sheets::backend::in_memory::InMemorySheetsBackend;
+
+    #[test]
+    fn test_rows_round_trip() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+        let priced =
+            BalanceSnapshot::new("Optimism", "Hold", "ETH", 2.0, timestamp).with_price(3000.0);
+        let unpriced = BalanceSnapshot::new("Kraken", "default", "BTC", 0.5, timestamp);
+
+        let row = snapshot_to_row(&priced);
+        assert_eq!(
+            row,
+            vec![
+                json!("2024-05-01 12:30:00"),
+                json!("Optimism"),
+                json!("Hold"),
+                json!("ETH"),
+                json!(2.0),
+                json!(6000.0),
+            ]
+        );
+        assert_eq!(row_to_snapshot(&row), Some(priced));
+        assert_eq!(row_to_snapshot(&snapshot_to_row(&unpriced)), Some(unpriced));
+
+        // As read back from the sheet: the date-time as its serial number, trailing empty cells
+        // omitted
+        let stored = [
+            json!(45413.520833333336),
+            json!("Kraken"),
+            json!("default"),
+            json!("BTC"),
+            json!(1000.5),
+        ];
+        let snapshot = row_to_snapshot(&stored).unwrap();
+        assert_eq!(snapshot.timestamp, timestamp);
+        assert_eq!(snapshot.amount, 1000.5);
+        assert_eq!(snapshot.usd_value, None);
+
+        let header =
+            ["Timestamp", "Source", "Account", "Asset", "Amount", "Value"].map(|h| json!(h));
+        assert_eq!(row_to_snapshot(&header), None);
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_round_trip_through_the_sheet() {
+        let backend = Arc::new(InMemorySheetsBackend::new().with_values(
+            "'History'!A1",
+            vec![["Timestamp", "Source", "Account", "Asset", "Amount", "Value"]
+                .map(|header| json!(header))
+                .to_vec()],
+        ));
+        let repository = SpreadsheetSnapshotRepository::new(
+            Arc::new(backend.spreadsheet_manager(SpreadsheetConfig::default())),
+            "'History'!A:F",
+        );
+
+        let morning = Utc.with_ymd_and_hms(2024, 5, 1, 9, 15, 30).unwrap();
+        let evening = Utc.with_ymd_and_hms(2024, 5, 1, 21, 0, 0).unwrap();
+        let snapshots = vec![
+            BalanceSnapshot::new("Optimism", "Hold", "ETH", 2.0, morning).with_price(3000.0),
+            BalanceSnapshot::new("Kraken", "default", "BTC", 0.5, evening),
+        ];
+        repository.save_snapshots(&snapshots).await.unwrap();
+
+        // Stored as a date-time cell, whatever format the sheet shows it in
+        assert_eq!(
+            backend.values("'History'!A2"),
+            vec![vec![json!(45413.38576388889)]]
+        );
+        assert_eq!(
+            repository
+                .get_snapshots(&SnapshotQuery::default())
+                .await
+                .unwrap(),
+            snapshots
+        );
+        assert_eq!(
+            repository
+                .get_snapshots(&SnapshotQuery {
+                    since: Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()),
+                    ..Default::default()
+                })
+                .await
+                .unwrap(),
+            snapshots[1..]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use error_stack::{report, ResultExt};
+use rusqlite::{params, Connection};
+use tracing::instrument;
+
+use crate::ports::snapshot_repository::{
+    BalanceSnapshot, SnapshotQuery, SnapshotRepository, SnapshotRepositoryError,
+};
+
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to open the snapshot database")]
+pub struct OpenDatabaseError;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS balance_snapshots (
+        id INTEGER PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        source TEXT NOT NULL,
+        account TEXT NOT NULL,
+        asset TEXT NOT NULL,
+        amount REAL NOT NULL,
+        price REAL,
+        usd_value REAL
+    );
+    CREATE INDEX IF NOT EXISTS balance_snapshots_asset_timestamp
+        ON balance_snapshots (asset, timestamp);
+";
+
+/// Keeps every snapshot in a local SQLite database. Timestamps are stored as RFC 3339 UTC text
+/// with a fixed precision, so they sort chronologically.
+#[derive(Debug, Clone)]
+pub struct SqliteSnapshotRepository {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSnapshotRepository {
+    /// Opens (or creates) the database at `path`, creating its parent directory if needed
+    pub fn open(path: impl AsRef<Path>) -> error_stack::Result<Self, OpenDatabaseError> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)
+                .change_context(OpenDatabaseError)
+                .attach_printable_lazy(|| format!("Creating {}", parent.display()))?;
+        }
+
+        let connection = Connection::open(path)
+            .change_context(OpenDatabaseError)
+            .attach_printable_lazy(|| format!("Opening {}", path.display()))?;
+        Self::with_connection(connection)
+    }
+
+    pub fn open_in_memory() -> error_stack::Result<Self, OpenDatabaseError> {
+        let connection = Connection::open_in_memory().change_context(OpenDatabaseError)?;
+        Self::with_connection(connection)
+    }
+
+    fn with_connection(connection: Connection) -> error_stack::Result<Self, OpenDatabaseError> {
+        connection
+            .execute_batch(SCHEMA)
+            .change_context(OpenDatabaseError)
+            .attach_printable("Creating the snapshot tables")?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Runs `f` on the connection without blocking the async runtime
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    ) -> error_stack::Result<T, rusqlite::Error> {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || f(&mut connection.lock().expect("Lock poisoned")))
+            .await
+            .expect("SQLite task panicked")
+            .map_err(|error| report!(error))
+    }
+}
+
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn parse_timestamp(index: usize, value: String) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|error| {
+            rusqlite::Error::FromSqlConversionFailure(
+                index,
+                rusqlite::types::Type::Text,
+                Box::new(error),
+            )
+        })
+}
+
+#[async_trait::async_trait]
+impl SnapshotRepository for SqliteSnapshotRepository {
+    #[instrument(skip(self, snapshots), fields(count = snapshots.len()))]
+    async fn save_snapshots(
+        &self,
+        snapshots: &[BalanceSnapshot],
+    ) -> error_stack::Result<(), SnapshotRepositoryError> {
+        let snapshots = snapshots.to_vec();
+
+        self.blocking(move |connection| {
+            let transaction = connection.transaction()?;
+            {
+                let mut insert = transaction.prepare(
+                    "INSERT INTO balance_snapshots
+                        (timestamp, source, account, asset, amount, price, usd_value)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )?;
+                for snapshot in &snapshots {
+                    insert.execute(params![
+                        format_timestamp(&snapshot.timestamp),
+                        snapshot.source,
+                        snapshot.account,
+                        snapshot.asset,
+                        snapshot.amount,
+                        snapshot.price,
+                        snapshot.usd_value,
+                    ])?;
+                }
+            }
+            transaction.commit()
+        })
+        .await
+        .change_context(SnapshotRepositoryError::SaveSnapshotsError)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_snapshots(
+        &self,
+        query: &SnapshotQuery,
+    ) -> error_stack::Result<Vec<BalanceSnapshot>, SnapshotRepositoryError> {
+        let query = query.clone();
+
+        self.blocking(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT timestamp, source, account, asset, amount, price, usd_value
+                    FROM balance_snapshots
+                    WHERE (?1 IS NULL OR source = ?1)
+                        AND (?2 IS NULL OR account = ?2)
+                        AND (?3 IS NULL OR asset = ?3)
+                        AND (?4 IS NULL OR timestamp >= ?4)
+                        AND (?5 IS NULL OR timestamp < ?5)
+                    ORDER BY timestamp, id",
+            )?;
+
+            let rows = statement.query_map(
+                params![
+                    query.source,
+                    query.account,
+                    query.asset,
+                    query.since.as_ref().map(format_timestamp),
+                    query.until.as_ref().map(format_timestamp),
+                ],
+                |row| {
+                    Ok(BalanceSnapshot {
+                        timestamp: parse_timestamp(0, row.get(0)?)?,
+                        source: row.get(1)?,
+                        account: row.get(2)?,
+                        asset: row.get(3)?,
+                        amount: row.get(4)?,
+                        price: row.get(5)?,
+                        usd_value: row.get(6)?,
+                    })
+                },
+            )?;
+
+            rows.collect()
+        })
+        .await
+        .change_context(SnapshotRepositoryError::FetchSnapshotsError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_save_and_query_snapshots() {
+        let repository = SqliteSnapshotRepository::open_in_memory().unwrap();
+        let (yesterday, today) = (
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 5, 2, 12, 0, 0).unwrap(),
+        );
+
+        repository
+            .save_snapshots(&[
+                BalanceSnapshot::new("Kraken", "default", "BTC", 0.5, yesterday),
+                BalanceSnapshot::new("Optimism", "Hold", "ETH", 2.0, yesterday).with_price(3000.0),
+            ])
+            .await
+            .unwrap();
+        repository
+            .save_snapshots(&[BalanceSnapshot::new(
+                "Kraken", "default", "BTC", 0.75, today,
+            )])
+            .await
+            .unwrap();
+
+        let btc = repository
+            .get_snapshots(&SnapshotQuery {
+                asset: Some("BTC".to_owned()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            btc.iter()
+                .map(|snapshot| snapshot.amount)
+                .collect::<Vec<_>>(),
+            vec![0.5, 0.75]
+        );
+        assert_eq!(btc[1].timestamp, today);
+
+        let yesterday_snapshots = repository
+            .get_snapshots(&SnapshotQuery {
+                until: Some(today),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(yesterday_snapshots.len(), 2);
+        assert_eq!(yesterday_snapshots[1].usd_value, Some(6000.0));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod api;
 pub mod price;
+pub mod spreadsheet_asset_prices;
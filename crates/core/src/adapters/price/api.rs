@@ -1,6 +1,13 @@
 use std::collections::HashMap;
 
-pub struct CoinGeckoApi;
+use crate::adapters::http::{send_rate_limited, HTTP_CLIENT};
+
+pub const COINGECKO_BASE_URL: &str = "https://api.coingecko.com";
+
+#[derive(Debug)]
+pub struct CoinGeckoApi {
+    base_url: String,
+}
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct CoinResponse {
@@ -20,13 +27,28 @@ pub struct PriceResponse {
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct PricesResponse(pub HashMap<String, PriceResponse>);
 
+impl Default for CoinGeckoApi {
+    fn default() -> Self {
+        Self::new(COINGECKO_BASE_URL)
+    }
+}
+
 impl CoinGeckoApi {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
     pub async fn prices(&self, tokens: &[String]) -> PricesResponse {
         let url = format!(
-            "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd",
+            "{}/api/v3/simple/price?ids={}&vs_currencies=usd",
+            self.base_url,
             tokens.join(",")
         );
-        let response = reqwest::get(&url).await.unwrap().text().await.unwrap();
+        let response = send_rate_limited(|| HTTP_CLIENT.get(&url), &url, None)
+            .await
+            .unwrap();
         let prices: PricesResponse = serde_json::from_str(&response).unwrap();
         prices
     }
@@ -1,13 +1,47 @@
 use std::collections::HashMap;
 
+use crate::ports::token_price_source::TokenPriceSource;
+
 use super::api::CoinGeckoApi;
 
 pub async fn get_token_prices(tokens: &[String]) -> HashMap<String, Option<f64>> {
-    CoinGeckoApi
-        .prices(tokens)
-        .await
-        .0
-        .into_iter()
-        .map(|(k, v)| (k, v.usd))
-        .collect()
+    CoinGeckoApi::default().token_prices(tokens).await
+}
+
+#[async_trait::async_trait]
+impl TokenPriceSource for CoinGeckoApi {
+    async fn token_prices(&self, token_ids: &[String]) -> HashMap<String, Option<f64>> {
+        self.prices(token_ids)
+            .await
+            .0
+            .into_iter()
+            .map(|(k, v)| (k, v.usd))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapters::http::fixture_server::FixtureServer;
+    use crate::adapters::price::api::COINGECKO_BASE_URL;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_prices_leave_out_unknown_ids() {
+        let server = FixtureServer::start("coingecko_simple_price", COINGECKO_BASE_URL).await;
+        let tokens = ["bitcoin", "ethereum", "not-a-coin"].map(String::from);
+
+        let prices = CoinGeckoApi::new(server.base_url())
+            .token_prices(&tokens)
+            .await;
+
+        assert_eq!(
+            prices,
+            HashMap::from([
+                ("bitcoin".to_string(), Some(67250.0)),
+                ("ethereum".to_string(), Some(3480.12)),
+            ])
+        );
+    }
 }
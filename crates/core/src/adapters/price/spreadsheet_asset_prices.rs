@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use error_stack::ResultExt;
+use tracing::instrument;
+
+use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
+use crate::adapters::sheets::spreadsheet_read::SpreadsheetRead;
+use crate::domain::sheets::ranges;
+use crate::ports::asset_price_source::{AssetPriceSource, AssetPriceSourceError};
+
+/// Prices last written to the spreadsheet by the token prices routine, paired with the token
+/// names of the same rows
+#[derive(Debug)]
+pub struct SpreadsheetAssetPrices {
+    spreadsheet_manager: Arc<SpreadsheetManager>,
+}
+
+impl SpreadsheetAssetPrices {
+    pub fn new(spreadsheet_manager: Arc<SpreadsheetManager>) -> Self {
+        Self {
+            spreadsheet_manager,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AssetPriceSource for SpreadsheetAssetPrices {
+    #[instrument(skip(self))]
+    async fn asset_prices(
+        &self,
+    ) -> error_stack::Result<HashMap<String, f64>, AssetPriceSourceError> {
+        let (names, prices) = futures::try_join!(
+            self.spreadsheet_manager
+                .read_named_range(ranges::tokens::RO_NAMES),
+            self.spreadsheet_manager
+                .read_named_typed_range(ranges::tokens::RW_PRICES),
+        )
+        .change_context(AssetPriceSourceError::FetchPricesError)?;
+
+        Ok(names
+            .into_iter()
+            .zip(prices)
+            .filter(|(name, _)| !name.is_empty())
+            .filter_map(|(name, price)| Some((name, price.as_f64()?)))
+            .collect())
+    }
+}
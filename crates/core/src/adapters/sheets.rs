@@ -1,8 +1,14 @@
 pub mod auth;
+pub mod backend;
 pub mod cell_range;
 pub mod flatten_double_vec;
 pub mod http_client;
+pub mod number_format;
+pub mod retry;
+pub mod schema_bootstrapper;
+pub mod schema_validator;
 pub mod spreadsheet_manager;
 pub mod spreadsheet_read;
 pub mod spreadsheet_write;
+pub mod spreadsheet_write_batch;
 pub mod value_range_factory;
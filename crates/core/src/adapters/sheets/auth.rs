@@ -1,24 +1,108 @@
+use error_stack::ResultExt;
 use google_sheets4::oauth2::{self, authenticator::Authenticator};
 use google_sheets4::{hyper, hyper_rustls};
+use thiserror::Error;
 
-use crate::adapters::config::sheets_config::SpreadsheetConfig;
+use crate::adapters::config::sheets_config::{SheetsAuthConfig, SpreadsheetConfig};
 
+type Connector = hyper_rustls::HttpsConnector<hyper::client::HttpConnector>;
+
+#[derive(Error, Debug)]
+pub enum SheetsAuthError {
+    #[error("Failed to read the service account key")]
+    ServiceAccountKeyError,
+    #[error("Failed to read the OAuth client secret")]
+    ClientSecretError,
+    #[error("Failed to create the authenticator")]
+    AuthenticatorError,
+}
+
+/// Authenticator for the mode configured in `[sheets.auth]`
 pub async fn auth(
     config: &SpreadsheetConfig,
-    client: hyper::Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>>,
-) -> Authenticator<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>> {
-    let priv_key_path = config.priv_key.as_ref();
-    let secret: oauth2::ServiceAccountKey = oauth2::read_service_account_key(priv_key_path)
-        .await
-        .unwrap_or_else(|err| {
-            panic!(
-                "Google Sheets update failed: could not read service account private key at '{}'. Reason: {}. Please provide a valid service account private key to enable Google Sheets integration.",
-                priv_key_path, err
-            )
-        });
+    client: hyper::Client<Connector>,
+) -> error_stack::Result<Authenticator<Connector>, SheetsAuthError> {
+    match &config.auth {
+        SheetsAuthConfig::ServiceAccountFile => {
+            let priv_key_path = config.priv_key.as_ref();
+            let secret = oauth2::read_service_account_key(priv_key_path)
+                .await
+                .change_context(SheetsAuthError::ServiceAccountKeyError)
+                .attach_printable_lazy(|| {
+                    format!(
+                        "Could not read the service account private key at '{}'",
+                        priv_key_path
+                    )
+                })?;
+
+            service_account_auth(secret, client).await
+        }
+        SheetsAuthConfig::ServiceAccountEnv { var } => {
+            let key = std::env::var(var.as_ref())
+                .change_context(SheetsAuthError::ServiceAccountKeyError)
+                .attach_printable_lazy(|| format!("Environment variable {} is not set", var))?;
+            let secret = oauth2::parse_service_account_key(key)
+                .change_context(SheetsAuthError::ServiceAccountKeyError)
+                .attach_printable_lazy(|| {
+                    format!("Environment variable {} is not a service account key", var)
+                })?;
+
+            service_account_auth(secret, client).await
+        }
+        SheetsAuthConfig::InstalledApp {
+            client_secret,
+            token_file,
+        } => installed_app_auth(client_secret, token_file, client).await,
+    }
+}
 
-    oauth2::ServiceAccountAuthenticator::with_client(secret, client.clone())
+async fn service_account_auth(
+    secret: oauth2::ServiceAccountKey,
+    client: hyper::Client<Connector>,
+) -> error_stack::Result<Authenticator<Connector>, SheetsAuthError> {
+    oauth2::ServiceAccountAuthenticator::with_client(secret, client)
         .build()
         .await
-        .expect("could not create an authenticator")
+        .change_context(SheetsAuthError::AuthenticatorError)
+}
+
+/// Signs in with a Google account: the first run prints a URL to approve access in the
+/// browser, later runs reuse (and refresh) the tokens kept in `token_file`
+async fn installed_app_auth(
+    client_secret: &str,
+    token_file: &str,
+    client: hyper::Client<Connector>,
+) -> error_stack::Result<Authenticator<Connector>, SheetsAuthError> {
+    let secret = oauth2::read_application_secret(client_secret)
+        .await
+        .change_context(SheetsAuthError::ClientSecretError)
+        .attach_printable_lazy(|| {
+            format!(
+                "Could not read the OAuth client secret at '{}'",
+                client_secret
+            )
+        })?;
+
+    if let Some(dir) = std::path::Path::new(token_file)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .change_context(SheetsAuthError::AuthenticatorError)
+            .attach_printable_lazy(|| {
+                format!("Could not create the token directory {}", dir.display())
+            })?;
+    }
+
+    oauth2::InstalledFlowAuthenticator::with_client(
+        secret,
+        oauth2::InstalledFlowReturnMethod::HTTPRedirect,
+        client,
+    )
+    .persist_tokens_to_disk(token_file)
+    .build()
+    .await
+    .change_context(SheetsAuthError::AuthenticatorError)
+    .attach_printable_lazy(|| format!("Token file: {}", token_file))
 }
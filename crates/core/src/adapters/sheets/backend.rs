@@ -0,0 +1,103 @@
+pub mod dry_run;
+pub mod google;
+pub mod in_memory;
+
+use google_sheets4::api::{Request, Spreadsheet, ValueRange};
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SheetsBackendError {
+    #[error("Failed to fetch spreadsheet metadata")]
+    FetchSpreadsheetError,
+    #[error("Failed to read values")]
+    ReadValuesError,
+    #[error("Failed to write values")]
+    WriteValuesError,
+    #[error("Failed to update the spreadsheet structure")]
+    BatchUpdateError,
+    #[error("Failed to authenticate with Google Sheets")]
+    AuthenticationError,
+}
+
+/// Why a backend call failed, attached to its [`SheetsBackendError`] so callers can tell
+/// transient failures apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureCause {
+    /// The API answered with this HTTP status
+    Status(u16),
+    /// No response came back: connection reset, timeout, ...
+    Transport,
+}
+
+impl FailureCause {
+    /// The API refused the request because of its quota, so it was not applied
+    pub fn is_quota_exceeded(&self) -> bool {
+        *self == FailureCause::Status(429)
+    }
+
+    /// Whether sending the request again may succeed. Server errors and dropped connections
+    /// leave it unknown whether the request was applied, so only `idempotent` ones are resent
+    /// then.
+    pub fn is_retryable(&self, idempotent: bool) -> bool {
+        match self {
+            FailureCause::Status(status) if (500..600).contains(status) => idempotent,
+            FailureCause::Status(_) => self.is_quota_exceeded(),
+            FailureCause::Transport => idempotent,
+        }
+    }
+}
+
+/// How the values read from the spreadsheet are rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueRender {
+    /// As shown on the sheet, e.g. `$1,234.50`
+    #[default]
+    Formatted,
+    /// As stored: numbers as numbers, dates as serial numbers, e.g. `1234.5`
+    Unformatted,
+}
+
+impl ValueRender {
+    /// `valueRenderOption` of the Sheets API
+    pub fn as_api_option(&self) -> &'static str {
+        match self {
+            ValueRender::Formatted => "FORMATTED_VALUE",
+            ValueRender::Unformatted => "UNFORMATTED_VALUE",
+        }
+    }
+}
+
+/// Raw Sheets API operations used by [`SpreadsheetManager`](super::spreadsheet_manager::SpreadsheetManager),
+/// so the spreadsheet it talks to can be swapped (e.g. for a dry run)
+#[async_trait::async_trait]
+pub trait SheetsBackend: std::fmt::Debug + Send + Sync {
+    /// Spreadsheet metadata: sheets and named ranges, without cell data
+    async fn get_spreadsheet(&self) -> error_stack::Result<Spreadsheet, SheetsBackendError>;
+
+    async fn get_values(
+        &self,
+        range: &str,
+        render: ValueRender,
+    ) -> error_stack::Result<ValueRange, SheetsBackendError>;
+
+    /// Writes every value range, each carrying its own A1 `range`, in a single request
+    async fn update_values(
+        &self,
+        value_ranges: Vec<ValueRange>,
+    ) -> error_stack::Result<(), SheetsBackendError>;
+
+    /// Adds `rows` right after the last row of the table found in `range`
+    async fn append_values(
+        &self,
+        range: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> error_stack::Result<(), SheetsBackendError>;
+
+    /// Applies structural changes (new sheets, named ranges, ...) atomically: either every
+    /// request succeeds or none is applied
+    async fn batch_update(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SheetsBackendError>;
+}
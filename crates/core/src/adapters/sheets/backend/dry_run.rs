@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use google_sheets4::api::{Request, Spreadsheet, ValueRange};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::OnceCell;
+use tracing::instrument;
+
+use crate::adapters::sheets::cell_range::CellRange;
+use crate::domain::sheets::a1_notation::ToA1Notation;
+
+use super::{SheetsBackend, SheetsBackendError, ValueRender};
+
+/// A write a routine would have made
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordedWrite {
+    /// Named range the write covers exactly, if any
+    pub named_range: Option<String>,
+    pub range: String,
+    /// Values currently in the range; empty when they could not be read, or for appends
+    pub old_values: Vec<Vec<Value>>,
+    pub new_values: Vec<Vec<Value>>,
+    /// Whether `new_values` would be added after the table in `range` instead of replacing it
+    pub append: bool,
+}
+
+/// Collects the writes of dry-run backends until they are exported
+#[derive(Debug, Default)]
+pub struct DryRunRecorder {
+    writes: Mutex<Vec<RecordedWrite>>,
+}
+
+impl DryRunRecorder {
+    pub fn record(&self, write: RecordedWrite) {
+        self.writes.lock().expect("Lock poisoned").push(write);
+    }
+
+    pub fn take(&self) -> Vec<RecordedWrite> {
+        std::mem::take(&mut *self.writes.lock().expect("Lock poisoned"))
+    }
+
+    /// Takes the recorded writes and prints them as JSON, or writes them to `output`.
+    /// Returns the number of writes exported.
+    pub fn export(&self, output: Option<&str>) -> std::io::Result<usize> {
+        let writes = self.take();
+        let json = serde_json::to_string_pretty(&writes)?;
+
+        match output {
+            Some(path) => std::fs::write(path, json)?,
+            None => println!("{}", json),
+        }
+
+        Ok(writes.len())
+    }
+}
+
+/// Forwards reads to the wrapped backend, but only records writes (along with the values they
+/// would replace) instead of sending them
+#[derive(Debug)]
+pub struct DryRunBackend {
+    inner: Arc<dyn SheetsBackend>,
+    recorder: Arc<DryRunRecorder>,
+    /// Named range of each resolved A1 range, used to label the recorded writes
+    named_ranges: OnceCell<HashMap<String, String>>,
+}
+
+impl DryRunBackend {
+    pub fn new(inner: Arc<dyn SheetsBackend>, recorder: Arc<DryRunRecorder>) -> Self {
+        Self {
+            inner,
+            recorder,
+            named_ranges: OnceCell::new(),
+        }
+    }
+
+    async fn named_range_of(&self, range: &str) -> Option<String> {
+        let named_ranges = self
+            .named_ranges
+            .get_or_init(|| async {
+                match self.inner.get_spreadsheet().await {
+                    Ok(spreadsheet) => named_ranges_by_a1(&spreadsheet),
+                    Err(error) => {
+                        tracing::warn!("Dry run: could not fetch named ranges: {:?}", error);
+                        HashMap::new()
+                    }
+                }
+            })
+            .await;
+
+        named_ranges.get(range).cloned()
+    }
+}
+
+/// Resolves every named range the same way the spreadsheet manager does before writing to it
+fn named_ranges_by_a1(spreadsheet: &Spreadsheet) -> HashMap<String, String> {
+    let sheet_titles = spreadsheet
+        .sheets
+        .iter()
+        .flatten()
+        .filter_map(|sheet| sheet.properties.as_ref())
+        .filter_map(|properties| Some((properties.sheet_id?, properties.title.clone()?)))
+        .collect::<HashMap<_, _>>();
+
+    spreadsheet
+        .named_ranges
+        .iter()
+        .flatten()
+        .filter_map(|named_range| {
+            let grid_range = named_range.range.as_ref()?;
+            let sheet_title = sheet_titles.get(&grid_range.sheet_id.unwrap_or(0)).cloned();
+            let cell_range = CellRange::try_from_grid_range(grid_range, sheet_title).ok()?;
+            let a1 = cell_range.to_a1_notation(cell_range.sheet_title.as_deref());
+            Some((a1.0, named_range.name.clone()?))
+        })
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl SheetsBackend for DryRunBackend {
+    async fn get_spreadsheet(&self) -> error_stack::Result<Spreadsheet, SheetsBackendError> {
+        self.inner.get_spreadsheet().await
+    }
+
+    async fn get_values(
+        &self,
+        range: &str,
+        render: ValueRender,
+    ) -> error_stack::Result<ValueRange, SheetsBackendError> {
+        self.inner.get_values(range, render).await
+    }
+
+    #[instrument(skip(self, value_ranges), fields(count = value_ranges.len()))]
+    async fn update_values(
+        &self,
+        value_ranges: Vec<ValueRange>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        for value_range in value_ranges {
+            let range = value_range.range.unwrap_or_default();
+
+            let old_values = match self.inner.get_values(&range, ValueRender::Formatted).await {
+                Ok(current) => current.values.unwrap_or_default(),
+                Err(error) => {
+                    tracing::warn!("Dry run: could not read {}: {:?}", range, error);
+                    Vec::new()
+                }
+            };
+
+            tracing::info!("Dry run: skipping write to {}", range);
+            self.recorder.record(RecordedWrite {
+                named_range: self.named_range_of(&range).await,
+                range,
+                old_values,
+                new_values: value_range.values.unwrap_or_default(),
+                append: false,
+            });
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, rows), fields(count = rows.len()))]
+    async fn append_values(
+        &self,
+        range: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        tracing::info!("Dry run: skipping append to {}", range);
+        self.recorder.record(RecordedWrite {
+            named_range: self.named_range_of(range).await,
+            range: range.to_owned(),
+            old_values: Vec::new(),
+            new_values: rows,
+            append: true,
+        });
+
+        Ok(())
+    }
+
+    /// Records each request as a write to the pseudo-range `batchUpdate`, its JSON body as the
+    /// only value
+    #[instrument(skip(self, requests), fields(count = requests.len()))]
+    async fn batch_update(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        tracing::info!("Dry run: skipping {} spreadsheet updates", requests.len());
+        for request in requests {
+            self.recorder.record(RecordedWrite {
+                named_range: None,
+                range: "batchUpdate".to_owned(),
+                old_values: Vec::new(),
+                new_values: vec![vec![serde_json::to_value(&request).unwrap_or_default()]],
+                append: false,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use google_sheets4::api::{GridRange, NamedRange, Sheet, SheetProperties};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeBackend;
+
+    #[async_trait::async_trait]
+    impl SheetsBackend for FakeBackend {
+        async fn get_spreadsheet(&self) -> error_stack::Result<Spreadsheet, SheetsBackendError> {
+            Ok(Spreadsheet {
+                sheets: Some(vec![Sheet {
+                    properties: Some(SheetProperties {
+                        sheet_id: Some(0),
+                        title: Some("Tokens".to_owned()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }]),
+                named_ranges: Some(vec![NamedRange {
+                    name: Some("Tokens__vPrices".to_owned()),
+                    range: Some(GridRange {
+                        sheet_id: Some(0),
+                        start_row_index: Some(1),
+                        end_row_index: Some(3),
+                        start_column_index: Some(2),
+                        end_column_index: Some(3),
+                    }),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            })
+        }
+
+        async fn get_values(
+            &self,
+            range: &str,
+            _render: ValueRender,
+        ) -> error_stack::Result<ValueRange, SheetsBackendError> {
+            Ok(ValueRange {
+                range: Some(range.to_owned()),
+                values: Some(vec![vec![json!("$1")], vec![json!("$2")]]),
+                major_dimension: None,
+            })
+        }
+
+        async fn update_values(
+            &self,
+            _value_ranges: Vec<ValueRange>,
+        ) -> error_stack::Result<(), SheetsBackendError> {
+            panic!("Dry run must not write");
+        }
+
+        async fn append_values(
+            &self,
+            _range: &str,
+            _rows: Vec<Vec<Value>>,
+        ) -> error_stack::Result<(), SheetsBackendError> {
+            panic!("Dry run must not write");
+        }
+
+        async fn batch_update(
+            &self,
+            _requests: Vec<Request>,
+        ) -> error_stack::Result<(), SheetsBackendError> {
+            panic!("Dry run must not write");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_writes_instead_of_sending_them() {
+        let recorder = Arc::new(DryRunRecorder::default());
+        let backend = DryRunBackend::new(Arc::new(FakeBackend), Arc::clone(&recorder));
+
+        backend
+            .update_values(vec![ValueRange {
+                range: Some("'Tokens'!C2:C3".to_owned()),
+                values: Some(vec![vec![json!("$3")], vec![json!("$4")]]),
+                major_dimension: None,
+            }])
+            .await
+            .unwrap();
+
+        let writes = recorder.take();
+        assert_eq!(
+            writes,
+            vec![RecordedWrite {
+                named_range: Some("Tokens__vPrices".to_owned()),
+                range: "'Tokens'!C2:C3".to_owned(),
+                old_values: vec![vec![json!("$1")], vec![json!("$2")]],
+                new_values: vec![vec![json!("$3")], vec![json!("$4")]],
+                append: false,
+            }]
+        );
+        assert!(recorder.take().is_empty());
+    }
+}
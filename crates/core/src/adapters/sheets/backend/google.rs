@@ -0,0 +1,174 @@
+use error_stack::{Report, ResultExt};
+use google_sheets4::api::{
+    BatchUpdateSpreadsheetRequest, BatchUpdateValuesRequest, Request, Spreadsheet, ValueRange,
+};
+use google_sheets4::Sheets;
+use serde_json::Value;
+use tracing::instrument;
+
+use crate::adapters::config::sheets_config::SpreadsheetConfig;
+use crate::adapters::sheets::{auth, http_client};
+
+use super::{FailureCause, SheetsBackend, SheetsBackendError, ValueRender};
+
+type Connector =
+    google_sheets4::hyper_rustls::HttpsConnector<google_sheets4::hyper::client::HttpConnector>;
+
+/// The real Google Sheets API, authenticated as configured in `[sheets.auth]`
+pub struct GoogleSheetsBackend {
+    hub: Sheets<Connector>,
+    spreadsheet_id: Box<str>,
+}
+
+impl std::fmt::Debug for GoogleSheetsBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GoogleSheetsBackend")
+            .field("spreadsheet_id", &self.spreadsheet_id)
+            .finish()
+    }
+}
+
+impl GoogleSheetsBackend {
+    #[instrument(name = "GoogleSheetsBackend::new")]
+    pub async fn new(config: &SpreadsheetConfig) -> error_stack::Result<Self, SheetsBackendError> {
+        let client = http_client::http_client();
+        let auth = auth::auth(config, client.clone())
+            .await
+            .change_context(SheetsBackendError::AuthenticationError)?;
+
+        Ok(Self {
+            hub: Sheets::new(client, auth),
+            spreadsheet_id: config.spreadsheet_id.clone(),
+        })
+    }
+}
+
+/// `None` for failures that happen before a request is sent, e.g. a missing token
+fn failure_cause(error: &google_sheets4::Error) -> Option<FailureCause> {
+    use google_sheets4::Error;
+
+    match error {
+        Error::Failure(response) => Some(FailureCause::Status(response.status().as_u16())),
+        Error::BadRequest(body) => body["error"]["code"]
+            .as_u64()
+            .and_then(|code| u16::try_from(code).ok())
+            .map(FailureCause::Status),
+        Error::HttpError(_) | Error::Io(_) => Some(FailureCause::Transport),
+        _ => None,
+    }
+}
+
+fn api_error(
+    error: google_sheets4::Error,
+    context: SheetsBackendError,
+) -> Report<SheetsBackendError> {
+    let cause = failure_cause(&error);
+    let report = Report::new(error).change_context(context);
+
+    match cause {
+        Some(cause) => report.attach(cause),
+        None => report,
+    }
+}
+
+#[async_trait::async_trait]
+impl SheetsBackend for GoogleSheetsBackend {
+    #[instrument]
+    async fn get_spreadsheet(&self) -> error_stack::Result<Spreadsheet, SheetsBackendError> {
+        self.hub
+            .spreadsheets()
+            .get(&self.spreadsheet_id)
+            .doit()
+            .await
+            .map(|(_, spreadsheet)| spreadsheet)
+            .map_err(|error| api_error(error, SheetsBackendError::FetchSpreadsheetError))
+    }
+
+    #[instrument]
+    async fn get_values(
+        &self,
+        range: &str,
+        render: ValueRender,
+    ) -> error_stack::Result<ValueRange, SheetsBackendError> {
+        self.hub
+            .spreadsheets()
+            .values_get(&self.spreadsheet_id, range)
+            .value_render_option(render.as_api_option())
+            .doit()
+            .await
+            .map(|(_, value_range)| value_range)
+            .map_err(|error| api_error(error, SheetsBackendError::ReadValuesError))
+            .attach_printable_lazy(|| format!("Range: {}", range))
+    }
+
+    #[instrument(skip(value_ranges), fields(count = value_ranges.len()))]
+    async fn update_values(
+        &self,
+        value_ranges: Vec<ValueRange>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        let ranges = value_ranges
+            .iter()
+            .filter_map(|value_range| value_range.range.clone())
+            .collect::<Vec<_>>();
+
+        let request = BatchUpdateValuesRequest {
+            data: Some(value_ranges),
+            value_input_option: Some("USER_ENTERED".to_owned()),
+            ..Default::default()
+        };
+
+        self.hub
+            .spreadsheets()
+            .values_batch_update(request, &self.spreadsheet_id)
+            .doit()
+            .await
+            .map(|_| ())
+            .map_err(|error| api_error(error, SheetsBackendError::WriteValuesError))
+            .attach_printable_lazy(|| format!("Ranges: {:?}", ranges))
+    }
+
+    #[instrument(skip(rows), fields(count = rows.len()))]
+    async fn append_values(
+        &self,
+        range: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        let value_range = ValueRange {
+            range: Some(range.to_owned()),
+            values: Some(rows),
+            ..Default::default()
+        };
+
+        self.hub
+            .spreadsheets()
+            .values_append(value_range, &self.spreadsheet_id, range)
+            .value_input_option("USER_ENTERED")
+            .insert_data_option("INSERT_ROWS")
+            .doit()
+            .await
+            .map(|_| ())
+            .map_err(|error| api_error(error, SheetsBackendError::WriteValuesError))
+            .attach_printable_lazy(|| format!("Appending to range: {}", range))
+    }
+
+    #[instrument(skip(requests), fields(count = requests.len()))]
+    async fn batch_update(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        let count = requests.len();
+        let request = BatchUpdateSpreadsheetRequest {
+            requests: Some(requests),
+            ..Default::default()
+        };
+
+        self.hub
+            .spreadsheets()
+            .batch_update(request, &self.spreadsheet_id)
+            .doit()
+            .await
+            .map(|_| ())
+            .map_err(|error| api_error(error, SheetsBackendError::BatchUpdateError))
+            .attach_printable_lazy(|| format!("Requests: {}", count))
+    }
+}
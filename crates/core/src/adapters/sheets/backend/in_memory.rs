@@ -0,0 +1,614 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use error_stack::{report, ResultExt};
+use google_sheets4::api::{
+    GridProperties, GridRange, NamedRange, Request, Sheet, SheetProperties, Spreadsheet, ValueRange,
+};
+use serde_json::Value;
+
+use crate::adapters::config::sheets_config::SpreadsheetConfig;
+use crate::adapters::sheets::retry::RetryPolicy;
+use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
+use crate::domain::sheets::a1_notation::{generic_a1_notation_split, A1Notation};
+use crate::domain::sheets::cell_value::{date_time_to_serial, date_to_serial};
+use crate::domain::sheets::column::Column;
+
+use super::{SheetsBackend, SheetsBackendError, ValueRender};
+
+/// Grid of a new sheet, as created from the Sheets UI
+const DEFAULT_ROW_COUNT: i32 = 1000;
+const DEFAULT_COLUMN_COUNT: i32 = 26;
+
+#[derive(Debug, Clone)]
+struct SheetData {
+    properties: SheetProperties,
+    /// Non-empty cells by (row, column) index
+    cells: BTreeMap<(u32, u32), Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    sheets: Vec<SheetData>,
+    named_ranges: Vec<NamedRange>,
+}
+
+impl State {
+    fn sheet(&self, title: &str) -> Option<&SheetData> {
+        self.sheets
+            .iter()
+            .find(|sheet| sheet.properties.title.as_deref() == Some(title))
+    }
+
+    fn sheet_mut(&mut self, title: &str) -> Option<&mut SheetData> {
+        self.sheets
+            .iter_mut()
+            .find(|sheet| sheet.properties.title.as_deref() == Some(title))
+    }
+
+    fn add_sheet(&mut self, properties: SheetProperties) -> Result<(), String> {
+        let title = properties.title.clone().unwrap_or_default();
+        if self.sheet(&title).is_some() {
+            return Err(format!(
+                "A sheet with the name \"{}\" already exists",
+                title
+            ));
+        }
+
+        let sheet_id = properties.sheet_id.unwrap_or_else(|| {
+            self.sheets
+                .iter()
+                .filter_map(|sheet| sheet.properties.sheet_id)
+                .max()
+                .map_or(0, |max| max + 1)
+        });
+        self.sheets.push(SheetData {
+            properties: SheetProperties {
+                sheet_id: Some(sheet_id),
+                title: Some(title),
+                grid_properties: properties.grid_properties.or(Some(GridProperties {
+                    row_count: Some(DEFAULT_ROW_COUNT),
+                    column_count: Some(DEFAULT_COLUMN_COUNT),
+                    ..Default::default()
+                })),
+                ..properties
+            },
+            cells: BTreeMap::new(),
+        });
+        Ok(())
+    }
+
+    fn apply(&mut self, request: Request) -> Result<(), String> {
+        if let Some(add_sheet) = request.add_sheet {
+            return self.add_sheet(add_sheet.properties.unwrap_or_default());
+        }
+
+        if let Some(add_named_range) = request.add_named_range {
+            let named_range = add_named_range.named_range.unwrap_or_default();
+            if self
+                .named_ranges
+                .iter()
+                .any(|existing| existing.name == named_range.name)
+            {
+                return Err(format!(
+                    "Named range {:?} already exists",
+                    named_range.name.unwrap_or_default()
+                ));
+            }
+            self.named_ranges.push(NamedRange {
+                named_range_id: named_range
+                    .named_range_id
+                    .clone()
+                    .or_else(|| Some(format!("in-memory-{}", self.named_ranges.len()))),
+                ..named_range
+            });
+            return Ok(());
+        }
+
+        if let Some(update_named_range) = request.update_named_range {
+            let update = update_named_range.named_range.unwrap_or_default();
+            let existing = self
+                .named_ranges
+                .iter_mut()
+                .find(|existing| existing.named_range_id == update.named_range_id)
+                .ok_or_else(|| format!("No named range with id {:?}", update.named_range_id))?;
+            existing.range = update.range;
+            return Ok(());
+        }
+
+        if let Some(update_sheet_properties) = request.update_sheet_properties {
+            let update = update_sheet_properties.properties.unwrap_or_default();
+            let sheet = self
+                .sheets
+                .iter_mut()
+                .find(|sheet| sheet.properties.sheet_id == update.sheet_id)
+                .ok_or_else(|| format!("No sheet with id {:?}", update.sheet_id))?;
+            // Only grid sizes are ever updated
+            if let Some(grid_properties) = update.grid_properties {
+                sheet.properties.grid_properties = Some(grid_properties);
+            }
+            return Ok(());
+        }
+
+        // Formats are not kept: values are always read back as entered
+        if request.repeat_cell.is_some() {
+            return Ok(());
+        }
+
+        Err(format!("Unsupported request: {:?}", request))
+    }
+}
+
+/// Bounds of an A1 range within its sheet: zero-based and inclusive, with no end row for
+/// whole-column ranges such as `A:F`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bounds {
+    sheet_title: String,
+    rows: (u32, Option<u32>),
+    columns: (u32, u32),
+}
+
+/// Column and optional row of one end of an A1 range, e.g. `AB12` or `AB`
+fn parse_corner(corner: &str) -> Option<(u32, Option<u32>)> {
+    let digits_at = corner
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(corner.len());
+    let (letters, digits) = corner.split_at(digits_at);
+
+    let column = Column::from_col_str(letters).ok()?.index();
+    let row = match digits {
+        "" => None,
+        digits => Some(digits.parse::<u32>().ok()?.checked_sub(1)?),
+    };
+    Some((column, row))
+}
+
+fn parse_bounds(range: &str) -> Option<Bounds> {
+    let parts = generic_a1_notation_split(&A1Notation(range.to_owned()));
+    let sheet_title = parts.sheet_title?.trim_matches('\'').to_owned();
+
+    let (start_column, start_row) = parse_corner(&parts.start)?;
+    let (end_column, end_row) = parse_corner(&parts.end)?;
+
+    Some(Bounds {
+        sheet_title,
+        rows: (start_row.unwrap_or(0), end_row),
+        columns: (start_column, end_column),
+    })
+}
+
+/// Stores a value the way `USER_ENTERED` input does: `'` keeps text as-is, numeric text becomes
+/// a number, ISO dates and date-times become their serial number, and formulas are kept as their
+/// text (they are not evaluated)
+fn entered_value(value: Value) -> Value {
+    match value {
+        Value::String(text) => match text.strip_prefix('\'') {
+            Some(text) => Value::String(text.to_owned()),
+            None => entered_number(&text)
+                .and_then(|number| serde_json::Number::from_f64(number).map(Value::Number))
+                .unwrap_or(Value::String(text)),
+        },
+        other => other,
+    }
+}
+
+fn entered_number(text: &str) -> Option<f64> {
+    text.parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(date_time_to_serial)
+        })
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .map(date_to_serial)
+        })
+}
+
+/// Numbers as text, roughly as shown by a sheet without a number format
+fn formatted_value(value: &Value) -> Value {
+    match value {
+        Value::Number(number) => Value::String(match number.as_f64() {
+            Some(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                format!("{}", number as i64)
+            }
+            _ => number.to_string(),
+        }),
+        Value::Bool(boolean) => Value::String(boolean.to_string().to_uppercase()),
+        other => other.clone(),
+    }
+}
+
+/// An in-process stand-in for the Sheets API, so routines can run end to end without a real
+/// spreadsheet. Values are stored as `USER_ENTERED` input would store them; formats are ignored
+/// and formulas are not evaluated.
+#[derive(Debug, Default)]
+pub struct InMemorySheetsBackend {
+    state: Mutex<State>,
+}
+
+impl InMemorySheetsBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every sheet and named range of `layout`, e.g. [`DEFAULT_LAYOUT`](crate::domain::sheets::ranges::DEFAULT_LAYOUT)
+    pub fn from_layout(layout: &[(&str, &str, &str)]) -> Self {
+        layout
+            .iter()
+            .fold(Self::new(), |backend, (name, sheet_title, range)| {
+                backend.with_named_range(name, &format!("'{}'!{}", sheet_title, range))
+            })
+    }
+
+    /// Adds a sheet, unless one with the same title exists
+    pub fn with_sheet(self, title: &str) -> Self {
+        {
+            let mut state = self.state.lock().expect("Lock poisoned");
+            if state.sheet(title).is_none() {
+                let _ = state.add_sheet(SheetProperties {
+                    title: Some(title.to_owned()),
+                    ..Default::default()
+                });
+            }
+        }
+        self
+    }
+
+    /// Adds a named range over an A1 range such as `'Tokens'!A2:A201`, and its sheet if missing.
+    /// Panics if the range is not bounded.
+    pub fn with_named_range(self, name: &str, range: &str) -> Self {
+        let bounds = parse_bounds(range)
+            .unwrap_or_else(|| panic!("Invalid range {} for named range {}", range, name));
+        let end_row = bounds
+            .rows
+            .1
+            .unwrap_or_else(|| panic!("Named range {} must have an end row", name));
+        let backend = self.with_sheet(&bounds.sheet_title);
+
+        {
+            let mut state = backend.state.lock().expect("Lock poisoned");
+            let sheet_id = state
+                .sheet(&bounds.sheet_title)
+                .and_then(|sheet| sheet.properties.sheet_id);
+            let index = |index: u32| i32::try_from(index).ok();
+            state
+                .apply(Request {
+                    add_named_range: Some(google_sheets4::api::AddNamedRangeRequest {
+                        named_range: Some(NamedRange {
+                            name: Some(name.to_owned()),
+                            range: Some(GridRange {
+                                sheet_id,
+                                start_row_index: index(bounds.rows.0),
+                                end_row_index: index(end_row + 1),
+                                start_column_index: index(bounds.columns.0),
+                                end_column_index: index(bounds.columns.1 + 1),
+                            }),
+                            ..Default::default()
+                        }),
+                    }),
+                    ..Default::default()
+                })
+                .unwrap_or_else(|error| panic!("{}", error));
+        }
+        backend
+    }
+
+    /// Enters `rows` starting at the top left corner of `range`, e.g. `'Tokens'!A2`. Panics if the
+    /// range is invalid.
+    pub fn with_values(self, range: &str, rows: Vec<Vec<Value>>) -> Self {
+        let bounds = parse_bounds(range).unwrap_or_else(|| panic!("Invalid range {}", range));
+        let backend = self.with_sheet(&bounds.sheet_title);
+
+        {
+            let mut state = backend.state.lock().expect("Lock poisoned");
+            let sheet = state
+                .sheet_mut(&bounds.sheet_title)
+                .expect("Sheet was just added");
+            write_rows(sheet, bounds.rows.0, bounds.columns.0, rows);
+        }
+        backend
+    }
+
+    /// Values of `range` as stored (numbers as numbers), without the trailing empty rows and
+    /// cells, the same as an `UNFORMATTED_VALUE` read. Panics if the range is invalid.
+    pub fn values(&self, range: &str) -> Vec<Vec<Value>> {
+        let bounds = parse_bounds(range).unwrap_or_else(|| panic!("Invalid range {}", range));
+        let state = self.state.lock().expect("Lock poisoned");
+
+        state
+            .sheet(&bounds.sheet_title)
+            .map(|sheet| read_rows(sheet, &bounds, ValueRender::Unformatted))
+            .unwrap_or_default()
+    }
+
+    /// Manager over this backend, not throttled since there is no quota to respect
+    pub fn spreadsheet_manager(self: &Arc<Self>, config: SpreadsheetConfig) -> SpreadsheetManager {
+        let retry = RetryPolicy::unthrottled(&config.retry);
+        SpreadsheetManager::with_backend(config, self.clone()).with_retry_policy(retry)
+    }
+}
+
+fn write_rows(sheet: &mut SheetData, start_row: u32, start_column: u32, rows: Vec<Vec<Value>>) {
+    for (row_offset, row) in rows.into_iter().enumerate() {
+        for (column_offset, value) in row.into_iter().enumerate() {
+            let position = (
+                start_row + row_offset as u32,
+                start_column + column_offset as u32,
+            );
+            match entered_value(value) {
+                // Null leaves the cell as it is
+                Value::Null => {}
+                Value::String(text) if text.is_empty() => {
+                    sheet.cells.remove(&position);
+                }
+                value => {
+                    sheet.cells.insert(position, value);
+                }
+            }
+        }
+    }
+}
+
+fn read_rows(sheet: &SheetData, bounds: &Bounds, render: ValueRender) -> Vec<Vec<Value>> {
+    let (start_row, start_column) = (bounds.rows.0, bounds.columns.0);
+    let end_row = bounds.rows.1.unwrap_or(u32::MAX);
+
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    for (&(row, column), value) in sheet.cells.range((start_row, 0)..) {
+        if row > end_row {
+            break;
+        }
+        if column < start_column || column > bounds.columns.1 {
+            continue;
+        }
+
+        let (row, column) = ((row - start_row) as usize, (column - start_column) as usize);
+        if rows.len() <= row {
+            rows.resize_with(row + 1, Vec::new);
+        }
+        if rows[row].len() <= column {
+            rows[row].resize(column + 1, Value::String(String::new()));
+        }
+        rows[row][column] = match render {
+            ValueRender::Formatted => formatted_value(value),
+            ValueRender::Unformatted => value.clone(),
+        };
+    }
+    rows
+}
+
+impl InMemorySheetsBackend {
+    fn bounds(
+        range: &str,
+        error: SheetsBackendError,
+    ) -> error_stack::Result<Bounds, SheetsBackendError> {
+        parse_bounds(range)
+            .ok_or(report!(error))
+            .attach_printable_lazy(|| format!("Unable to parse range: {}", range))
+    }
+}
+
+#[async_trait::async_trait]
+impl SheetsBackend for InMemorySheetsBackend {
+    async fn get_spreadsheet(&self) -> error_stack::Result<Spreadsheet, SheetsBackendError> {
+        let state = self.state.lock().expect("Lock poisoned");
+
+        Ok(Spreadsheet {
+            sheets: Some(
+                state
+                    .sheets
+                    .iter()
+                    .map(|sheet| Sheet {
+                        properties: Some(sheet.properties.clone()),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            named_ranges: Some(state.named_ranges.clone()),
+            ..Default::default()
+        })
+    }
+
+    async fn get_values(
+        &self,
+        range: &str,
+        render: ValueRender,
+    ) -> error_stack::Result<ValueRange, SheetsBackendError> {
+        let bounds = Self::bounds(range, SheetsBackendError::ReadValuesError)?;
+        let state = self.state.lock().expect("Lock poisoned");
+
+        let sheet = state
+            .sheet(&bounds.sheet_title)
+            .ok_or(report!(SheetsBackendError::ReadValuesError))
+            .attach_printable_lazy(|| format!("Unable to parse range: {}", range))?;
+        let rows = read_rows(sheet, &bounds, render);
+
+        Ok(ValueRange {
+            range: Some(range.to_owned()),
+            major_dimension: Some("ROWS".to_owned()),
+            // Like the API, an empty range has no values at all
+            values: (!rows.is_empty()).then_some(rows),
+        })
+    }
+
+    async fn update_values(
+        &self,
+        value_ranges: Vec<ValueRange>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        let mut state = self.state.lock().expect("Lock poisoned");
+        // Applied to a copy first, so a failing range leaves every other one unwritten too
+        let mut updated = state.clone();
+
+        for value_range in value_ranges {
+            let range = value_range.range.clone().unwrap_or_default();
+            let bounds = Self::bounds(&range, SheetsBackendError::WriteValuesError)?;
+
+            let mut rows = value_range.values.unwrap_or_default();
+            if value_range.major_dimension.as_deref() == Some("COLUMNS") {
+                let row_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+                rows = (0..row_count)
+                    .map(|row| {
+                        rows.iter()
+                            .map(|column| column.get(row).cloned().unwrap_or(Value::Null))
+                            .collect()
+                    })
+                    .collect();
+            }
+
+            let row_count = rows.len() as u32;
+            let column_count = rows.iter().map(Vec::len).max().unwrap_or(0) as u32;
+            let fits_rows = bounds
+                .rows
+                .1
+                .is_none_or(|end_row| bounds.rows.0 + row_count <= end_row + 1);
+            let fits_columns = bounds.columns.0 + column_count <= bounds.columns.1 + 1;
+            if !fits_rows || !fits_columns {
+                return Err(
+                    report!(SheetsBackendError::WriteValuesError).attach_printable(format!(
+                        "{} rows of {} columns do not fit in range {}",
+                        row_count, column_count, range
+                    )),
+                );
+            }
+
+            let sheet = updated
+                .sheet_mut(&bounds.sheet_title)
+                .ok_or(report!(SheetsBackendError::WriteValuesError))
+                .attach_printable_lazy(|| format!("Unable to parse range: {}", range))?;
+            write_rows(sheet, bounds.rows.0, bounds.columns.0, rows);
+        }
+
+        *state = updated;
+        Ok(())
+    }
+
+    async fn append_values(
+        &self,
+        range: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        let bounds = Self::bounds(range, SheetsBackendError::WriteValuesError)?;
+        let mut state = self.state.lock().expect("Lock poisoned");
+
+        let sheet = state
+            .sheet_mut(&bounds.sheet_title)
+            .ok_or(report!(SheetsBackendError::WriteValuesError))
+            .attach_printable_lazy(|| format!("Unable to parse range: {}", range))?;
+
+        // Right after the last row with data in the range's columns
+        let next_row = sheet
+            .cells
+            .keys()
+            .filter(|(row, column)| {
+                *row >= bounds.rows.0 && (bounds.columns.0..=bounds.columns.1).contains(column)
+            })
+            .map(|(row, _)| row + 1)
+            .max()
+            .unwrap_or(bounds.rows.0);
+        write_rows(sheet, next_row, bounds.columns.0, rows);
+
+        Ok(())
+    }
+
+    async fn batch_update(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SheetsBackendError> {
+        let mut state = self.state.lock().expect("Lock poisoned");
+        let mut updated = state.clone();
+
+        for request in requests {
+            updated.apply(request).map_err(|error| {
+                report!(SheetsBackendError::BatchUpdateError).attach_printable(error)
+            })?;
+        }
+
+        *state = updated;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_bounds() {
+        assert_eq!(
+            parse_bounds("'Tokens'!C2:D201"),
+            Some(Bounds {
+                sheet_title: "Tokens".to_owned(),
+                rows: (1, Some(200)),
+                columns: (2, 3),
+            })
+        );
+        assert_eq!(
+            parse_bounds("History!A:F").map(|bounds| (bounds.rows, bounds.columns)),
+            Some(((0, None), (0, 5)))
+        );
+        assert_eq!(
+            parse_bounds("'Airdrops'!A2").map(|bounds| (bounds.rows, bounds.columns)),
+            Some(((1, Some(1)), (0, 0)))
+        );
+        assert_eq!(parse_bounds("A1:B2"), None);
+    }
+
+    #[tokio::test]
+    async fn test_values_round_trip_as_user_entered() {
+        let backend = InMemorySheetsBackend::new().with_named_range("Prices", "'Tokens'!C2:C4");
+
+        backend
+            .update_values(vec![ValueRange {
+                range: Some("'Tokens'!C2:C4".to_owned()),
+                values: Some(vec![
+                    vec![json!(1.5)],
+                    vec![json!("'007")],
+                    vec![json!("42")],
+                ]),
+                major_dimension: Some("ROWS".to_owned()),
+            }])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            backend.values("'Tokens'!C2:C4"),
+            vec![vec![json!(1.5)], vec![json!("007")], vec![json!(42.0)]]
+        );
+        let formatted = backend
+            .get_values("'Tokens'!C2:C4", ValueRender::Formatted)
+            .await
+            .unwrap();
+        assert_eq!(
+            formatted.values,
+            Some(vec![
+                vec![json!("1.5")],
+                vec![json!("007")],
+                vec![json!("42")]
+            ])
+        );
+
+        // Does not fit: nothing is written
+        assert!(backend
+            .update_values(vec![ValueRange {
+                range: Some("'Tokens'!C2:C3".to_owned()),
+                values: Some(vec![vec![json!("")]; 3]),
+                major_dimension: None,
+            }])
+            .await
+            .is_err());
+        assert_eq!(backend.values("'Tokens'!C2:C4").len(), 3);
+
+        backend
+            .append_values("'Tokens'!C:C", vec![vec![json!(7)]])
+            .await
+            .unwrap();
+        assert_eq!(backend.values("'Tokens'!C5"), vec![vec![json!(7)]]);
+    }
+}
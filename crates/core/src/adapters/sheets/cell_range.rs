@@ -55,6 +55,23 @@ impl CellRange {
     pub async fn try_from_grid_range_with_sheet_manager(
         grid_range: GridRange,
         spreadsheet_manager: &SpreadsheetManager,
+    ) -> error_stack::Result<Self, CellRangeParseError> {
+        let sheet_title = match grid_range.sheet_id {
+            None => None,
+            Some(sheet_id) => spreadsheet_manager
+                .get_sheet_title(sheet_id)
+                .await
+                .map_err(|error| CellRangeParseError::GetSheetTitleError(error.to_string()))?
+                .into(),
+        };
+
+        Self::try_from_grid_range(&grid_range, sheet_title)
+    }
+
+    /// Same as [`Self::try_from_grid_range_with_sheet_manager`], with the sheet title already known
+    pub fn try_from_grid_range(
+        grid_range: &GridRange,
+        sheet_title: Option<String>,
     ) -> error_stack::Result<Self, CellRangeParseError> {
         let (start_column_index, end_column_index, start_row_index, end_row_index) = (
             CellRange::convert_index(grid_range.start_column_index, "start_column")?,
@@ -74,15 +91,6 @@ impl CellRange {
             },
         );
 
-        let sheet_title = match grid_range.sheet_id {
-            None => None,
-            Some(sheet_id) => spreadsheet_manager
-                .get_sheet_title(sheet_id)
-                .await
-                .map_err(|error| CellRangeParseError::GetSheetTitleError(error.to_string()))?
-                .into(),
-        };
-
         Ok(CellRange {
             start,
             end,
@@ -91,6 +99,21 @@ impl CellRange {
     }
 }
 
+impl CellRange {
+    /// Inverse of [`CellRange::try_from_grid_range`]: end indexes are exclusive
+    pub fn to_grid_range(&self, sheet_id: i32) -> GridRange {
+        let index = |index: u32| i32::try_from(index).unwrap_or(i32::MAX);
+
+        GridRange {
+            sheet_id: Some(sheet_id),
+            start_row_index: Some(index(self.start.row.index())),
+            end_row_index: Some(index(self.end.row.index() + 1)),
+            start_column_index: Some(index(self.start.col.index())),
+            end_column_index: Some(index(self.end.col.index() + 1)),
+        }
+    }
+}
+
 impl ToA1Notation for CellRange {
     fn to_a1_notation(&self, sheet_name: Option<&str>) -> A1Notation {
         let start = self.start.to_a1_notation(sheet_name);
@@ -0,0 +1,105 @@
+use google_sheets4::api::{
+    CellData, CellFormat, GridRange, NumberFormat, RepeatCellRequest, Request,
+};
+
+use crate::domain::sheets::cell_value::{self, CellValue};
+
+/// Requests giving each cell of `columns`, written from the top-left corner of `grid_range`, the
+/// number format of its value. Consecutive cells sharing a format are formatted at once; cells
+/// without one keep theirs.
+pub fn number_format_requests(grid_range: &GridRange, columns: &[&[CellValue]]) -> Vec<Request> {
+    let (sheet_id, start_row, start_column) = (
+        grid_range.sheet_id,
+        grid_range.start_row_index.unwrap_or(0),
+        grid_range.start_column_index.unwrap_or(0),
+    );
+
+    let mut requests = Vec::new();
+    for (column_offset, column) in (0..).zip(columns.iter()) {
+        let mut runs: Vec<(i32, i32, cell_value::NumberFormat)> = Vec::new();
+        for (row_offset, cell) in (0..).zip(column.iter()) {
+            let Some(format) = cell.number_format() else {
+                continue;
+            };
+            match runs.last_mut() {
+                Some((_, end, last)) if *end == row_offset && *last == format => *end += 1,
+                _ => runs.push((row_offset, row_offset + 1, format)),
+            }
+        }
+
+        requests.extend(runs.into_iter().map(|(start, end, format)| Request {
+            repeat_cell: Some(RepeatCellRequest {
+                range: Some(GridRange {
+                    sheet_id,
+                    start_row_index: Some(start_row + start),
+                    end_row_index: Some(start_row + end),
+                    start_column_index: Some(start_column + column_offset),
+                    end_column_index: Some(start_column + column_offset + 1),
+                }),
+                cell: Some(CellData {
+                    user_entered_format: Some(CellFormat {
+                        number_format: Some(NumberFormat {
+                            type_: Some(format.kind.to_owned()),
+                            pattern: Some(format.pattern.to_owned()),
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                fields: Some(google_sheets4::FieldMask::new(&[
+                    "userEnteredFormat.numberFormat",
+                ])),
+            }),
+            ..Default::default()
+        }));
+    }
+
+    requests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consecutive_formats_are_merged() {
+        let grid_range = GridRange {
+            sheet_id: Some(3),
+            start_row_index: Some(1),
+            end_row_index: Some(10),
+            start_column_index: Some(2),
+            end_column_index: Some(3),
+        };
+        let column = [
+            CellValue::Currency(1.0),
+            CellValue::Currency(2.0),
+            CellValue::from("+1 more"),
+            CellValue::Currency(3.0),
+            CellValue::Percent(0.5),
+        ];
+
+        let ranges = number_format_requests(&grid_range, &[&column])
+            .into_iter()
+            .map(|request| {
+                let repeat_cell = request.repeat_cell.unwrap();
+                let range = repeat_cell.range.unwrap();
+                let format = repeat_cell.cell.unwrap().user_entered_format.unwrap();
+                (
+                    (range.start_row_index, range.end_row_index),
+                    range.start_column_index,
+                    format.number_format.unwrap().type_,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let currency = Some("CURRENCY".to_owned());
+        assert_eq!(
+            ranges,
+            vec![
+                ((Some(1), Some(3)), Some(2), currency.clone()),
+                ((Some(4), Some(5)), Some(2), currency),
+                ((Some(5), Some(6)), Some(2), Some("PERCENT".to_owned())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::adapters::config::sheets_config::RetryConfig;
+use crate::adapters::http::rate_limiter::{TokenBucket, RATE_LIMITERS};
+
+use super::backend::{FailureCause, SheetsBackendError};
+
+/// The Sheets API quota is per user, so every call shares one bucket, whose rate
+/// `[[http.rate_limits]]` can override for this host
+const SHEETS_API_URL: &str = "https://sheets.googleapis.com";
+
+/// Paces the calls to the Sheets API and retries the ones that fail transiently
+#[derive(Debug)]
+pub struct RetryPolicy {
+    config: RetryConfig,
+    /// `None` when calls are not paced at all
+    quota: Option<Arc<TokenBucket>>,
+}
+
+impl RetryPolicy {
+    pub fn new(config: RetryConfig, quota: Arc<TokenBucket>) -> Self {
+        Self {
+            config,
+            quota: Some(quota),
+        }
+    }
+
+    /// Throttled by the process-wide Sheets API bucket
+    pub fn from_config(config: &RetryConfig) -> Self {
+        Self::new(config.clone(), RATE_LIMITERS.bucket(SHEETS_API_URL, None))
+    }
+
+    /// Retries without pacing, for backends with no quota such as the in-memory one
+    pub fn unthrottled(config: &RetryConfig) -> Self {
+        Self {
+            config: config.clone(),
+            quota: None,
+        }
+    }
+
+    /// Delay after the failed `attempt`: half of the exponential backoff, plus a random share of
+    /// the other half so that routines failing together do not retry in lockstep
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .config
+            .initial_backoff_ms
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.config.max_backoff_ms);
+        let half = ceiling / 2;
+
+        Duration::from_millis(half + rand::thread_rng().gen_range(0..=ceiling - half))
+    }
+
+    /// Runs `call` until it succeeds, fails for good or runs out of attempts, each attempt
+    /// waiting for the quota first. `target` (e.g. the range) and the attempt that failed are
+    /// attached to the error.
+    pub async fn run<T, Fut>(
+        &self,
+        target: &str,
+        idempotent: bool,
+        call: impl Fn() -> Fut,
+    ) -> error_stack::Result<T, SheetsBackendError>
+    where
+        Fut: Future<Output = error_stack::Result<T, SheetsBackendError>>,
+    {
+        let max_attempts = self.config.max_attempts.max(1);
+        let mut attempt = 1;
+
+        loop {
+            if let Some(quota) = &self.quota {
+                quota.acquire().await;
+            }
+
+            let error = match call().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            let cause = error.downcast_ref::<FailureCause>().copied();
+            let retryable = cause.is_some_and(|cause| cause.is_retryable(idempotent));
+            if !retryable || attempt >= max_attempts {
+                return Err(error.attach_printable(format!(
+                    "{}: attempt {} of {} failed",
+                    target, attempt, max_attempts
+                )));
+            }
+
+            let backoff = self.backoff(attempt);
+            if cause.is_some_and(|cause| cause.is_quota_exceeded()) {
+                if let Some(quota) = &self.quota {
+                    quota.hold_for(backoff).await;
+                }
+            }
+
+            tracing::warn!(
+                "Sheets API call on {} failed ({:?}), retrying in {:?} ({}/{})",
+                target,
+                cause,
+                backoff,
+                attempt,
+                max_attempts
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use error_stack::report;
+
+    use crate::adapters::http::rate_limiter::RateLimit;
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        let config = RetryConfig {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 4,
+        };
+        let quota = TokenBucket::new(RateLimit {
+            requests_per_second: 1000.0,
+            burst: 100,
+        });
+
+        RetryPolicy::new(config, Arc::new(quota))
+    }
+
+    /// Runs a call failing with `causes` in turn, then succeeding: how many times it was made and
+    /// whether it succeeded in the end
+    async fn attempts(idempotent: bool, causes: &[FailureCause]) -> (u32, bool) {
+        let calls = AtomicU32::new(0);
+
+        let result = policy()
+            .run("'Tokens'!A1", idempotent, || async {
+                let call = calls.fetch_add(1, Ordering::SeqCst) as usize;
+                match causes.get(call) {
+                    Some(cause) => Err(report!(SheetsBackendError::ReadValuesError).attach(*cause)),
+                    None => Ok(()),
+                }
+            })
+            .await;
+
+        (calls.load(Ordering::SeqCst), result.is_ok())
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_only() {
+        let unavailable = FailureCause::Status(503);
+        let quota = FailureCause::Status(429);
+
+        assert_eq!(attempts(true, &[unavailable, quota]).await, (3, true));
+        assert_eq!(attempts(true, &[unavailable; 3]).await, (3, false));
+        assert_eq!(
+            attempts(true, &[FailureCause::Status(400)]).await,
+            (1, false)
+        );
+
+        // An append may have been applied before the server failed, so it is not resent
+        assert_eq!(attempts(false, &[unavailable]).await, (1, false));
+        assert_eq!(attempts(false, &[quota]).await, (2, true));
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use error_stack::ResultExt;
+use google_sheets4::api::{
+    AddNamedRangeRequest, AddSheetRequest, GridProperties, GridRange, NamedRange, Request,
+    SheetProperties, UpdateSheetPropertiesRequest,
+};
+use tracing::instrument;
+
+use crate::domain::sheets::a1_notation::{A1Notation, FromA1Notation};
+use crate::domain::sheets::ranges::DEFAULT_LAYOUT;
+use crate::ports::schema_bootstrapper::{
+    BootstrapReport, SchemaBootstrapper, SchemaBootstrapperError,
+};
+
+use super::cell_range::CellRange;
+use super::spreadsheet_manager::SpreadsheetManager;
+
+/// Grid of a sheet created from the Sheets UI; sheets grow past it to fit their ranges
+const DEFAULT_ROW_COUNT: i32 = 1000;
+const DEFAULT_COLUMN_COUNT: i32 = 26;
+
+/// Where a named range is created when the spreadsheet lacks it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeLayout {
+    pub name: String,
+    pub sheet_title: String,
+    /// A1 range within the sheet, e.g. `A2:A201`
+    pub range: String,
+}
+
+impl RangeLayout {
+    pub fn new(
+        name: impl Into<String>,
+        sheet_title: impl Into<String>,
+        range: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            sheet_title: sheet_title.into(),
+            range: range.into(),
+        }
+    }
+}
+
+/// Creates the sheets and named ranges of [`DEFAULT_LAYOUT`], plus the configured ones, that
+/// the spreadsheet is missing
+#[derive(Debug)]
+pub struct SpreadsheetSchemaBootstrapper {
+    spreadsheet_manager: Arc<SpreadsheetManager>,
+    ranges: Vec<RangeLayout>,
+    sheets: Vec<String>,
+}
+
+impl SpreadsheetSchemaBootstrapper {
+    pub fn new(spreadsheet_manager: Arc<SpreadsheetManager>) -> Self {
+        let ranges = DEFAULT_LAYOUT
+            .iter()
+            .map(|(name, sheet_title, range)| RangeLayout::new(*name, *sheet_title, *range))
+            .collect();
+
+        Self {
+            spreadsheet_manager,
+            ranges,
+            sheets: Vec::new(),
+        }
+    }
+
+    /// Adds a named range, unless one with the same name is already laid out
+    pub fn with_range(mut self, layout: RangeLayout) -> Self {
+        if !self.ranges.iter().any(|range| range.name == layout.name) {
+            self.ranges.push(layout);
+        }
+        self
+    }
+
+    /// Adds a sheet written to by A1 notation rather than through a named range
+    pub fn with_sheet(mut self, title: impl Into<String>) -> Self {
+        let title = title.into();
+        if !self.sheets.contains(&title) {
+            self.sheets.push(title);
+        }
+        self
+    }
+}
+
+/// Grid of a sheet that ranges are laid out on, grown to fit them
+struct SheetGrid {
+    sheet_id: i32,
+    title: String,
+    row_count: i32,
+    column_count: i32,
+    /// Row and column counts of a sheet the spreadsheet already has
+    existing: Option<(i32, i32)>,
+}
+
+impl SheetGrid {
+    fn fit(&mut self, grid_range: &GridRange) {
+        self.row_count = self.row_count.max(grid_range.end_row_index.unwrap_or(0));
+        self.column_count = self
+            .column_count
+            .max(grid_range.end_column_index.unwrap_or(0));
+    }
+
+    fn properties(&self) -> SheetProperties {
+        SheetProperties {
+            sheet_id: Some(self.sheet_id),
+            grid_properties: Some(GridProperties {
+                row_count: Some(self.row_count),
+                column_count: Some(self.column_count),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the sheet when it is missing, or grows it when a range falls outside of its grid
+    fn into_request(self) -> Option<Request> {
+        match self.existing {
+            None => Some(Request {
+                add_sheet: Some(AddSheetRequest {
+                    properties: Some(SheetProperties {
+                        title: Some(self.title.clone()),
+                        ..self.properties()
+                    }),
+                }),
+                ..Default::default()
+            }),
+            Some((row_count, column_count))
+                if self.row_count > row_count || self.column_count > column_count =>
+            {
+                Some(Request {
+                    update_sheet_properties: Some(UpdateSheetPropertiesRequest {
+                        properties: Some(self.properties()),
+                        fields: Some(google_sheets4::FieldMask::new(&[
+                            "gridProperties.rowCount",
+                            "gridProperties.columnCount",
+                        ])),
+                    }),
+                    ..Default::default()
+                })
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+/// Requests creating what is missing (sheets first, so the named ranges can refer to them,
+/// and growing the existing sheets the new ranges do not fit in), along with the report of
+/// what they create
+fn plan(
+    ranges: &[RangeLayout],
+    sheets: &[String],
+    sheet_titles: &HashMap<i32, String>,
+    grid_properties: &HashMap<i32, GridProperties>,
+    named_ranges: &HashMap<String, GridRange>,
+) -> error_stack::Result<(Vec<Request>, BootstrapReport), SchemaBootstrapperError> {
+    let mut sheet_ids = sheet_titles
+        .iter()
+        .map(|(sheet_id, title)| (title.clone(), *sheet_id))
+        .collect::<HashMap<_, _>>();
+    let mut next_sheet_id = sheet_titles.keys().max().map_or(0, |max| max + 1);
+    let mut grids: Vec<SheetGrid> = Vec::new();
+
+    // Index of the sheet's grid, added on first use
+    let mut grid_of = |title: &str, grids: &mut Vec<SheetGrid>| {
+        let sheet_id = *sheet_ids.entry(title.to_owned()).or_insert_with(|| {
+            next_sheet_id += 1;
+            next_sheet_id - 1
+        });
+        match grids.iter().position(|grid| grid.sheet_id == sheet_id) {
+            Some(index) => index,
+            None => {
+                let existing = sheet_titles.contains_key(&sheet_id).then(|| {
+                    let properties = grid_properties.get(&sheet_id);
+                    (
+                        properties
+                            .and_then(|properties| properties.row_count)
+                            .unwrap_or(DEFAULT_ROW_COUNT),
+                        properties
+                            .and_then(|properties| properties.column_count)
+                            .unwrap_or(DEFAULT_COLUMN_COUNT),
+                    )
+                });
+                let (row_count, column_count) =
+                    existing.unwrap_or((DEFAULT_ROW_COUNT, DEFAULT_COLUMN_COUNT));
+                grids.push(SheetGrid {
+                    sheet_id,
+                    title: title.to_owned(),
+                    row_count,
+                    column_count,
+                    existing,
+                });
+                grids.len() - 1
+            }
+        }
+    };
+
+    let mut report = BootstrapReport::default();
+    let mut named_range_requests = Vec::new();
+
+    for layout in ranges {
+        if named_ranges.contains_key(&layout.name) {
+            report.existing_ranges.push(layout.name.clone());
+            continue;
+        }
+
+        let cell_range = CellRange::from_a1_notation(&A1Notation(layout.range.clone()))
+            .change_context(SchemaBootstrapperError::InvalidLayoutError)
+            .attach_printable_lazy(|| {
+                format!("Invalid range {} for {}", layout.range, layout.name)
+            })?;
+
+        let index = grid_of(&layout.sheet_title, &mut grids);
+        let grid = &mut grids[index];
+        let grid_range = cell_range.to_grid_range(grid.sheet_id);
+        grid.fit(&grid_range);
+
+        report.created_ranges.push(layout.name.clone());
+        named_range_requests.push(Request {
+            add_named_range: Some(AddNamedRangeRequest {
+                named_range: Some(NamedRange {
+                    name: Some(layout.name.clone()),
+                    range: Some(grid_range),
+                    ..Default::default()
+                }),
+            }),
+            ..Default::default()
+        });
+    }
+
+    for title in sheets {
+        grid_of(title, &mut grids);
+    }
+
+    report.created_sheets = grids
+        .iter()
+        .filter(|grid| grid.existing.is_none())
+        .map(|grid| grid.title.clone())
+        .collect();
+    let requests = grids
+        .into_iter()
+        .filter_map(SheetGrid::into_request)
+        .chain(named_range_requests)
+        .collect();
+
+    Ok((requests, report))
+}
+
+#[async_trait::async_trait]
+impl SchemaBootstrapper for SpreadsheetSchemaBootstrapper {
+    #[instrument(skip(self), fields(ranges = self.ranges.len(), sheets = self.sheets.len()))]
+    async fn bootstrap(&self) -> error_stack::Result<BootstrapReport, SchemaBootstrapperError> {
+        let metadata = self
+            .spreadsheet_manager
+            .refresh_metadata()
+            .await
+            .change_context(SchemaBootstrapperError::FetchMetadataError)?;
+        let (requests, report) = plan(
+            &self.ranges,
+            &self.sheets,
+            &metadata.sheet_titles,
+            &metadata.grid_properties,
+            &metadata.grid_ranges(),
+        )?;
+        if requests.is_empty() {
+            return Ok(report);
+        }
+
+        self.spreadsheet_manager
+            .batch_update(requests)
+            .await
+            .change_context(SchemaBootstrapperError::CreateError)?;
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sheets::ranges::EXPECTED_SHAPES;
+
+    use super::*;
+
+    #[test]
+    fn test_default_layout_fits_expected_shapes() {
+        assert_eq!(DEFAULT_LAYOUT.len(), EXPECTED_SHAPES.len());
+
+        for (name, shape) in EXPECTED_SHAPES {
+            let (_, _, range) = DEFAULT_LAYOUT
+                .iter()
+                .find(|(laid_out, _, _)| laid_out == name)
+                .unwrap_or_else(|| panic!("{} is not laid out", name));
+            let cell_range = CellRange::from_a1_notation(&A1Notation(range.to_string())).unwrap();
+
+            assert!(
+                shape.matches(cell_range.row_count(), cell_range.column_count()),
+                "{} is laid out as {}, which is not {}",
+                name,
+                range,
+                shape
+            );
+        }
+    }
+
+    #[test]
+    fn test_plan_creates_only_what_is_missing() {
+        let ranges = [
+            RangeLayout::new("Tokens__vIDs", "Tokens", "A2:A201"),
+            RangeLayout::new("Tokens__vNames", "Tokens", "B2:B201"),
+            RangeLayout::new("AaH__vHypeBalances_Names", "AaH", "AE2:AF201"),
+        ];
+        let sheets = ["History".to_owned(), "Tokens".to_owned()];
+        let sheet_titles = HashMap::from([(0, "Tokens".to_owned()), (4, "Notes".to_owned())]);
+        let named_ranges = HashMap::from([("Tokens__vIDs".to_owned(), GridRange::default())]);
+
+        let (requests, report) = plan(
+            &ranges,
+            &sheets,
+            &sheet_titles,
+            &HashMap::new(),
+            &named_ranges,
+        )
+        .unwrap();
+
+        assert_eq!(
+            report,
+            BootstrapReport {
+                created_sheets: vec!["AaH".to_owned(), "History".to_owned()],
+                created_ranges: vec![
+                    "Tokens__vNames".to_owned(),
+                    "AaH__vHypeBalances_Names".to_owned()
+                ],
+                existing_ranges: vec!["Tokens__vIDs".to_owned()],
+            }
+        );
+
+        let added_sheets = requests
+            .iter()
+            .filter_map(|request| request.add_sheet.as_ref()?.properties.as_ref())
+            .map(|properties| {
+                let grid = properties.grid_properties.as_ref().unwrap();
+                (properties.sheet_id, grid.row_count, grid.column_count)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            added_sheets,
+            vec![
+                (Some(5), Some(1000), Some(32)),
+                (Some(6), Some(1000), Some(26))
+            ]
+        );
+
+        let added_ranges = requests
+            .iter()
+            .filter_map(|request| request.add_named_range.as_ref()?.named_range.as_ref())
+            .filter_map(|named_range| named_range.range.as_ref())
+            .map(|range| {
+                (
+                    range.sheet_id,
+                    (range.start_row_index, range.end_row_index),
+                    (range.start_column_index, range.end_column_index),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            added_ranges,
+            vec![
+                (Some(0), (Some(1), Some(201)), (Some(1), Some(2))),
+                (Some(5), (Some(1), Some(201)), (Some(30), Some(32))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_grows_existing_sheets_to_fit_new_ranges() {
+        let ranges = [
+            RangeLayout::new("Tokens__vNames", "Tokens", "B2:B201"),
+            RangeLayout::new("AaH__vHypeBalances_Names", "AaH", "AE2:AF201"),
+        ];
+        let sheet_titles = HashMap::from([(0, "Tokens".to_owned()), (3, "AaH".to_owned())]);
+        let grid = |row_count, column_count| GridProperties {
+            row_count: Some(row_count),
+            column_count: Some(column_count),
+            ..Default::default()
+        };
+        let grid_properties = HashMap::from([(0, grid(1000, 26)), (3, grid(100, 26))]);
+
+        let (requests, report) = plan(
+            &ranges,
+            &[],
+            &sheet_titles,
+            &grid_properties,
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        assert!(report.created_sheets.is_empty());
+        assert!(requests.iter().all(|request| request.add_sheet.is_none()));
+
+        let grown_sheets = requests
+            .iter()
+            .filter_map(|request| {
+                request
+                    .update_sheet_properties
+                    .as_ref()?
+                    .properties
+                    .as_ref()
+            })
+            .map(|properties| {
+                let grid = properties.grid_properties.as_ref().unwrap();
+                (properties.sheet_id, grid.row_count, grid.column_count)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(grown_sheets, vec![(Some(3), Some(201), Some(32))]);
+
+        // Grown before the named range is added on it
+        assert!(requests[0].update_sheet_properties.is_some());
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|request| request.add_named_range.is_some())
+                .count(),
+            2
+        );
+    }
+}
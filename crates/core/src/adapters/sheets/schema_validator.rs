@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use error_stack::ResultExt;
+use google_sheets4::api::GridRange;
+use tracing::instrument;
+
+use crate::domain::sheets::ranges::{RangeShape, EXPECTED_SHAPES};
+use crate::ports::schema_validator::{
+    SchemaProblem, SchemaReport, SchemaValidator, SchemaValidatorError,
+};
+
+use super::cell_range::CellRange;
+use super::spreadsheet_manager::SpreadsheetManager;
+
+/// Something the spreadsheet must contain for the routines to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaExpectation {
+    /// A named range of the given shape. With `sheet_title`, routines write to that sheet
+    /// instead of the range's own, so it must exist too.
+    NamedRange {
+        name: String,
+        shape: RangeShape,
+        sheet_title: Option<String>,
+    },
+    /// A sheet written to by A1 notation rather than through a named range
+    Sheet { title: String },
+}
+
+/// Checks the named ranges of [`EXPECTED_SHAPES`], plus the configured ones, against the
+/// spreadsheet metadata
+#[derive(Debug)]
+pub struct SpreadsheetSchemaValidator {
+    spreadsheet_manager: Arc<SpreadsheetManager>,
+    expectations: Vec<SchemaExpectation>,
+}
+
+impl SpreadsheetSchemaValidator {
+    pub fn new(spreadsheet_manager: Arc<SpreadsheetManager>) -> Self {
+        let expectations = EXPECTED_SHAPES
+            .iter()
+            .map(|(name, shape)| SchemaExpectation::NamedRange {
+                name: name.to_string(),
+                shape: *shape,
+                sheet_title: None,
+            })
+            .collect();
+
+        Self {
+            spreadsheet_manager,
+            expectations,
+        }
+    }
+
+    /// Adds an expectation, unless an identical one is already checked
+    pub fn expect(mut self, expectation: SchemaExpectation) -> Self {
+        if !self.expectations.contains(&expectation) {
+            self.expectations.push(expectation);
+        }
+        self
+    }
+}
+
+fn check_expectation(
+    expectation: &SchemaExpectation,
+    named_ranges: &HashMap<String, GridRange>,
+    sheet_titles: &HashMap<i32, String>,
+) -> Vec<SchemaProblem> {
+    let sheet_exists = |title: &str| sheet_titles.values().any(|existing| existing == title);
+
+    let (name, shape, sheet_title) = match expectation {
+        SchemaExpectation::Sheet { title } if sheet_exists(title) => return Vec::new(),
+        SchemaExpectation::Sheet { title } => {
+            return vec![SchemaProblem {
+                range: format!("'{}'", title),
+                problem: "sheet not found".to_owned(),
+            }]
+        }
+        SchemaExpectation::NamedRange {
+            name,
+            shape,
+            sheet_title,
+        } => (name, shape, sheet_title),
+    };
+
+    let problem = |problem: String| SchemaProblem {
+        range: name.clone(),
+        problem,
+    };
+
+    let Some(grid_range) = named_ranges.get(name) else {
+        return vec![problem("named range not found".to_owned())];
+    };
+
+    let mut problems = Vec::new();
+
+    let sheet_id = grid_range.sheet_id.unwrap_or(0);
+    if !sheet_titles.contains_key(&sheet_id) {
+        problems.push(problem(format!(
+            "refers to a missing sheet (id {})",
+            sheet_id
+        )));
+    }
+    if let Some(sheet_title) = sheet_title.as_deref().filter(|title| !sheet_exists(title)) {
+        problems.push(problem(format!(
+            "is written to sheet '{}', which does not exist",
+            sheet_title
+        )));
+    }
+
+    let Ok(cell_range) = CellRange::try_from_grid_range(grid_range, None) else {
+        problems.push(problem(
+            "is unbounded; select explicit start and end rows and columns".to_owned(),
+        ));
+        return problems;
+    };
+
+    let (rows, columns) = (cell_range.row_count(), cell_range.column_count());
+    if !shape.matches(rows, columns) {
+        problems.push(problem(format!(
+            "should be {}, but spans {} rows and {} columns",
+            shape, rows, columns
+        )));
+    }
+
+    problems
+}
+
+#[async_trait::async_trait]
+impl SchemaValidator for SpreadsheetSchemaValidator {
+    #[instrument(skip(self), fields(expectations = self.expectations.len()))]
+    async fn validate(&self) -> error_stack::Result<SchemaReport, SchemaValidatorError> {
+        let metadata = self
+            .spreadsheet_manager
+            .refresh_metadata()
+            .await
+            .change_context(SchemaValidatorError::FetchMetadataError)?;
+        let (named_ranges, sheet_titles) = (metadata.grid_ranges(), &metadata.sheet_titles);
+
+        Ok(SchemaReport {
+            checked: self.expectations.len(),
+            problems: self
+                .expectations
+                .iter()
+                .flat_map(|expectation| check_expectation(expectation, &named_ranges, sheet_titles))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_range(sheet_id: i32, rows: (i32, i32), columns: (i32, i32)) -> GridRange {
+        GridRange {
+            sheet_id: Some(sheet_id),
+            start_row_index: Some(rows.0),
+            end_row_index: Some(rows.1),
+            start_column_index: Some(columns.0),
+            end_column_index: Some(columns.1),
+        }
+    }
+
+    fn named_range(name: &str, shape: RangeShape) -> SchemaExpectation {
+        SchemaExpectation::NamedRange {
+            name: name.to_owned(),
+            shape,
+            sheet_title: None,
+        }
+    }
+
+    #[test]
+    fn test_check_expectation() {
+        let sheet_titles = HashMap::from([(0, "Tokens".to_owned())]);
+        let named_ranges = HashMap::from([
+            ("Prices".to_owned(), grid_range(0, (1, 10), (2, 3))),
+            ("Total".to_owned(), grid_range(0, (0, 1), (0, 2))),
+            ("Orphan".to_owned(), grid_range(7, (0, 1), (0, 1))),
+            (
+                "Unbounded".to_owned(),
+                GridRange {
+                    end_row_index: None,
+                    ..grid_range(0, (0, 0), (0, 1))
+                },
+            ),
+        ]);
+        let check = |expectation: SchemaExpectation| {
+            check_expectation(&expectation, &named_ranges, &sheet_titles)
+                .into_iter()
+                .map(|problem| problem.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(check(named_range("Prices", RangeShape::Column)).is_empty());
+        assert_eq!(
+            check(named_range("Total", RangeShape::Cell)),
+            vec!["Total: should be a single cell, but spans 1 rows and 2 columns"]
+        );
+        assert_eq!(
+            check(named_range("Missing", RangeShape::Cell)),
+            vec!["Missing: named range not found"]
+        );
+        assert_eq!(
+            check(named_range("Orphan", RangeShape::Cell)),
+            vec!["Orphan: refers to a missing sheet (id 7)"]
+        );
+        assert_eq!(
+            check(named_range("Unbounded", RangeShape::Column)),
+            vec!["Unbounded: is unbounded; select explicit start and end rows and columns"]
+        );
+        assert_eq!(
+            check(SchemaExpectation::NamedRange {
+                name: "Prices".to_owned(),
+                shape: RangeShape::Table { min_rows: 20 },
+                sheet_title: Some("History".to_owned()),
+            }),
+            vec![
+                "Prices: is written to sheet 'History', which does not exist",
+                "Prices: should be at least 20 rows, but spans 9 rows and 1 columns",
+            ]
+        );
+        assert!(check(SchemaExpectation::Sheet {
+            title: "Tokens".to_owned()
+        })
+        .is_empty());
+    }
+}
@@ -1,10 +1,11 @@
 use crate::domain::sheets::a1_notation::ToA1Notation;
 use error_stack::{report, ResultExt};
-use google_sheets4::{
-    api::{GridRange, NamedRange, ValueRange},
-    Sheets,
+use google_sheets4::api::{
+    GridProperties, GridRange, NamedRange, Request, Spreadsheet, UpdateNamedRangeRequest,
+    ValueRange,
 };
-use std::{collections::HashMap, fmt::Debug};
+use std::time::{Duration, Instant};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 use thiserror::Error;
 use tokio::sync::RwLock;
 use tracing::instrument;
@@ -13,18 +14,67 @@ use crate::adapters::config::sheets_config::SpreadsheetConfig;
 use crate::domain::sheets::a1_notation::A1Notation;
 
 use super::{
-    auth::{self},
+    backend::{google::GoogleSheetsBackend, SheetsBackend, ValueRender},
     cell_range::CellRange,
-    http_client::{self},
+    retry::RetryPolicy,
 };
 
 pub struct SpreadsheetManager {
     pub config: SpreadsheetConfig,
-    pub(super) hub: Sheets<
-        google_sheets4::hyper_rustls::HttpsConnector<google_sheets4::hyper::client::HttpConnector>,
-    >,
-    pub named_ranges_cache: RwLock<Option<HashMap<String, GridRange>>>,
-    pub sheet_title_cache: RwLock<HashMap<i32, String>>,
+    pub(super) backend: Arc<dyn SheetsBackend>,
+    pub(super) retry: RetryPolicy,
+    metadata_cache: RwLock<Option<Arc<SpreadsheetMetadata>>>,
+}
+
+/// Sheets and named ranges of the spreadsheet, fetched together
+#[derive(Debug)]
+pub struct SpreadsheetMetadata {
+    pub named_ranges: HashMap<String, NamedRange>,
+    pub sheet_titles: HashMap<i32, String>,
+    /// Row and column counts of each sheet, by sheet id
+    pub grid_properties: HashMap<i32, GridProperties>,
+    fetched_at: Instant,
+}
+
+impl SpreadsheetMetadata {
+    fn from_spreadsheet(spreadsheet: Spreadsheet) -> Self {
+        let named_ranges = spreadsheet
+            .named_ranges
+            .into_iter()
+            .flatten()
+            .filter_map(|named_range| Some((named_range.name.clone()?, named_range)))
+            .collect();
+
+        let sheet_properties = spreadsheet
+            .sheets
+            .into_iter()
+            .flatten()
+            .filter_map(|sheet| sheet.properties)
+            .collect::<Vec<_>>();
+        let sheet_titles = sheet_properties
+            .iter()
+            .filter_map(|properties| Some((properties.sheet_id?, properties.title.clone()?)))
+            .collect();
+        let grid_properties = sheet_properties
+            .into_iter()
+            .filter_map(|properties| Some((properties.sheet_id?, properties.grid_properties?)))
+            .collect();
+
+        Self {
+            named_ranges,
+            sheet_titles,
+            grid_properties,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    /// Named ranges that have a range, by name
+    pub fn grid_ranges(&self) -> HashMap<String, GridRange> {
+        self.named_ranges
+            .iter()
+            .filter_map(|(name, named_range)| Some((name.clone(), named_range.range.clone()?)))
+            .collect()
+    }
 }
 
 impl Debug for SpreadsheetManager {
@@ -35,108 +85,113 @@ impl Debug for SpreadsheetManager {
 
 #[derive(Error, Debug)]
 pub enum SpreadsheetManagerError {
+    #[error("Failed to authenticate with Google Sheets")]
+    FailedToAuthenticate,
     #[error("Failed to fetch named range: {0}")]
     FailedToFetchNamedRange(&'static str),
     #[error("Failed to fetch sheet title")]
     FailedToFetchSheetTitle,
+    #[error("Failed to fetch the spreadsheet metadata")]
+    FailedToFetchMetadata,
     #[error("Failed to fetch range")]
     FailedToFetchRange,
     #[error("Failed to write range")]
     FailedToWriteRange,
+    #[error("Failed to update the spreadsheet structure")]
+    FailedToUpdateSpreadsheet,
+    #[error("Data does not fit in the named range")]
+    RangeOverflow,
 }
 
 impl SpreadsheetManager {
     #[instrument(name = "SpreadsheetManager::new")]
-    pub async fn new(config: SpreadsheetConfig) -> Self {
-        let client = http_client::http_client();
-        let auth = auth::auth(&config, client.clone()).await;
-        let hub: Sheets<
-            google_sheets4::hyper_rustls::HttpsConnector<
-                google_sheets4::hyper::client::HttpConnector,
-            >,
-        > = Sheets::new(client.clone(), auth);
+    pub async fn new(
+        config: SpreadsheetConfig,
+    ) -> error_stack::Result<Self, SpreadsheetManagerError> {
+        let backend = GoogleSheetsBackend::new(&config)
+            .await
+            .change_context(SpreadsheetManagerError::FailedToAuthenticate)?;
+        Ok(Self::with_backend(config, Arc::new(backend)))
+    }
 
+    pub fn with_backend(config: SpreadsheetConfig, backend: Arc<dyn SheetsBackend>) -> Self {
         SpreadsheetManager {
+            retry: RetryPolicy::from_config(&config.retry),
             config,
-            hub,
-            named_ranges_cache: RwLock::new(None),
-            sheet_title_cache: RwLock::new(HashMap::new()),
+            backend,
+            metadata_cache: RwLock::new(None),
         }
     }
 
+    /// Replaces the retry policy, e.g. to throttle against a bucket of its own
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Fetches the sheets and named ranges in one request, replacing the cached ones
     #[instrument]
-    async fn fetch_named_ranges_vec(
-        &self,
-    ) -> error_stack::Result<Vec<NamedRange>, SpreadsheetManagerError> {
-        let response = self
-            .hub
-            .spreadsheets()
-            .get(&self.config.spreadsheet_id)
-            .doit()
+    pub async fn refresh_metadata(
+        &self,
+    ) -> error_stack::Result<Arc<SpreadsheetMetadata>, SpreadsheetManagerError> {
+        let spreadsheet = self
+            .retry
+            .run("spreadsheet metadata", true, || {
+                self.backend.get_spreadsheet()
+            })
             .await
-            .change_context(SpreadsheetManagerError::FailedToFetchNamedRange(
-                "Failed to fetch spreadsheet",
-            ))?;
+            .change_context(SpreadsheetManagerError::FailedToFetchMetadata)?;
+
+        let metadata = Arc::new(SpreadsheetMetadata::from_spreadsheet(spreadsheet));
+        *self.metadata_cache.write().await = Some(Arc::clone(&metadata));
 
-        let named_ranges = response.1.named_ranges.ok_or(report!(
-            SpreadsheetManagerError::FailedToFetchNamedRange(
-                "Named ranges not present in spreadsheet response"
-            )
-        ))?;
-        Ok(named_ranges)
+        Ok(metadata)
     }
 
-    #[instrument]
-    async fn fetch_named_range_map(
+    /// Drops the cached metadata, so the next lookup fetches it again
+    pub async fn invalidate_metadata(&self) {
+        *self.metadata_cache.write().await = None;
+    }
+
+    /// The cached metadata while younger than the configured TTL, fetched otherwise. The flag
+    /// tells whether it was just fetched.
+    async fn metadata(
         &self,
-    ) -> error_stack::Result<HashMap<String, GridRange>, SpreadsheetManagerError> {
-        let named_ranges = self.fetch_named_ranges_vec().await?;
-        let mut map = HashMap::new();
-        for named_range in named_ranges {
-            map.insert(
-                named_range.name.ok_or(report!(
-                    SpreadsheetManagerError::FailedToFetchNamedRange(
-                        "Named range name not present"
-                    )
-                ))?,
-                named_range.range.ok_or(report!(
-                    SpreadsheetManagerError::FailedToFetchNamedRange(
-                        "Named range range not present"
-                    )
-                ))?,
-            );
+    ) -> error_stack::Result<(Arc<SpreadsheetMetadata>, bool), SpreadsheetManagerError> {
+        let ttl = Duration::from_secs(self.config.metadata_cache.ttl_secs);
+        let cached = self.metadata_cache.read().await.clone();
+
+        match cached {
+            Some(metadata) if metadata.fetched_at.elapsed() < ttl => Ok((metadata, false)),
+            _ => Ok((self.refresh_metadata().await?, true)),
         }
+    }
 
-        Ok(map)
+    /// Looks something up in the metadata, refreshing it once on a miss: the range or sheet may
+    /// have been created or renamed since the metadata was cached
+    async fn lookup<T>(
+        &self,
+        find: impl Fn(&SpreadsheetMetadata) -> Option<T>,
+    ) -> error_stack::Result<Option<T>, SpreadsheetManagerError> {
+        let (metadata, fresh) = self.metadata().await?;
+        if let Some(found) = find(&metadata) {
+            return Ok(Some(found));
+        }
+        if fresh {
+            return Ok(None);
+        }
+
+        tracing::debug!("Spreadsheet metadata cache miss, refreshing");
+        Ok(find(&*self.refresh_metadata().await?))
     }
 
     #[instrument]
     pub async fn named_range_map(
         &self,
     ) -> error_stack::Result<HashMap<String, GridRange>, SpreadsheetManagerError> {
-        let cache = {
-            // -- MUTEX READ --
-            let guard = self.named_ranges_cache.read().await;
-            guard.clone()
-            // -- END MUTEX READ --
-        };
-
-        let map = match cache {
-            Some(map) => map,
-            None => {
-                let fetched_map = self.fetch_named_range_map().await?;
-
-                {
-                    // -- MUTEX WRITE --
-                    let mut guard = self.named_ranges_cache.write().await;
-                    guard.replace(fetched_map.clone());
-                    // -- END MUTEX WRITE --
-                }
-                fetched_map
-            }
-        };
+        let (metadata, _) = self.metadata().await?;
 
-        Ok(map.clone())
+        Ok(metadata.grid_ranges())
     }
 
     #[instrument]
@@ -144,67 +199,64 @@ impl SpreadsheetManager {
         &self,
         target_sheet_id: i32,
     ) -> error_stack::Result<String, SpreadsheetManagerError> {
-        if let Some(title) = self
-            .sheet_title_cache
-            .read()
-            .await
-            .get(&target_sheet_id)
-            .cloned()
-        {
-            return Ok(title);
-        }
-
-        let response = self
-            .hub
-            .spreadsheets()
-            .get(&self.config.spreadsheet_id)
-            .doit()
-            .await
-            .change_context(SpreadsheetManagerError::FailedToFetchSheetTitle)?;
-
-        let sheets = response
-            .1
-            .sheets
-            .ok_or(SpreadsheetManagerError::FailedToFetchSheetTitle)?;
-
-        let mut guard = self.sheet_title_cache.write().await;
-        for sheet in &sheets {
-            if let (Some(sheet_id), Some(title)) = (
-                sheet.properties.as_ref().and_then(|p| p.sheet_id),
-                sheet.properties.as_ref().and_then(|p| p.title.as_ref()),
-            ) {
-                guard.insert(sheet_id, title.clone());
-            }
-        }
+        self.lookup(|metadata| metadata.sheet_titles.get(&target_sheet_id).cloned())
+            .await?
+            .ok_or_else(|| {
+                report!(SpreadsheetManagerError::FailedToFetchSheetTitle)
+                    .attach_printable(format!("Sheet with id {} not found", target_sheet_id))
+            })
+    }
 
-        guard.get(&target_sheet_id).cloned().ok_or_else(|| {
+    #[instrument]
+    pub async fn get_sheet_id(
+        &self,
+        sheet_title: &str,
+    ) -> error_stack::Result<i32, SpreadsheetManagerError> {
+        self.lookup(|metadata| {
+            metadata
+                .sheet_titles
+                .iter()
+                .find_map(|(sheet_id, title)| (title == sheet_title).then_some(*sheet_id))
+        })
+        .await?
+        .ok_or_else(|| {
             report!(SpreadsheetManagerError::FailedToFetchSheetTitle)
-                .attach_printable(format!("Sheet with id {} not found", target_sheet_id))
+                .attach_printable(format!("Sheet {} not found", sheet_title))
         })
     }
 
+    async fn find_named_range(
+        &self,
+        name: &str,
+    ) -> error_stack::Result<NamedRange, SpreadsheetManagerError> {
+        self.lookup(|metadata| metadata.named_ranges.get(name).cloned())
+            .await?
+            .ok_or(report!(SpreadsheetManagerError::FailedToFetchNamedRange(
+                "Named range not found"
+            )))
+            .attach_printable_lazy(|| format!("Named range {} not found in spreadsheet", name))
+    }
+
     #[instrument]
     pub async fn get_named_range(
         &self,
         name: &str,
     ) -> error_stack::Result<GridRange, SpreadsheetManagerError> {
-        let named_ranges = self.named_range_map().await?;
-
-        named_ranges
-            .get(name)
-            .cloned()
+        self.find_named_range(name)
+            .await?
+            .range
             .ok_or(report!(SpreadsheetManagerError::FailedToFetchNamedRange(
-                "Named range not found"
+                "Named range range not present"
             )))
-            .attach_printable_lazy(|| format!("Named range {} not found in spreadsheet", name))
+            .attach_printable_lazy(|| format!("Named range {} has no range", name))
     }
 
+    /// Resolves a named range to its A1 notation, sheet title included
     #[instrument]
-    pub(super) async fn write_named_range(
+    pub(super) async fn named_range_a1(
         &self,
         name: &str,
-        value_range: ValueRange,
-    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+    ) -> error_stack::Result<A1Notation, SpreadsheetManagerError> {
         let grid_range = self.get_named_range(name).await?;
 
         let sheet_title = self
@@ -217,11 +269,7 @@ impl SpreadsheetManager {
 
         let cell_range = cell_range.with_sheet_title(sheet_title);
 
-        self.write_range(
-            &cell_range.to_a1_notation(cell_range.sheet_title.as_deref()),
-            value_range,
-        )
-        .await
+        Ok(cell_range.to_a1_notation(cell_range.sheet_title.as_deref()))
     }
 
     #[instrument]
@@ -230,14 +278,282 @@ impl SpreadsheetManager {
         range_str: &A1Notation,
         value_range: ValueRange,
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
-        self.hub
-            .spreadsheets()
-            .values_update(value_range, &self.config.spreadsheet_id, range_str.as_ref())
-            .value_input_option("USER_ENTERED")
-            .doit()
+        let value_range = ValueRange {
+            range: Some(range_str.as_ref().to_owned()),
+            ..value_range
+        };
+
+        self.retry
+            .run(range_str.as_ref(), true, || {
+                self.backend.update_values(vec![value_range.clone()])
+            })
             .await
-            .map(|_| ())
             .change_context(SpreadsheetManagerError::FailedToWriteRange)
             .attach_printable_lazy(|| format!("Failed to write to range {} ", range_str))
     }
+
+    /// Writes every value range (each carrying its own `range`) in one request
+    #[instrument(skip(value_ranges), fields(count = value_ranges.len()))]
+    pub(super) async fn write_ranges(
+        &self,
+        value_ranges: Vec<ValueRange>,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        let count = value_ranges.len();
+        let target = format!("{} ranges", count);
+
+        self.retry
+            .run(&target, true, || {
+                self.backend.update_values(value_ranges.clone())
+            })
+            .await
+            .change_context(SpreadsheetManagerError::FailedToWriteRange)
+            .attach_printable_lazy(|| format!("Failed to write {} ranges in a batch", count))
+    }
+
+    /// Adds `rows` after the last row of the table in `range` (e.g. `'History'!A:F`), leaving
+    /// the existing rows untouched
+    #[instrument(skip(rows), fields(count = rows.len()))]
+    pub async fn append_rows(
+        &self,
+        range: &str,
+        rows: Vec<Vec<serde_json::Value>>,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        self.retry
+            .run(range, false, || {
+                self.backend.append_values(range, rows.clone())
+            })
+            .await
+            .change_context(SpreadsheetManagerError::FailedToWriteRange)
+            .attach_printable_lazy(|| format!("Failed to append rows to range {}", range))
+    }
+
+    /// Every row of `range`, as stored rather than as shown: numbers as numbers and dates as
+    /// serial numbers, whatever the sheet's locale and formats. Trailing empty cells are omitted.
+    #[instrument]
+    pub async fn read_rows(
+        &self,
+        range: &str,
+    ) -> error_stack::Result<Vec<Vec<serde_json::Value>>, SpreadsheetManagerError> {
+        self.retry
+            .run(range, true, || {
+                self.backend.get_values(range, ValueRender::Unformatted)
+            })
+            .await
+            .map(|value_range| value_range.values.unwrap_or_default())
+            .change_context(SpreadsheetManagerError::FailedToFetchRange)
+            .attach_printable_lazy(|| format!("Failed to read rows of range {}", range))
+    }
+
+    /// Grows the named range `name` downwards to `row_count` rows, keeping its start and columns
+    #[instrument]
+    pub async fn extend_named_range(
+        &self,
+        name: &str,
+        row_count: u32,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        let named_range = self.find_named_range(name).await?;
+
+        let mut range = named_range.range.clone().unwrap_or_default();
+        range.end_row_index = Some(
+            range
+                .start_row_index
+                .unwrap_or(0)
+                .saturating_add(i32::try_from(row_count).unwrap_or(i32::MAX)),
+        );
+
+        let request = Request {
+            update_named_range: Some(UpdateNamedRangeRequest {
+                named_range: Some(NamedRange {
+                    range: Some(range),
+                    ..named_range
+                }),
+                fields: Some(google_sheets4::FieldMask::new(&["range"])),
+            }),
+            ..Default::default()
+        };
+
+        self.batch_update(vec![request])
+            .await
+            .attach_printable_lazy(|| format!("Failed to extend named range {}", name))
+    }
+
+    /// Applies cell formats in a single `batchUpdate`. Unlike [`Self::batch_update`], the cached
+    /// metadata is kept, since formats cannot change it.
+    #[instrument(skip(requests), fields(count = requests.len()))]
+    pub async fn format_cells(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        self.retry
+            .run("cell formats", true, || {
+                self.backend.batch_update(requests.clone())
+            })
+            .await
+            .change_context(SpreadsheetManagerError::FailedToUpdateSpreadsheet)
+            .attach_printable("Failed to apply cell formats")
+    }
+
+    /// Sends structural changes in a single `batchUpdate`, then drops the cached metadata,
+    /// which they may have changed
+    #[instrument(skip(requests), fields(count = requests.len()))]
+    pub async fn batch_update(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        // Structural changes such as new sheets are not resent after a server error, which may
+        // have applied them already
+        let result = self
+            .retry
+            .run("batchUpdate", false, || {
+                self.backend.batch_update(requests.clone())
+            })
+            .await
+            .change_context(SpreadsheetManagerError::FailedToUpdateSpreadsheet);
+
+        self.invalidate_metadata().await;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use google_sheets4::api::{Sheet, SheetProperties};
+
+    use crate::adapters::sheets::backend::SheetsBackendError;
+
+    use super::*;
+
+    /// Serves `spreadsheet` as the metadata, counting how often it is fetched
+    #[derive(Debug, Default)]
+    struct FakeBackend {
+        spreadsheet: Mutex<Spreadsheet>,
+        fetches: Mutex<usize>,
+    }
+
+    impl FakeBackend {
+        fn set_sheets(&self, sheets: &[(i32, &str)], named_ranges: &[(&str, i32)]) {
+            *self.spreadsheet.lock().unwrap() = Spreadsheet {
+                sheets: Some(
+                    sheets
+                        .iter()
+                        .map(|(sheet_id, title)| Sheet {
+                            properties: Some(SheetProperties {
+                                sheet_id: Some(*sheet_id),
+                                title: Some(title.to_string()),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        })
+                        .collect(),
+                ),
+                named_ranges: Some(
+                    named_ranges
+                        .iter()
+                        .map(|(name, sheet_id)| NamedRange {
+                            name: Some(name.to_string()),
+                            range: Some(GridRange {
+                                sheet_id: Some(*sheet_id),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            };
+        }
+
+        fn fetches(&self) -> usize {
+            *self.fetches.lock().unwrap()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl SheetsBackend for FakeBackend {
+        async fn get_spreadsheet(&self) -> error_stack::Result<Spreadsheet, SheetsBackendError> {
+            *self.fetches.lock().unwrap() += 1;
+            Ok(self.spreadsheet.lock().unwrap().clone())
+        }
+
+        async fn get_values(
+            &self,
+            _range: &str,
+            _render: ValueRender,
+        ) -> error_stack::Result<ValueRange, SheetsBackendError> {
+            Err(report!(SheetsBackendError::ReadValuesError))
+                .attach_printable("Only metadata is served")
+        }
+
+        async fn update_values(
+            &self,
+            _value_ranges: Vec<ValueRange>,
+        ) -> error_stack::Result<(), SheetsBackendError> {
+            Err(report!(SheetsBackendError::WriteValuesError))
+                .attach_printable("Only metadata is served")
+        }
+
+        async fn append_values(
+            &self,
+            _range: &str,
+            _rows: Vec<Vec<serde_json::Value>>,
+        ) -> error_stack::Result<(), SheetsBackendError> {
+            Err(report!(SheetsBackendError::WriteValuesError))
+                .attach_printable("Only metadata is served")
+        }
+
+        async fn batch_update(
+            &self,
+            _requests: Vec<Request>,
+        ) -> error_stack::Result<(), SheetsBackendError> {
+            Ok(())
+        }
+    }
+
+    fn manager(ttl_secs: u64) -> (SpreadsheetManager, Arc<FakeBackend>) {
+        let backend = Arc::new(FakeBackend::default());
+        backend.set_sheets(&[(0, "Tokens")], &[("Tokens__vIDs", 0)]);
+
+        let mut config = SpreadsheetConfig::default();
+        config.metadata_cache.ttl_secs = ttl_secs;
+
+        (
+            SpreadsheetManager::with_backend(config, backend.clone()),
+            backend,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_metadata_is_fetched_once_and_refreshed_on_misses() {
+        let (manager, backend) = manager(300);
+
+        assert_eq!(manager.get_sheet_title(0).await.unwrap(), "Tokens");
+        assert!(manager.get_named_range("Tokens__vIDs").await.is_ok());
+        assert_eq!(backend.fetches(), 1);
+
+        // Renamed since the metadata was cached
+        backend.set_sheets(&[(0, "Assets")], &[("Assets__vIDs", 0)]);
+        assert_eq!(manager.get_sheet_title(0).await.unwrap(), "Tokens");
+        assert!(manager.get_named_range("Assets__vIDs").await.is_ok());
+        assert_eq!(manager.get_sheet_title(0).await.unwrap(), "Assets");
+        assert_eq!(backend.fetches(), 2);
+
+        assert!(manager.get_named_range("Tokens__vIDs").await.is_err());
+        assert_eq!(backend.fetches(), 3);
+
+        manager.batch_update(Vec::new()).await.unwrap();
+        assert!(manager.get_named_range("Assets__vIDs").await.is_ok());
+        assert_eq!(backend.fetches(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_expires_after_ttl() {
+        let (manager, backend) = manager(0);
+
+        manager.get_sheet_title(0).await.unwrap();
+        manager.get_sheet_title(0).await.unwrap();
+        assert_eq!(backend.fetches(), 2);
+    }
 }
@@ -1,11 +1,14 @@
 use error_stack::{report, ResultExt};
+use serde_json::Value;
 use tracing::instrument;
 
 use crate::domain::sheets::a1_notation::ToA1Notation;
+use crate::domain::sheets::cell_value::CellValue;
 
 use crate::adapters::sheets::cell_range::CellRange;
 
 use super::{
+    backend::ValueRender,
     flatten_double_vec::FlattenDoubleVec,
     spreadsheet_manager::{SpreadsheetManager, SpreadsheetManagerError},
 };
@@ -21,28 +24,56 @@ pub trait SpreadsheetRead {
         name: &str,
     ) -> impl std::future::Future<Output = error_stack::Result<Vec<String>, SpreadsheetManagerError>>
            + Send;
+    /// Values of a named range as stored rather than as shown: numbers instead of `$1,234.50`.
+    /// Number formats are not read back, so every numeric cell is a [`CellValue::Number`].
+    fn read_named_typed_range(
+        &self,
+        name: &str,
+    ) -> impl std::future::Future<
+        Output = error_stack::Result<Vec<CellValue>, SpreadsheetManagerError>,
+    > + Send;
 }
 
-impl SpreadsheetRead for SpreadsheetManager {
-    #[instrument]
-    async fn read_range(
+/// Cell of an `UNFORMATTED_VALUE` read
+fn cell_value_from_json(value: Value) -> CellValue {
+    match value {
+        Value::Null => CellValue::Empty,
+        Value::String(text) if text.is_empty() => CellValue::Empty,
+        Value::String(text) => CellValue::Text(text),
+        Value::Number(number) => number.as_f64().map_or(CellValue::Empty, CellValue::Number),
+        Value::Bool(boolean) => CellValue::Text(boolean.to_string().to_uppercase()),
+        other => CellValue::Text(other.to_string()),
+    }
+}
+
+impl SpreadsheetManager {
+    async fn fetch_values(
         &self,
         range: &str,
-    ) -> error_stack::Result<Vec<String>, SpreadsheetManagerError> {
-        let response = self
-            .hub
-            .spreadsheets()
-            .values_get(&self.config.spreadsheet_id, range)
-            .doit()
+        render: ValueRender,
+    ) -> error_stack::Result<Vec<Vec<Value>>, SpreadsheetManagerError> {
+        let value_range = self
+            .retry
+            .run(range, true, || self.backend.get_values(range, render))
             .await
             .change_context(SpreadsheetManagerError::FailedToFetchRange)?;
 
-        let value_range = response.1;
-
-        let values = value_range
+        value_range
             .values
             .ok_or(report!(SpreadsheetManagerError::FailedToFetchRange))
-            .attach_printable_lazy(|| format!("Failed to fetch values for range {}", range))?
+            .attach_printable_lazy(|| format!("Failed to fetch values for range {}", range))
+    }
+}
+
+impl SpreadsheetRead for SpreadsheetManager {
+    #[instrument]
+    async fn read_range(
+        &self,
+        range: &str,
+    ) -> error_stack::Result<Vec<String>, SpreadsheetManagerError> {
+        let values = self
+            .fetch_values(range, ValueRender::Formatted)
+            .await?
             .flatten_double_vec();
 
         Ok(values)
@@ -75,4 +106,46 @@ impl SpreadsheetRead for SpreadsheetManager {
         )
         .await
     }
+
+    #[instrument]
+    async fn read_named_typed_range(
+        &self,
+        name: &str,
+    ) -> error_stack::Result<Vec<CellValue>, SpreadsheetManagerError> {
+        let range = self.named_range_a1(name).await?;
+
+        let values = self
+            .fetch_values(range.as_ref(), ValueRender::Unformatted)
+            .await?
+            .into_iter()
+            .flatten()
+            .map(cell_value_from_json)
+            .collect();
+
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_cell_value_from_json() {
+        assert_eq!(
+            cell_value_from_json(json!(1234.5)),
+            CellValue::Number(1234.5)
+        );
+        assert_eq!(
+            cell_value_from_json(json!("BTC")),
+            CellValue::Text("BTC".to_owned())
+        );
+        assert_eq!(cell_value_from_json(json!("")), CellValue::Empty);
+        assert_eq!(
+            cell_value_from_json(json!(true)),
+            CellValue::Text("TRUE".to_owned())
+        );
+    }
 }
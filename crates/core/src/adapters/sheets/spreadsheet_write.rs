@@ -1,13 +1,187 @@
+use std::borrow::Cow;
+
+use crate::adapters::config::sheets_config::OverflowPolicy;
 use crate::adapters::sheets::cell_range::CellRange;
 use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
 use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManagerError;
 use crate::domain::sheets::a1_notation::A1Notation;
 use crate::domain::sheets::a1_notation::ToA1Notation;
+use crate::domain::sheets::cell_value::CellValue;
+use crate::domain::sheets::ranges::RangeShape;
 use error_stack::{report, ResultExt};
-use google_sheets4::api::ValueRange;
+use google_sheets4::api::{Request, ValueRange};
 use tracing::instrument;
 
+use super::number_format::number_format_requests;
 use super::value_range_factory::ValueRangeFactory;
+
+/// Destination of resolved writes: the manager sends each one right away, while a
+/// [`SpreadsheetWriteBatch`](super::spreadsheet_write_batch::SpreadsheetWriteBatch) collects them
+pub trait SpreadsheetWriteSink: std::fmt::Debug + Sync {
+    /// Manager used to resolve named ranges and sheet titles
+    fn spreadsheet_manager(&self) -> &SpreadsheetManager;
+
+    fn write_value_range(
+        &self,
+        range: &A1Notation,
+        value_range: ValueRange,
+    ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
+
+    /// Sends `batchUpdate` requests formatting cells that were just written
+    fn write_formats(
+        &self,
+        requests: Vec<Request>,
+    ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
+}
+
+impl SpreadsheetWriteSink for SpreadsheetManager {
+    fn spreadsheet_manager(&self) -> &SpreadsheetManager {
+        self
+    }
+
+    async fn write_value_range(
+        &self,
+        range: &A1Notation,
+        value_range: ValueRange,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        self.write_range(range, value_range).await
+    }
+
+    async fn write_formats(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        self.format_cells(requests).await
+    }
+}
+
+/// Rows a named range offers once its overflow policy is applied
+enum RowFit {
+    /// Every value fits in that many rows, the range having been extended if needed
+    Fits(u32),
+    /// Only that many rows, so the values have to go through [`truncate_column`]
+    Truncated(u32),
+}
+
+/// Applies the configured [`OverflowPolicy`] of `name` when `rows` values do not fit in it.
+/// Extending happens right away, even when the values themselves are batched.
+async fn fit_rows(
+    spreadsheet_manager: &SpreadsheetManager,
+    name: &str,
+    cell_range: &CellRange,
+    rows: usize,
+) -> error_stack::Result<RowFit, SpreadsheetManagerError> {
+    let capacity = cell_range.row_count();
+    if rows <= capacity as usize {
+        return Ok(RowFit::Fits(capacity));
+    }
+
+    match spreadsheet_manager.config.overflow.policy_for(name) {
+        OverflowPolicy::Fail => Err(report!(SpreadsheetManagerError::RangeOverflow))
+            .attach_printable_lazy(|| {
+                format!(
+                    "{} rows do not fit in the {} rows of named range {}",
+                    rows, capacity, name
+                )
+            }),
+        OverflowPolicy::Truncate => {
+            tracing::warn!(
+                "Truncating {} rows to the {} rows of named range {}",
+                rows,
+                capacity,
+                name
+            );
+            Ok(RowFit::Truncated(capacity))
+        }
+        OverflowPolicy::Extend => {
+            let rows = u32::try_from(rows).unwrap_or(u32::MAX);
+            tracing::warn!(
+                "Extending named range {} from {} to {} rows",
+                name,
+                capacity,
+                rows
+            );
+            spreadsheet_manager.extend_named_range(name, rows).await?;
+            Ok(RowFit::Fits(rows))
+        }
+    }
+}
+
+/// The values that fit in `capacity` rows, the last row replaced by `last`
+fn truncate_column<V: Clone>(values: &[V], capacity: u32, last: V) -> Vec<V> {
+    let kept = (capacity as usize).saturating_sub(1).min(values.len());
+    values[..kept].iter().cloned().chain([last]).collect()
+}
+
+fn overflow_marker(hidden: usize) -> CellValue {
+    CellValue::Text(format!("+{} more", hidden))
+}
+
+/// Writes `columns` side by side from the top of the named range `name`, which must have the
+/// given shape, then applies the number formats of their values
+async fn write_named_columns<S: SpreadsheetWriteSink>(
+    sink: &S,
+    name: &str,
+    shape: RangeShape,
+    columns: &[&[CellValue]],
+) -> error_stack::Result<(), SpreadsheetManagerError> {
+    let spreadsheet_manager = sink.spreadsheet_manager();
+    let grid_range = spreadsheet_manager.get_named_range(name).await?;
+
+    let cell_range =
+        CellRange::try_from_grid_range_with_sheet_manager(grid_range.clone(), spreadsheet_manager)
+            .await
+            .change_context(SpreadsheetManagerError::FailedToWriteRange)?;
+
+    if !shape.matches(cell_range.row_count(), cell_range.column_count()) {
+        return Err(report!(SpreadsheetManagerError::FailedToWriteRange)).attach_printable_lazy(
+            || {
+                format!(
+                    "Named range {} is not {}, trying to write {:?} to it",
+                    name, shape, columns
+                )
+            },
+        );
+    }
+
+    let rows = columns.iter().map(|column| column.len()).min().unwrap_or(0);
+    let (columns, row_count) = match fit_rows(spreadsheet_manager, name, &cell_range, rows).await? {
+        RowFit::Fits(row_count) => (
+            columns
+                .iter()
+                .map(|column| Cow::Borrowed(*column))
+                .collect(),
+            row_count,
+        ),
+        RowFit::Truncated(row_count) => {
+            let hidden = rows + 1 - row_count as usize;
+            let columns = columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    let last = match index {
+                        0 => overflow_marker(hidden),
+                        _ => CellValue::Empty,
+                    };
+                    Cow::Owned(truncate_column(column, row_count, last))
+                })
+                .collect::<Vec<Cow<[CellValue]>>>();
+            (columns, row_count)
+        }
+    };
+    let columns = columns.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+
+    let value_range = ValueRange::from_typed_columns(&columns, row_count);
+    let range = spreadsheet_manager.named_range_a1(name).await?;
+    sink.write_value_range(&range, value_range).await?;
+
+    let format_requests = number_format_requests(&grid_range, &columns);
+    if format_requests.is_empty() {
+        return Ok(());
+    }
+    sink.write_formats(format_requests).await
+}
+
 pub trait SpreadsheetWrite {
     fn write_value(
         &self,
@@ -21,6 +195,13 @@ pub trait SpreadsheetWrite {
         values: &[String],
     ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
 
+    /// Writes typed values down the column of `range` and applies their number formats
+    fn write_typed_column(
+        &self,
+        range: &CellRange,
+        values: &[CellValue],
+    ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
+
     fn write_named_cell(
         &self,
         name: &str,
@@ -39,9 +220,35 @@ pub trait SpreadsheetWrite {
         col1_values: &[String],
         col2_values: &[String],
     ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
+
+    /// Writes a typed value and applies its number format
+    fn write_named_typed_cell(
+        &self,
+        name: &str,
+        value: &CellValue,
+    ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
+
+    /// Writes typed values and applies their number formats
+    fn write_named_typed_column(
+        &self,
+        name: &str,
+        values: &[CellValue],
+    ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
+
+    fn write_named_typed_two_columns(
+        &self,
+        name: &str,
+        col1_values: &[CellValue],
+        col2_values: &[CellValue],
+    ) -> impl std::future::Future<Output = error_stack::Result<(), SpreadsheetManagerError>> + Send;
+}
+
+/// Untyped strings are entered as if typed in, for the sheet to parse
+fn entered(values: &[String]) -> Vec<CellValue> {
+    values.iter().cloned().map(CellValue::Entered).collect()
 }
 
-impl SpreadsheetWrite for SpreadsheetManager {
+impl<T: SpreadsheetWriteSink> SpreadsheetWrite for T {
     #[instrument]
     async fn write_value(
         &self,
@@ -49,7 +256,7 @@ impl SpreadsheetWrite for SpreadsheetManager {
         value: &str,
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
         let value_range = ValueRange::from_single_cell(value);
-        self.write_range(position_str, value_range).await
+        self.write_value_range(position_str, value_range).await
     }
 
     #[instrument]
@@ -59,37 +266,46 @@ impl SpreadsheetWrite for SpreadsheetManager {
         values: &[String],
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
         let value_range = ValueRange::from_single_column(values, range.row_count());
-        self.write_range(
+        self.write_value_range(
             &range.to_a1_notation(range.sheet_title.as_deref()),
             value_range,
         )
         .await
     }
 
+    #[instrument]
+    async fn write_typed_column(
+        &self,
+        range: &CellRange,
+        values: &[CellValue],
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        let value_range = ValueRange::from_typed_columns(&[values], range.row_count());
+        self.write_value_range(
+            &range.to_a1_notation(range.sheet_title.as_deref()),
+            value_range,
+        )
+        .await?;
+
+        // Same as named ranges without a sheet id: the first sheet
+        let sheet_id = match &range.sheet_title {
+            Some(sheet_title) => self.spreadsheet_manager().get_sheet_id(sheet_title).await?,
+            None => 0,
+        };
+        let format_requests = number_format_requests(&range.to_grid_range(sheet_id), &[values]);
+        if format_requests.is_empty() {
+            return Ok(());
+        }
+        self.write_formats(format_requests).await
+    }
+
     #[instrument]
     async fn write_named_cell(
         &self,
         name: &str,
         value: &str,
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
-        let grid_range = self.get_named_range(name).await?;
-
-        let cell_range = CellRange::try_from_grid_range_with_sheet_manager(grid_range, self)
+        self.write_named_typed_cell(name, &CellValue::Entered(value.to_owned()))
             .await
-            .change_context(SpreadsheetManagerError::FailedToWriteRange)?;
-
-        if cell_range.column_count() != 1 || cell_range.row_count() != 1 {
-            return Err(report!(SpreadsheetManagerError::FailedToWriteRange))
-                .attach_printable_lazy(|| {
-                    format!(
-                        "Named range {} is not a single cell, trying to write {} to it",
-                        name, value
-                    )
-                });
-        }
-
-        let value_range = ValueRange::from_single_cell(value);
-        return self.write_named_range(name, value_range).await;
     }
 
     #[instrument]
@@ -98,51 +314,70 @@ impl SpreadsheetWrite for SpreadsheetManager {
         name: &str,
         values: &[String],
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
-        let grid_range = self.get_named_range(name).await?;
+        self.write_named_typed_column(name, &entered(values)).await
+    }
 
-        let cell_range = CellRange::try_from_grid_range_with_sheet_manager(grid_range, self)
+    #[instrument]
+    async fn write_named_two_columns(
+        &self,
+        name: &str,
+        col1_values: &[String],
+        col2_values: &[String],
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        self.write_named_typed_two_columns(name, &entered(col1_values), &entered(col2_values))
             .await
-            .change_context(SpreadsheetManagerError::FailedToWriteRange)?;
+    }
 
-        if cell_range.column_count() != 1 {
-            return Err(report!(SpreadsheetManagerError::FailedToWriteRange))
-                .attach_printable_lazy(|| {
-                    format!(
-                        "Named range {} is not a single column, trying to write {:?} to it",
-                        name, values
-                    )
-                });
-        }
+    #[instrument]
+    async fn write_named_typed_cell(
+        &self,
+        name: &str,
+        value: &CellValue,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        write_named_columns(self, name, RangeShape::Cell, &[std::slice::from_ref(value)]).await
+    }
 
-        let value_range = ValueRange::from_single_column(values, cell_range.row_count());
-        return self.write_named_range(name, value_range).await;
+    #[instrument]
+    async fn write_named_typed_column(
+        &self,
+        name: &str,
+        values: &[CellValue],
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        write_named_columns(self, name, RangeShape::Column, &[values]).await
     }
 
     #[instrument]
-    async fn write_named_two_columns(
+    async fn write_named_typed_two_columns(
         &self,
         name: &str,
-        col1_values: &[String],
-        col2_values: &[String],
+        col1_values: &[CellValue],
+        col2_values: &[CellValue],
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
-        let grid_range = self.get_named_range(name).await?;
+        write_named_columns(
+            self,
+            name,
+            RangeShape::TwoColumns,
+            &[col1_values, col2_values],
+        )
+        .await
+    }
+}
 
-        let cell_range = CellRange::try_from_grid_range_with_sheet_manager(grid_range, self)
-            .await
-            .change_context(SpreadsheetManagerError::FailedToWriteRange)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if cell_range.column_count() != 2 {
-            return Err(report!(SpreadsheetManagerError::FailedToWriteRange))
-                .attach_printable_lazy(|| {
-                    format!(
-                        "Named range {} is not a two columns, trying to write col1 {:?} and col2 {:?} to it",
-                        name, col1_values, col2_values
-                    )
-                });
-        }
+    #[test]
+    fn test_truncate_column() {
+        let values = ["BTC", "ETH", "SOL", "ATOM"].map(CellValue::from);
 
-        let value_range =
-            ValueRange::from_two_columns(col1_values, col2_values, cell_range.row_count());
-        return self.write_named_range(name, value_range).await;
+        assert_eq!(
+            truncate_column(&values, 3, overflow_marker(2)),
+            ["BTC", "ETH", "+2 more"].map(CellValue::from)
+        );
+        assert_eq!(
+            truncate_column(&values, 1, overflow_marker(4)),
+            vec![CellValue::from("+4 more")]
+        );
     }
 }
@@ -0,0 +1,92 @@
+use std::sync::Mutex;
+
+use google_sheets4::api::{Request, ValueRange};
+
+use crate::domain::sheets::a1_notation::A1Notation;
+
+use super::spreadsheet_manager::{SpreadsheetManager, SpreadsheetManagerError};
+use super::spreadsheet_write::SpreadsheetWriteSink;
+
+/// Collects the writes of a routine run and sends them in a single `values_batchUpdate` on
+/// [`flush`](Self::flush), followed by a single `batchUpdate` for their number formats.
+/// Dropping the batch discards its writes, so a routine that fails halfway leaves the
+/// spreadsheet untouched.
+pub struct SpreadsheetWriteBatch<'a> {
+    spreadsheet_manager: &'a SpreadsheetManager,
+    pending: Mutex<Vec<ValueRange>>,
+    pending_formats: Mutex<Vec<Request>>,
+}
+
+// Only the number of pending writes, since the batch is traced on every write
+impl std::fmt::Debug for SpreadsheetWriteBatch<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpreadsheetWriteBatch")
+            .field("pending", &self.len())
+            .finish()
+    }
+}
+
+impl<'a> SpreadsheetWriteBatch<'a> {
+    pub fn new(spreadsheet_manager: &'a SpreadsheetManager) -> Self {
+        Self {
+            spreadsheet_manager,
+            pending: Mutex::new(Vec::new()),
+            pending_formats: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.lock().expect("Lock poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends every collected write at once; a batch without writes makes no request
+    pub async fn flush(self) -> error_stack::Result<(), SpreadsheetManagerError> {
+        let value_ranges = self.pending.into_inner().expect("Lock poisoned");
+        let formats = self.pending_formats.into_inner().expect("Lock poisoned");
+
+        if !value_ranges.is_empty() {
+            tracing::debug!("Flushing {} batched writes", value_ranges.len());
+            self.spreadsheet_manager.write_ranges(value_ranges).await?;
+        }
+        if !formats.is_empty() {
+            tracing::debug!("Flushing {} batched formats", formats.len());
+            self.spreadsheet_manager.format_cells(formats).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SpreadsheetWriteSink for SpreadsheetWriteBatch<'_> {
+    fn spreadsheet_manager(&self) -> &SpreadsheetManager {
+        self.spreadsheet_manager
+    }
+
+    async fn write_value_range(
+        &self,
+        range: &A1Notation,
+        mut value_range: ValueRange,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        value_range.range = Some(range.as_ref().to_owned());
+        self.pending
+            .lock()
+            .expect("Lock poisoned")
+            .push(value_range);
+        Ok(())
+    }
+
+    async fn write_formats(
+        &self,
+        requests: Vec<Request>,
+    ) -> error_stack::Result<(), SpreadsheetManagerError> {
+        self.pending_formats
+            .lock()
+            .expect("Lock poisoned")
+            .extend(requests);
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 use google_sheets4::api::ValueRange;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::borrow::Cow;
 
+use crate::domain::sheets::cell_value::{date_to_serial, CellValue};
+
 pub trait ValueRangeFactory {
     fn from_single_cell<'a, T: Into<Cow<'a, str>> + Clone>(cell_value: T) -> Self;
     fn from_single_column<'a, T: Into<Cow<'a, str>> + Clone>(
@@ -13,12 +15,39 @@ pub trait ValueRangeFactory {
         column_values2: &[T],
         row_count: u32,
     ) -> Self;
+    /// One row per index of the columns, as many as the shortest one has, padded with empty
+    /// rows up to `row_count`
+    fn from_typed_columns(columns: &[&[CellValue]], row_count: u32) -> Self;
 }
 
 fn wrap_value<'a, T: Into<Cow<'a, str>>>(value: T) -> Value {
     Value::String(value.into().into_owned())
 }
 
+/// How a typed cell is sent with `USER_ENTERED` input: numbers and dates as plain numbers (their
+/// format is applied separately), text escaped with `'` so the sheet keeps it as-is
+pub fn cell_value_to_json(cell: &CellValue) -> Value {
+    let number = |value: f64| {
+        if value.is_finite() {
+            json!(value)
+        } else {
+            json!("")
+        }
+    };
+
+    match cell {
+        CellValue::Empty => json!(""),
+        CellValue::Text(text) => json!(format!("'{}", text)),
+        CellValue::Entered(text) => json!(text),
+        CellValue::Number(value) | CellValue::Currency(value) | CellValue::Percent(value) => {
+            number(*value)
+        }
+        CellValue::Date(date) => number(date_to_serial(*date)),
+        CellValue::Formula(formula) if formula.starts_with('=') => json!(formula),
+        CellValue::Formula(formula) => json!(format!("={}", formula)),
+    }
+}
+
 impl ValueRangeFactory for ValueRange {
     fn from_single_cell<'a, T: Into<Cow<'a, str>> + Clone>(cell_value: T) -> Self {
         ValueRange {
@@ -75,6 +104,29 @@ impl ValueRangeFactory for ValueRange {
             values: Some(values),
         }
     }
+
+    fn from_typed_columns(columns: &[&[CellValue]], row_count: u32) -> Self {
+        let rows = columns.iter().map(|column| column.len()).min().unwrap_or(0);
+
+        let mut values = (0..rows)
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| cell_value_to_json(&column[row]))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        values.extend(
+            (rows..row_count as usize).map(|_| columns.iter().map(|_| json!("")).collect()),
+        );
+
+        Self {
+            major_dimension: Some("ROWS".to_string()),
+            range: None,
+            values: Some(values),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -143,4 +195,26 @@ mod tests {
             "Values should be two columns with Value::String(\"1\") and Value::String(\"3\") and Value::String(\"2\") and Value::String(\"4\")"
         );
     }
+
+    #[test]
+    fn test_from_typed_columns() {
+        let names = [
+            CellValue::from("BTC"),
+            CellValue::Entered("$1.5".to_owned()),
+        ];
+        let amounts = [
+            CellValue::Currency(2.5),
+            CellValue::Formula("A1*2".to_owned()),
+        ];
+
+        let value_range = ValueRange::from_typed_columns(&[&names, &amounts], 3);
+        assert_eq!(
+            value_range.values,
+            Some(vec![
+                vec![json!("'BTC"), json!(2.5)],
+                vec![json!("$1.5"), json!("=A1*2")],
+                vec![json!(""), json!("")],
+            ])
+        );
+    }
 }
@@ -0,0 +1,2 @@
+pub mod cosmos_use_cases;
+pub mod solana_use_cases;
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use error_stack::ResultExt;
+
+use crate::adapters::blockchain::cosmos::balance_fetcher::CosmosBalanceFetcher;
+use crate::adapters::config::blockchain_config::CosmosBlockchainConfig;
+use crate::application::exchange::use_cases::{ExchangeUseCases, ExchangeUseCasesError};
+use crate::domain::exchange::BalanceUpdateTarget;
+
+/// Balances of the Cosmos Hub, Osmosis, Celestia and Injective addresses, summed per symbol
+pub struct CosmosUseCases {
+    balance_fetcher: CosmosBalanceFetcher,
+}
+
+impl CosmosUseCases {
+    pub fn new(config: &CosmosBlockchainConfig) -> Self {
+        Self {
+            balance_fetcher: CosmosBalanceFetcher::new(config),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExchangeUseCases for CosmosUseCases {
+    fn exchange_name(&self) -> &'static str {
+        "Cosmos"
+    }
+
+    fn spreadsheet_target(&self) -> BalanceUpdateTarget {
+        BalanceUpdateTarget::Cosmos
+    }
+
+    async fn fetch_balances(
+        &self,
+    ) -> error_stack::Result<HashMap<String, f64>, ExchangeUseCasesError> {
+        let balances = self
+            .balance_fetcher
+            .fetch_balances()
+            .await
+            .change_context(ExchangeUseCasesError::FetchBalancesError("Cosmos"))?
+            .into_iter()
+            .map(|token| (token.symbol, token.balance))
+            .collect::<HashMap<_, _>>();
+
+        tracing::trace!("Fetched Cosmos balances: {:?}", balances);
+
+        Ok(balances)
+    }
+}
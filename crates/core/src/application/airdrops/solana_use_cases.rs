@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use error_stack::ResultExt;
+
+use crate::adapters::blockchain::solana::balance_fetcher::SolanaBalanceFetcher;
+use crate::adapters::config::blockchain_config::SolanaBlockchainConfig;
+use crate::application::exchange::use_cases::{ExchangeUseCases, ExchangeUseCasesError};
+use crate::domain::exchange::BalanceUpdateTarget;
+
+/// Treats a Solana wallet as an exchange account so its balances can be written by
+/// [`ExchangeBalancesRoutine`](crate::application::exchange::exchange_balances_routine::ExchangeBalancesRoutine)
+pub struct SolanaUseCases {
+    address: Box<str>,
+    balance_fetcher: SolanaBalanceFetcher,
+}
+
+impl SolanaUseCases {
+    pub fn new(config: &SolanaBlockchainConfig) -> Self {
+        Self {
+            address: config.address.clone(),
+            balance_fetcher: SolanaBalanceFetcher::new(config),
+        }
+    }
+
+    /// Keeps the Solana token list under `dir` between runs
+    pub fn with_token_list_cache(mut self, dir: &str) -> Self {
+        self.balance_fetcher = self.balance_fetcher.with_token_list_cache(dir);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ExchangeUseCases for SolanaUseCases {
+    fn exchange_name(&self) -> &'static str {
+        "Solana"
+    }
+
+    fn spreadsheet_target(&self) -> BalanceUpdateTarget {
+        BalanceUpdateTarget::Solana
+    }
+
+    async fn fetch_balances(
+        &self,
+    ) -> error_stack::Result<HashMap<String, f64>, ExchangeUseCasesError> {
+        let balances = self
+            .balance_fetcher
+            .fetch_balances(&self.address)
+            .await
+            .change_context(ExchangeUseCasesError::FetchBalancesError("Solana"))?
+            .into_iter()
+            .map(|token| (token.symbol, token.balance))
+            .collect::<HashMap<_, _>>();
+
+        tracing::trace!("Fetched Solana balances: {:?}", balances);
+
+        Ok(balances)
+    }
+}
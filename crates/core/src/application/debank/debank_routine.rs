@@ -7,12 +7,16 @@ use tracing::{event, instrument, Level};
 
 use crate::adapters::config::blockchain_config::MultiEvmBlockchainConfig;
 use crate::adapters::debank::aah_parser::{AaHParser, TokenBalance};
+use crate::adapters::debank::api_client::DebankApiClient;
 use crate::adapters::debank::balance::format_balance;
 use crate::adapters::sheets::spreadsheet_manager::{SpreadsheetManager, SpreadsheetManagerError};
 use crate::adapters::sheets::spreadsheet_write::SpreadsheetWrite;
+use crate::adapters::sheets::spreadsheet_write_batch::SpreadsheetWriteBatch;
 use crate::domain::debank::{Chain, DebankResponse};
 use crate::domain::routine::{Routine, RoutineError};
+use crate::domain::sheets::cell_value::CellValue;
 use crate::domain::sheets::ranges;
+use crate::ports::debank_portfolio_source::DebankPortfolioSource;
 
 // Minimum USD value for positions to be included in the spreadsheet
 const MIN_USD_VALUE: f64 = 1.0;
@@ -176,6 +180,7 @@ pub static RELEVANT_DEBANK_TOKENS: LazyLock<Vec<RelevantDebankToken>> = LazyLock
 pub struct DebankRoutine {
     config: MultiEvmBlockchainConfig,
     spreadsheet_manager: Arc<SpreadsheetManager>,
+    portfolio_source: Arc<dyn DebankPortfolioSource>,
 }
 
 impl fmt::Debug for DebankRoutine {
@@ -191,11 +196,21 @@ impl DebankRoutine {
         spreadsheet_manager: Arc<SpreadsheetManager>,
     ) -> Self {
         Self {
+            portfolio_source: Arc::new(DebankApiClient::new(config.debank_api_url.to_string())),
             config,
             spreadsheet_manager,
         }
     }
 
+    /// Replaces the Debank scraper API as the source of the portfolios
+    pub fn with_portfolio_source(
+        mut self,
+        portfolio_source: Arc<dyn DebankPortfolioSource>,
+    ) -> Self {
+        self.portfolio_source = portfolio_source;
+        self
+    }
+
     #[instrument(skip(self), name = "DebankRoutine::load_debank_data")]
     async fn load_debank_data(
         &self,
@@ -206,23 +221,10 @@ impl DebankRoutine {
             "Loading Debank data via API"
         );
 
-        // Create API client
-        let api_client = crate::adapters::debank::api_client::DebankApiClient::new(
-            "http://localhost:8000".to_string(),
-        );
-
-        // Create scrape request
-        let scrape_request = crate::adapters::debank::api_client::ScrapeRequest {
-            wallet_address: wallet_address.to_string(),
-            chain: None, // No chain filter by default
-            save_html: false,
-            save_screenshot: false,
-            headless: true,
-        };
-
         // Scrape wallet data via API
-        let debank_response = api_client
-            .scrape_wallet(scrape_request)
+        let debank_response = self
+            .portfolio_source
+            .portfolio(wallet_address)
             .await
             .change_context(RoutineError::routine_failure(
                 "Failed to scrape wallet data via API".to_string(),
@@ -278,32 +280,37 @@ impl DebankRoutine {
         Ok((aah_parser.balances, chain_order))
     }
 
-    #[instrument]
+    #[instrument(skip(batch))]
     async fn update_debank_balance_on_spreadsheet(
         &self,
+        batch: &SpreadsheetWriteBatch<'_>,
         balance: f64,
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
-        self.spreadsheet_manager
-            .write_named_cell(ranges::airdrops::RW_DEBANK_TOTAL_USD, &balance.to_string())
+        batch
+            .write_named_typed_cell(
+                ranges::airdrops::RW_DEBANK_TOTAL_USD,
+                &CellValue::Currency(balance),
+            )
             .await?;
 
         Ok(())
     }
 
     #[instrument(
-        skip(self, balances, chain_order),
+        skip(self, batch, balances, chain_order),
         name = "DebankRoutine::update_debank_eth_AaH_balances_on_spreadsheet"
     )]
     #[allow(non_snake_case)] // Specially allowed for the sake of readability of an acronym
     async fn update_debank_eth_AaH_balances_on_spreadsheet(
         &self,
+        batch: &SpreadsheetWriteBatch<'_>,
         balances: HashMap<String, HashMap<String, TokenBalance>>,
         chain_order: Vec<String>,
     ) -> error_stack::Result<(), SpreadsheetManagerError> {
         futures::future::join_all(
             RELEVANT_DEBANK_TOKENS
                 .iter()
-                .map(|token| self.update_balances_for_token(token, &balances, &chain_order))
+                .map(|token| self.update_balances_for_token(batch, token, &balances, &chain_order))
                 .collect::<Vec<_>>(),
         )
         .await
@@ -313,9 +320,10 @@ impl DebankRoutine {
         Ok(())
     }
 
-    #[instrument]
+    #[instrument(skip(batch))]
     async fn update_balances_for_token(
         &self,
+        batch: &SpreadsheetWriteBatch<'_>,
         token: &RelevantDebankToken,
         balances: &HashMap<String, HashMap<String, TokenBalance>>,
         chain_order: &Vec<String>,
@@ -407,7 +415,7 @@ impl DebankRoutine {
             "Final sorted order for token"
         );
 
-        self.spreadsheet_manager
+        batch
             .write_named_two_columns(
                 token.range_balance_two_cols,
                 names.as_slice(),
@@ -524,20 +532,30 @@ impl DebankRoutine {
             "Using total balance from API"
         );
 
+        // Both updates are sent together, so a failure in either leaves the sheet untouched
+        let batch = SpreadsheetWriteBatch::new(&self.spreadsheet_manager);
+
         tracing::trace!("Updating TOTAL balance on the spreadsheet");
-        self.update_debank_balance_on_spreadsheet(total_balance)
+        self.update_debank_balance_on_spreadsheet(&batch, total_balance)
             .await
             .change_context(RoutineError::routine_failure(format!(
                 "Failed to update Debank balance on the spreadsheet"
             )))?;
 
         tracing::trace!("Updating AaH balances on the spreadsheet");
-        self.update_debank_eth_AaH_balances_on_spreadsheet(balances, chain_order)
+        self.update_debank_eth_AaH_balances_on_spreadsheet(&batch, balances, chain_order)
             .await
             .change_context(RoutineError::routine_failure(format!(
                 "Failed to update Debank AaH balances on the spreadsheet"
             )))?;
 
+        batch
+            .flush()
+            .await
+            .change_context(RoutineError::routine_failure(
+                "Failed to write Debank balances to the spreadsheet",
+            ))?;
+
         tracing::info!("Debank: ✅ Updated Debank balance on the spreadsheet");
         Ok(())
     }
@@ -564,3 +582,91 @@ impl Routine for DebankRoutine {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::adapters::config::sheets_config::SpreadsheetConfig;
+    use crate::adapters::sheets::backend::in_memory::InMemorySheetsBackend;
+    use crate::domain::debank::{ChainWallet, SpotTokenInfo};
+    use crate::domain::sheets::ranges::DEFAULT_LAYOUT;
+    use crate::ports::debank_portfolio_source::DebankPortfolioSourceError;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakePortfolioSource;
+
+    #[async_trait::async_trait]
+    impl DebankPortfolioSource for FakePortfolioSource {
+        async fn portfolio(
+            &self,
+            _wallet_address: &str,
+        ) -> error_stack::Result<DebankResponse, DebankPortfolioSourceError> {
+            let token = |name: &str, amount: &str, usd_value: &str| SpotTokenInfo {
+                name: name.to_owned(),
+                price: String::new(),
+                amount: amount.to_owned(),
+                usd_value: usd_value.to_owned(),
+            };
+
+            Ok(DebankResponse {
+                total_usd_value: "$5,500".to_owned(),
+                chains: vec![Chain {
+                    name: "Arbitrum".to_owned(),
+                    wallet_info: Some(ChainWallet {
+                        usd_value: "$5,500".to_owned(),
+                        tokens: vec![
+                            token("ETH", "1.5", "$4,500"),
+                            token("USDC", "1000", "$1,000"),
+                        ],
+                    }),
+                    project_info: vec![],
+                }],
+                metadata: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_total_and_token_positions() {
+        let backend = Arc::new(
+            InMemorySheetsBackend::from_layout(DEFAULT_LAYOUT)
+                // Left from a previous run, cleared since the position is gone
+                .with_values(
+                    "'AaH'!D3",
+                    vec![vec![json!("Base - <wallet> (ETH)"), json!(2)]],
+                ),
+        );
+        let config = MultiEvmBlockchainConfig {
+            addresses: vec!["0xabc123".into()],
+            debank_api_url: "http://localhost:8000".into(),
+        };
+
+        DebankRoutine::new(
+            config,
+            Arc::new(backend.spreadsheet_manager(SpreadsheetConfig::default())),
+        )
+        .with_portfolio_source(Arc::new(FakePortfolioSource))
+        .run()
+        .await
+        .unwrap();
+
+        assert_eq!(backend.values("'Airdrops'!A2"), vec![vec![json!(5500.0)]]);
+        assert_eq!(
+            backend.values("'AaH'!D2:E201"),
+            vec![vec![
+                json!("Arbitrum - <wallet> (ETH) (0xabc1)"),
+                json!(1.5)
+            ]]
+        );
+        assert_eq!(
+            backend.values("'AaH'!A2:B201"),
+            vec![vec![
+                json!("Arbitrum - <wallet> (USDC) (0xabc1)"),
+                json!(1000.0)
+            ]]
+        );
+    }
+}
@@ -63,3 +63,33 @@ impl ExchangeUseCases for BinanceUseCases {
         Ok(balances)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::adapters::config::binance_config::BinanceConfig;
+    use crate::adapters::http::fixture_server::{credential, FixtureServer};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_balances_from_account() {
+        let server = FixtureServer::start("binance_account", "https://api.binance.com").await;
+        let use_cases = BinanceUseCases::new(BinanceAccountFactory::new(BinanceConfig {
+            api_key: credential("BINANCE_API_KEY"),
+            secret_key: credential("BINANCE_SECRET_KEY"),
+            rest_api_endpoint: server.base_url().into(),
+        }));
+
+        let balances = use_cases.fetch_balances().await.unwrap();
+
+        // Empty balances are dropped, and USDC is tracked as USDT
+        assert_eq!(
+            balances,
+            HashMap::from([
+                ("BTC".to_string(), 0.0015),
+                ("ETH".to_string(), 0.3),
+                ("USDT".to_string(), 250.5),
+            ])
+        );
+    }
+}
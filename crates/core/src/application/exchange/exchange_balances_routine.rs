@@ -3,17 +3,25 @@ use std::{collections::HashMap, fmt, sync::Arc};
 use error_stack::ResultExt;
 use tracing::instrument;
 
+use crate::application::snapshots::save_snapshots;
 use crate::domain::{
     exchange::BalanceRepository,
     routine::{Routine, RoutineError},
 };
+use crate::ports::asset_price_source::AssetPriceSource;
+use crate::ports::snapshot_repository::{BalanceSnapshot, SnapshotRepository};
 
 use super::use_cases::ExchangeUseCases;
 
+/// Exchanges have a single account per configured key
+const SNAPSHOT_ACCOUNT: &str = "default";
+
 pub struct ExchangeBalancesRoutine<T: ExchangeUseCases> {
     routine_name: String,
     use_cases: T,
     persistence: Arc<dyn BalanceRepository>,
+    snapshots: Option<Arc<dyn SnapshotRepository>>,
+    asset_prices: Option<Arc<dyn AssetPriceSource>>,
 }
 
 impl<T: ExchangeUseCases> fmt::Debug for ExchangeBalancesRoutine<T> {
@@ -30,9 +38,47 @@ impl<T: ExchangeUseCases> ExchangeBalancesRoutine<T> {
             routine_name: format!("{} Balances", use_cases.exchange_name()),
             use_cases,
             persistence,
+            snapshots: None,
+            asset_prices: None,
         }
     }
 
+    /// Also appends every fetched balance to `snapshots`, if any, after each run
+    pub fn with_snapshots(mut self, snapshots: Option<Arc<dyn SnapshotRepository>>) -> Self {
+        self.snapshots = snapshots;
+        self
+    }
+
+    /// Prices the snapshots with `asset_prices`, if any
+    pub fn with_asset_prices(mut self, asset_prices: Option<Arc<dyn AssetPriceSource>>) -> Self {
+        self.asset_prices = asset_prices;
+        self
+    }
+
+    async fn save_snapshots(&self, balances: &HashMap<String, f64>) {
+        let Some(snapshots) = &self.snapshots else {
+            return;
+        };
+
+        let timestamp = chrono::Utc::now();
+        let exchange_name = self.use_cases.exchange_name();
+        let records = balances
+            .iter()
+            .filter(|(_, amount)| **amount > 0.0)
+            .map(|(asset, amount)| {
+                BalanceSnapshot::new(exchange_name, SNAPSHOT_ACCOUNT, asset, *amount, timestamp)
+            })
+            .collect::<Vec<_>>();
+
+        save_snapshots(
+            self.name(),
+            snapshots.as_ref(),
+            self.asset_prices.as_deref(),
+            records,
+        )
+        .await;
+    }
+
     #[instrument]
     fn order_balances(&self, token_names: &[String], balances: &HashMap<String, f64>) -> Vec<f64> {
         let mut token_balances = Vec::with_capacity(token_names.len());
@@ -82,6 +128,8 @@ impl<T: ExchangeUseCases> Routine for ExchangeBalancesRoutine<T> {
                 "Failed to update balances in persistence",
             ))?;
 
+        self.save_snapshots(&balance_by_token).await;
+
         tracing::info!(
             "{}: ✅ Updated {} balances on the spreadsheet",
             self.name(),
@@ -91,3 +139,115 @@ impl<T: ExchangeUseCases> Routine for ExchangeBalancesRoutine<T> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::adapters::config::sheets_config::SpreadsheetConfig;
+    use crate::adapters::exchange::spreadsheet_balance_repository::SpreadsheetBalanceRepository;
+    use crate::adapters::persistence::sqlite_snapshot_repository::SqliteSnapshotRepository;
+    use crate::adapters::price::spreadsheet_asset_prices::SpreadsheetAssetPrices;
+    use crate::adapters::sheets::backend::in_memory::InMemorySheetsBackend;
+    use crate::application::exchange::use_cases::ExchangeUseCasesError;
+    use crate::domain::exchange::BalanceUpdateTarget;
+    use crate::domain::sheets::ranges::DEFAULT_LAYOUT;
+    use crate::ports::snapshot_repository::SnapshotQuery;
+
+    use super::*;
+
+    struct FakeExchange;
+
+    #[async_trait::async_trait]
+    impl ExchangeUseCases for FakeExchange {
+        fn exchange_name(&self) -> &'static str {
+            "Kraken"
+        }
+
+        fn spreadsheet_target(&self) -> BalanceUpdateTarget {
+            BalanceUpdateTarget::Kraken
+        }
+
+        async fn fetch_balances(
+            &self,
+        ) -> error_stack::Result<HashMap<String, f64>, ExchangeUseCasesError> {
+            Ok(HashMap::from([
+                ("BTC".to_owned(), 0.25),
+                ("DOT".to_owned(), 120.0),
+                ("NOT_TRACKED".to_owned(), 1.0),
+            ]))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_balances_in_token_order() {
+        let backend = Arc::new(
+            InMemorySheetsBackend::from_layout(DEFAULT_LAYOUT).with_values(
+                "'Tokens'!B2",
+                vec![vec![json!("DOT")], vec![json!("ETH")], vec![json!("BTC")]],
+            ),
+        );
+        let repository = SpreadsheetBalanceRepository::new(Arc::new(
+            backend.spreadsheet_manager(SpreadsheetConfig::default()),
+        ));
+
+        ExchangeBalancesRoutine::new(FakeExchange, Arc::new(repository))
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            backend.values("'Balances'!B2:B201"),
+            vec![vec![json!(120.0)], vec![json!(0.0)], vec![json!(0.25)]]
+        );
+        assert!(backend.values("'Balances'!A2:A201").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_are_valued_at_the_spreadsheet_prices() {
+        let backend = Arc::new(
+            InMemorySheetsBackend::from_layout(DEFAULT_LAYOUT).with_values(
+                "'Tokens'!B2",
+                vec![
+                    vec![json!("DOT"), json!(6.5)],
+                    vec![json!("BTC"), json!(64000.0)],
+                ],
+            ),
+        );
+        let spreadsheet_manager =
+            Arc::new(backend.spreadsheet_manager(SpreadsheetConfig::default()));
+        let snapshots = Arc::new(SqliteSnapshotRepository::open_in_memory().unwrap());
+
+        ExchangeBalancesRoutine::new(
+            FakeExchange,
+            Arc::new(SpreadsheetBalanceRepository::new(Arc::clone(
+                &spreadsheet_manager,
+            ))),
+        )
+        .with_snapshots(Some(snapshots.clone()))
+        .with_asset_prices(Some(Arc::new(SpreadsheetAssetPrices::new(
+            spreadsheet_manager,
+        ))))
+        .run()
+        .await
+        .unwrap();
+
+        let mut saved = snapshots
+            .get_snapshots(&SnapshotQuery::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| (snapshot.asset, snapshot.price, snapshot.usd_value))
+            .collect::<Vec<_>>();
+        saved.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            saved,
+            vec![
+                ("BTC".to_owned(), Some(64000.0), Some(16000.0)),
+                ("DOT".to_owned(), Some(6.5), Some(780.0)),
+                ("NOT_TRACKED".to_owned(), None, None),
+            ]
+        );
+    }
+}
@@ -1,31 +1,104 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
 
 use error_stack::ResultExt;
+use futures::future::try_join_all;
 use regex::Regex;
 use tracing::instrument;
 
-use crate::adapters::blockchain::chains::{ARBITRUM, OPTIMISM, POLYGON};
-use crate::adapters::config::blockchain_config::BlockchainConfig;
-use crate::adapters::config::sheets_config::SpreadsheetConfig;
-use crate::adapters::sheets::cell_range::CellRange;
-use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
-use crate::adapters::sheets::spreadsheet_read::SpreadsheetRead;
-use crate::adapters::sheets::spreadsheet_write::SpreadsheetWrite;
+use crate::adapters::blockchain::bitcoin::balance_fetcher::BitcoinBalanceFetcher;
+use crate::adapters::blockchain::chains::ChainRegistry;
+use crate::adapters::blockchain::cosmos::balance_fetcher::CosmosBalanceFetcher;
+use crate::adapters::blockchain::solana::balance_fetcher::SolanaBalanceFetcher;
+use crate::adapters::config::blockchain_config::{
+    BitcoinWalletConfig, BlockchainConfig, WalletGroupConfig,
+};
+use crate::adapters::config::sheets_config::HoldSheetConfig;
+use crate::application::snapshots::save_snapshots;
 use crate::domain::blockchain::chain::Chain;
-use crate::domain::blockchain::explorer::FetchBalanceError;
+use crate::domain::blockchain::explorer::{FetchBalanceError, SpamCheck};
 use crate::domain::blockchain::token::Token;
 use crate::domain::blockchain::token_balance::TokenBalance;
 use crate::domain::routine::{Routine, RoutineError};
-use crate::domain::sheets::a1_notation::ToA1Notation;
-use crate::domain::sheets::cell_position::CellPosition;
-use crate::domain::sheets::column::Column;
-use crate::domain::sheets::ranges;
-use crate::domain::sheets::row::Row;
-
+use crate::ports::asset_price_source::AssetPriceSource;
+use crate::ports::hold_balance_repository::{
+    HoldBalanceColumn, HoldBalanceRepository, HoldBalanceSource, HoldBalanceTable,
+    HoldBalanceTarget,
+};
+use crate::ports::snapshot_repository::{BalanceSnapshot, SnapshotRepository};
+
+const SOLANA_SOURCE: &str = "Solana";
+const COSMOS_SOURCE: &str = "Cosmos";
+const BITCOIN_SOURCE: &str = "Bitcoin";
+
+/// A configured wallet group, with its fetchers built once
 #[derive(Debug)]
+struct WalletGroup {
+    name: String,
+    target: HoldBalanceTarget,
+    evm_address: Option<Box<str>>,
+    solana: Option<(SolanaBalanceFetcher, Box<str>)>,
+    cosmos: Option<CosmosBalanceFetcher>,
+    bitcoin: Option<BitcoinWalletConfig>,
+}
+
+impl WalletGroup {
+    fn new(
+        config: &WalletGroupConfig,
+        default_sheet: &HoldSheetConfig,
+        token_cache_dir: &str,
+    ) -> Self {
+        let sheet = config.sheet.as_ref().unwrap_or(default_sheet);
+
+        Self {
+            name: config.name.to_string(),
+            target: HoldBalanceTarget {
+                range: sheet.range.to_string(),
+                sheet_title: sheet.sheet_title.as_deref().map(str::to_owned),
+            },
+            evm_address: config.evm.as_ref().map(|evm| evm.address.clone()),
+            solana: config.solana.as_ref().map(|solana| {
+                (
+                    SolanaBalanceFetcher::new(solana).with_token_list_cache(token_cache_dir),
+                    solana.address.clone(),
+                )
+            }),
+            cosmos: config.cosmos.as_ref().map(CosmosBalanceFetcher::new),
+            bitcoin: config.bitcoin.clone(),
+        }
+    }
+}
+
+/// Balances of one wallet group on every source, aligned with the routine's source titles.
+/// `None` where the group has no wallet on that source.
+type GroupBalances = Vec<Option<HashMap<String, f64>>>;
+
+/// Writes the balances of every configured wallet group: one title per source (each EVM chain,
+/// then Solana, Cosmos and Bitcoin) with one column per group below it
 pub struct UpdateHoldBalanceOnSheetsRoutine {
-    sheets_config: SpreadsheetConfig,
-    blockchain_config: BlockchainConfig,
+    wallet_groups: Vec<WalletGroup>,
+    chain_registry: Arc<ChainRegistry>,
+    repository: Arc<dyn HoldBalanceRepository>,
+    snapshots: Option<Arc<dyn SnapshotRepository>>,
+    asset_prices: Option<Arc<dyn AssetPriceSource>>,
+}
+
+impl fmt::Debug for UpdateHoldBalanceOnSheetsRoutine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateHoldBalanceOnSheetsRoutine")
+            .field(
+                "wallet_groups",
+                &self
+                    .wallet_groups
+                    .iter()
+                    .map(|group| &group.name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
 }
 
 struct TokenBalanceProcessor;
@@ -46,6 +119,12 @@ impl TokenBalanceProcessor {
         }
     }
 
+    /// Name `token` is tracked under on the sheet
+    fn sheet_symbol(&self, token: &str) -> String {
+        let (translated_symbol, _) = self.translate_aave_supply_token(token);
+        self.translate_token_to_sheets_name(&translated_symbol)
+    }
+
     fn process_token_balance(&self, token: &str, balance: f64) -> TokenBalance<String> {
         let (translated_symbol, _) = self.translate_aave_supply_token(token);
 
@@ -54,28 +133,62 @@ impl TokenBalanceProcessor {
             _ => 1f64,
         };
 
-        let translated_symbol = self.translate_token_to_sheets_name(&translated_symbol);
-
         TokenBalance::<String> {
-            symbol: translated_symbol,
+            symbol: self.sheet_symbol(token),
             balance: balance * mul,
         }
     }
 }
 
 impl UpdateHoldBalanceOnSheetsRoutine {
-    pub fn new(sheets_config: SpreadsheetConfig, blockchain_config: BlockchainConfig) -> Self {
+    pub fn new(
+        blockchain_config: &BlockchainConfig,
+        default_sheet: &HoldSheetConfig,
+        chain_registry: Arc<ChainRegistry>,
+        repository: Arc<dyn HoldBalanceRepository>,
+    ) -> Self {
         Self {
-            sheets_config,
-            blockchain_config,
+            wallet_groups: blockchain_config
+                .wallet_groups
+                .iter()
+                .map(|group| {
+                    WalletGroup::new(group, default_sheet, &blockchain_config.token_cache_dir)
+                })
+                .collect(),
+            chain_registry,
+            repository,
+            snapshots: None,
+            asset_prices: None,
         }
     }
 
-    #[instrument]
+    /// Also appends the balances of every wallet group to `snapshots`, if any, after each run
+    pub fn with_snapshots(mut self, snapshots: Option<Arc<dyn SnapshotRepository>>) -> Self {
+        self.snapshots = snapshots;
+        self
+    }
+
+    /// Prices the snapshots with `asset_prices`, if any
+    pub fn with_asset_prices(mut self, asset_prices: Option<Arc<dyn AssetPriceSource>>) -> Self {
+        self.asset_prices = asset_prices;
+        self
+    }
+
+    fn source_titles(&self) -> Vec<String> {
+        self.chain_registry
+            .chains()
+            .iter()
+            .map(|chain| chain.name.clone())
+            .chain([SOLANA_SOURCE, COSMOS_SOURCE, BITCOIN_SOURCE].map(str::to_owned))
+            .collect()
+    }
+
+    #[instrument(skip(self, spam_check))]
     pub async fn fetch_all_evm_balances(
         &self,
         chain: &Chain,
         evm_address: &str,
+        spam_check: &dyn SpamCheck,
     ) -> error_stack::Result<HashMap<Arc<Token>, TokenBalance>, FetchBalanceError> {
         tracing::info!("Fetching balance for {}", chain.name);
 
@@ -94,7 +207,7 @@ impl UpdateHoldBalanceOnSheetsRoutine {
         tracing::info!("Fetching ERC20 balances for {}", chain.name);
         let erc20_balances = chain
             .explorer
-            .fetch_erc20_balances(evm_address)
+            .fetch_erc20_balances(evm_address, spam_check)
             .await
             .attach_printable_lazy(|| {
                 format!(
@@ -114,37 +227,206 @@ impl UpdateHoldBalanceOnSheetsRoutine {
         Ok(balances)
     }
 
-    #[instrument]
-    async fn fetch_balance_hold(&self, chain: &Chain) -> HashMap<Arc<Token>, TokenBalance<String>> {
-        self.fetch_all_evm_balances(chain, &self.blockchain_config.hold.evm.address)
+    /// Balances of `group` on every source, in the order of [`Self::source_titles`]
+    #[instrument(skip(self, group, spam_check), fields(group = %group.name))]
+    async fn fetch_group_balances(
+        &self,
+        group: &WalletGroup,
+        spam_check: &dyn SpamCheck,
+    ) -> error_stack::Result<GroupBalances, RoutineError> {
+        let chains = self.chain_registry.chains();
+
+        // Chains are scanned in parallel; any failure aborts the run before the sheet is touched
+        let evm = async {
+            let Some(evm_address) = &group.evm_address else {
+                return Ok(vec![None; chains.len()]);
+            };
+
+            try_join_all(chains.iter().map(|chain| async move {
+                self.fetch_all_evm_balances(chain, evm_address, spam_check)
+                    .await
+                    .map(|balances| Some(merge_balances(balances.into_values())))
+                    .change_context_lazy(|| {
+                        RoutineError::routine_failure(format!(
+                            "Failed to fetch '{}' chain balances of '{}'",
+                            chain.name, group.name
+                        ))
+                    })
+            }))
             .await
-            .expect(format!("Should fetch '{}' chain balances for hold", chain.name).as_str())
+        };
+
+        let solana = async {
+            let Some((fetcher, address)) = &group.solana else {
+                return Ok(None);
+            };
+
+            fetcher
+                .fetch_balances(address)
+                .await
+                .map(|balances| Some(merge_balances(balances)))
+                .change_context_lazy(|| {
+                    RoutineError::routine_failure(format!(
+                        "Failed to fetch Solana balances of '{}'",
+                        group.name
+                    ))
+                })
+        };
+
+        let cosmos = async {
+            let Some(fetcher) = &group.cosmos else {
+                return Ok(None);
+            };
+
+            fetcher
+                .fetch_balances()
+                .await
+                .map(|balances| Some(merge_balances(balances)))
+                .change_context_lazy(|| {
+                    RoutineError::routine_failure(format!(
+                        "Failed to fetch Cosmos balances of '{}'",
+                        group.name
+                    ))
+                })
+        };
+
+        let bitcoin = async {
+            match &group.bitcoin {
+                Some(wallet) => self.fetch_bitcoin_balance(wallet).await.map(Some),
+                None => Ok(None),
+            }
+        };
+
+        let (mut balances, solana, cosmos, bitcoin) =
+            futures::try_join!(evm, solana, cosmos, bitcoin)?;
+        balances.extend([solana, cosmos, bitcoin]);
+        Ok(balances)
     }
 
-    #[instrument]
-    async fn fetch_balance_hold_sc(
+    #[instrument(skip(self))]
+    async fn fetch_bitcoin_balance(
         &self,
-        chain: &Chain,
-    ) -> HashMap<Arc<Token>, TokenBalance<String>> {
-        self.fetch_all_evm_balances(chain, &self.blockchain_config.hold_sc.evm.address)
+        wallet: &BitcoinWalletConfig,
+    ) -> error_stack::Result<HashMap<String, f64>, RoutineError> {
+        let balance = BitcoinBalanceFetcher::new(wallet)
+            .change_context(RoutineError::routine_failure(
+                "Invalid bitcoin wallet descriptor",
+            ))?
+            .fetch_balance()
             .await
-            .expect(format!("Should fetch '{}' chain balances for hold_sc", chain.name).as_str())
-    }
+            .change_context(RoutineError::routine_failure(
+                "Failed to fetch bitcoin balance",
+            ))?;
 
-    #[instrument]
-    async fn create_spreadsheet_manager(&self) -> SpreadsheetManager {
-        SpreadsheetManager::new(self.sheets_config.clone()).await
+        Ok(merge_balances([balance]))
     }
+}
+
+/// Translates symbols to their sheet names and sums the balances that end up under the same name
+fn merge_balances(
+    balances: impl IntoIterator<Item = TokenBalance<String>>,
+) -> HashMap<String, f64> {
+    balances
+        .into_iter()
+        .fold(HashMap::new(), |mut acc, token_balance| {
+            let processed = TokenBalanceProcessor
+                .process_token_balance(&token_balance.symbol, token_balance.balance);
+            *acc.entry(processed.symbol).or_insert(0f64) += processed.balance;
+            acc
+        })
+        .into_iter()
+        .filter(|(_, balance)| *balance > 0.0)
+        .collect()
+}
 
-    #[instrument]
-    async fn get_token_names_from_spreadsheet(&self) -> Vec<String> {
-        let spreadsheet_manager = self.create_spreadsheet_manager().await;
+/// Whether a token symbol, once translated to its sheet name, is tracked on the sheet, which is
+/// where prices come from
+fn sheet_price_lookup(token_names: &[String]) -> impl Fn(&str) -> bool + Sync + '_ {
+    let priced: HashSet<&str> = token_names.iter().map(String::as_str).collect();
+    move |symbol: &str| priced.contains(TokenBalanceProcessor.sheet_symbol(symbol).as_str())
+}
 
-        spreadsheet_manager
-            .read_named_range(ranges::tokens::RO_NAMES)
-            .await
-            .expect("Should read token names from the spreadsheet")
+/// One snapshot per token held by each wallet group on each source, including the tokens
+/// that are not tracked on the sheet
+fn hold_snapshots<'a>(
+    source_titles: &[String],
+    groups: impl IntoIterator<Item = (&'a str, &'a GroupBalances)>,
+    timestamp: chrono::DateTime<chrono::Utc>,
+) -> Vec<BalanceSnapshot> {
+    groups
+        .into_iter()
+        .flat_map(|(group_name, balances)| {
+            source_titles
+                .iter()
+                .zip(balances)
+                .filter_map(|(source, balances)| Some((source, balances.as_ref()?)))
+                .flat_map(move |(source, balances)| {
+                    balances.iter().map(move |(asset, amount)| {
+                        BalanceSnapshot::new(source, group_name, asset, *amount, timestamp)
+                    })
+                })
+        })
+        .collect()
+}
+
+fn order_balances(token_names: &[String], balances: &HashMap<String, f64>) -> Vec<Option<f64>> {
+    token_names
+        .iter()
+        .map(|token_name| balances.get(token_name).copied())
+        .collect()
+}
+
+/// Splits the fetched balances per target. On each target, a source is written when any of the
+/// target's groups has a wallet on it, with one column per group (blank for groups without one).
+fn layout_by_target(
+    source_titles: &[String],
+    groups: &[(&str, &HoldBalanceTarget, GroupBalances)],
+    token_names: &[String],
+) -> Vec<HoldBalanceTable> {
+    let mut targets: Vec<&HoldBalanceTarget> = Vec::new();
+    for (_, target, _) in groups {
+        if !targets.contains(target) {
+            targets.push(*target);
+        }
     }
+
+    targets
+        .into_iter()
+        .map(|target| {
+            let target_groups = groups
+                .iter()
+                .filter(|(_, group_target, _)| *group_target == target)
+                .collect::<Vec<_>>();
+
+            let sources = source_titles
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| {
+                    target_groups
+                        .iter()
+                        .any(|(_, _, balances)| balances[*index].is_some())
+                })
+                .map(|(index, title)| HoldBalanceSource {
+                    title: title.clone(),
+                    columns: target_groups
+                        .iter()
+                        .map(|(name, _, balances)| HoldBalanceColumn {
+                            label: name.to_string(),
+                            balances: match &balances[index] {
+                                Some(balances) => order_balances(token_names, balances),
+                                None => vec![None; token_names.len()],
+                            },
+                        })
+                        .collect(),
+                })
+                .collect();
+
+            HoldBalanceTable {
+                target: target.clone(),
+                sources,
+            }
+        })
+        .collect()
 }
 
 #[async_trait::async_trait]
@@ -155,191 +437,99 @@ impl Routine for UpdateHoldBalanceOnSheetsRoutine {
 
     #[instrument(skip(self), name = "UpdateHoldBalanceOnSheetsRoutine::run")]
     async fn run(&self) -> error_stack::Result<(), RoutineError> {
-        let chains = vec![&POLYGON, &OPTIMISM, &ARBITRUM];
-
-        //Parallelize fetching balances from multiple chains
-        let tasks = chains.iter().map(|chain| async move {
-            let hold_balances_raw = self.fetch_balance_hold(chain).await;
-            let hold_sc_balances_raw = self.fetch_balance_hold_sc(chain).await;
-
-            let hold_balances_compressed = hold_balances_raw.into_iter().fold(
-                HashMap::new(),
-                |mut acc, (_, token_balance)| {
-                    let processed_token_balance = TokenBalanceProcessor
-                        .process_token_balance(&token_balance.symbol, token_balance.balance);
-
-                    let acc_entry = acc.entry(processed_token_balance.symbol.clone()).or_insert(
-                        TokenBalance::<String> {
-                            symbol: processed_token_balance.symbol,
-                            balance: 0f64,
-                        },
-                    );
-
-                    acc_entry.balance += processed_token_balance.balance;
-                    tracing::info!("{}: {}", acc_entry.symbol, acc_entry.balance);
-                    acc
-                },
-            );
+        if self.wallet_groups.is_empty() {
+            tracing::warn!("{}: no wallet groups configured", self.name());
+            return Ok(());
+        }
 
-            let hold_sc_balances_compressed = hold_sc_balances_raw.into_iter().fold(
-                HashMap::new(),
-                |mut acc, (_, token_balance)| {
-                    let processed_token_balance = TokenBalanceProcessor
-                        .process_token_balance(&token_balance.symbol, token_balance.balance);
-
-                    let acc_entry = acc.entry(processed_token_balance.symbol.clone()).or_insert(
-                        TokenBalance::<String> {
-                            symbol: processed_token_balance.symbol,
-                            balance: 0f64,
-                        },
-                    );
-
-                    acc_entry.balance += processed_token_balance.balance;
-                    tracing::info!("{}: {}", acc_entry.symbol, acc_entry.balance);
-                    acc
-                },
+        let token_names = self.repository.get_token_names().await.change_context(
+            RoutineError::routine_failure("Failed to get token names from persistence"),
+        )?;
+
+        let has_price = sheet_price_lookup(&token_names);
+        let spam_scan = self.chain_registry.spam_filter().scan(Some(&has_price));
+
+        let group_balances = try_join_all(
+            self.wallet_groups
+                .iter()
+                .map(|group| self.fetch_group_balances(group, &spam_scan)),
+        )
+        .await?;
+
+        let spam_report = spam_scan.into_report();
+        tracing::info!("Filtered {} spam tokens", spam_report.filtered.len());
+        for token in &spam_report.filtered {
+            tracing::info!(
+                "  {} ({}): {}",
+                token.symbol,
+                token.contract_address,
+                token.reasons.join(", ")
             );
+        }
 
-            (
-                chain.name,
-                (hold_balances_compressed, hold_sc_balances_compressed),
-            )
+        let source_titles = self.source_titles();
+        let snapshots = self.snapshots.as_ref().map(|snapshots| {
+            let group_names = self.wallet_groups.iter().map(|group| group.name.as_str());
+            let records = hold_snapshots(
+                &source_titles,
+                group_names.zip(&group_balances),
+                chrono::Utc::now(),
+            );
+            (snapshots, records)
         });
 
-        let tasks_results = futures::future::join_all(tasks).await;
-
-        let hashmaps = tasks_results.into_iter().collect::<HashMap<_, _>>();
-
-        tracing::info!("Chains scanned: {:?}", hashmaps.keys().collect::<Vec<_>>());
-
-        let spreadsheet_manager = SpreadsheetManager::new(self.sheets_config.clone()).await;
-
-        let named_range = spreadsheet_manager
-            .get_named_range(ranges::balances::hold::RW_DATA)
-            .await
-            .expect(
-                format!(
-                    "Should get named range '{}'",
-                    ranges::balances::hold::RW_DATA
-                )
-                .as_str(),
+        let groups = self
+            .wallet_groups
+            .iter()
+            .zip(group_balances)
+            .map(|(group, balances)| (group.name.as_str(), &group.target, balances))
+            .collect::<Vec<_>>();
+
+        let tables = layout_by_target(&source_titles, &groups, &token_names);
+        for table in &tables {
+            tracing::info!(
+                "Writing sources {:?} to '{}'",
+                table
+                    .sources
+                    .iter()
+                    .map(|source| &source.title)
+                    .collect::<Vec<_>>(),
+                table.target.range
             );
+        }
 
-        let cell_range =
-            CellRange::try_from_grid_range_with_sheet_manager(named_range, &spreadsheet_manager)
-                .await
-                .expect("Named range parsing error");
-
-        let mut chain_title_cell = cell_range.start;
-
-        for chain in chains {
-            tracing::info!("Balances for '{}'", chain.name);
-            spreadsheet_manager
-                .write_value(
-                    &chain_title_cell.to_a1_notation("Balance - Trezor HOLD".into()),
-                    chain.name,
-                )
-                .await
-                .expect("Should write chain title");
-
-            let chain_title_col: Column = chain_title_cell.col;
-            let chain_title_row = chain_title_cell.row;
-
-            let wallet_hold_title_cell = chain_title_cell.clone() + Row::from_index(1);
-            let wallet_hold_title_cell_a1 =
-                wallet_hold_title_cell.to_a1_notation("Balance - Trezor HOLD".into());
-            let wallet_hold_sc_title_cell = wallet_hold_title_cell + Column::from_index(1);
-
-            spreadsheet_manager
-                .write_value(&wallet_hold_title_cell_a1, "Hold")
-                .await
-                .expect("Should write wallet hold title");
-
-            spreadsheet_manager
-                .write_value(
-                    &wallet_hold_sc_title_cell.to_a1_notation("Balance - Trezor HOLD".into()),
-                    "SC",
-                )
-                .await
-                .expect("Should write wallet hold sc title");
-
-            let (hold_balances, hold_sc_balances) = hashmaps
-                .get(chain.name)
-                .expect(format!("Should get '{}' chain balances", chain.name).as_str());
-
-            let token_names = self.get_token_names_from_spreadsheet().await;
-
-            let hold_col = chain_title_col;
-            let hold_sc_col: Column = chain_title_col + Column::from_index(1);
-
-            let tokens_start_row = chain_title_row + Row::from_index(1);
-            let tokens_end_row = cell_range.end.row;
-
-            let hold_balances_range = CellRange {
-                start: CellPosition {
-                    col: hold_col,
-                    row: tokens_start_row,
-                },
-                end: CellPosition {
-                    col: hold_col,
-                    row: tokens_end_row,
-                },
-                sheet_title: Some("Balance - Trezor HOLD".into()),
-            };
-
-            let hold_sc_balances_range = CellRange {
-                start: CellPosition {
-                    col: hold_sc_col,
-                    row: tokens_start_row,
-                },
-                end: CellPosition {
-                    col: hold_sc_col,
-                    row: tokens_end_row,
-                },
-                sheet_title: Some("Balance - Trezor HOLD".into()),
-            };
-
-            tracing::info!("  Hold balances:");
-            let hold_tokens_in_order = token_names.iter().fold(Vec::new(), |mut acc, token| {
-                let token_balance = match hold_balances.get(token) {
-                    Some(token_balance) => token_balance.balance.to_string(),
-                    None => "".to_owned(),
-                };
-
-                acc.push(token_balance);
-                acc
-            });
-
-            let hold_sc_tokens_in_order = token_names.iter().fold(Vec::new(), |mut acc, token| {
-                let token_balance = match hold_sc_balances.get(token) {
-                    Some(token_balance) => token_balance.balance.to_string(),
-                    None => "".to_owned(),
-                };
-
-                acc.push(token_balance);
-                acc
-            });
-
-            tracing::info!("  Hold balances: {:?}", hold_tokens_in_order);
-            spreadsheet_manager
-                .write_column(&hold_balances_range, hold_tokens_in_order.as_slice())
-                .await
-                .expect("Should write hold balances");
-
-            tracing::info!("  SC balances: {:?}", hold_tokens_in_order);
-            spreadsheet_manager
-                .write_column(&hold_sc_balances_range, hold_sc_tokens_in_order.as_slice())
-                .await
-                .expect("Should write hold sc balances");
-
-            chain_title_cell = chain_title_cell + Column::from_index(2);
+        self.repository
+            .update_hold_balances(&tables)
+            .await
+            .change_context(RoutineError::routine_failure(
+                "Failed to update hold balances",
+            ))?;
+
+        if let Some((snapshots, records)) = snapshots {
+            save_snapshots(
+                self.name(),
+                snapshots.as_ref(),
+                self.asset_prices.as_deref(),
+                records,
+            )
+            .await;
         }
+
+        tracing::info!(
+            "{}: ✅ Updated {} wallet groups",
+            self.name(),
+            self.wallet_groups.len()
+        );
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::adapters::blockchain::token::spam_filter::SpamFilter;
+    use crate::adapters::config::blockchain_config::{SpamFilterConfig, SpamFilterMode};
+    use crate::domain::blockchain::token::ERC20TokenInfo;
+
     use super::*;
 
     #[test]
@@ -365,4 +555,122 @@ mod tests {
             ("BTC".to_owned(), false)
         );
     }
+
+    #[test]
+    fn test_merge_balances() {
+        let balances = merge_balances([
+            TokenBalance::<String> {
+                symbol: "USDC".to_owned(),
+                balance: 1.0,
+            },
+            TokenBalance::<String> {
+                symbol: "aOptUSDC".to_owned(),
+                balance: 2.0,
+            },
+            TokenBalance::<String> {
+                symbol: "WBTC".to_owned(),
+                balance: 1e-8,
+            },
+        ]);
+
+        assert_eq!(balances.get("USDC"), Some(&3.0));
+        assert_eq!(balances.get("BTC"), Some(&1e2));
+        assert_eq!(
+            order_balances(&["BTC".to_owned(), "ETH".to_owned()], &balances),
+            vec![Some(1e2), None]
+        );
+    }
+
+    #[test]
+    fn test_priced_wrapped_and_aave_tokens_are_not_spam() {
+        let spam_filter = SpamFilter::from_config(&SpamFilterConfig {
+            mode: SpamFilterMode::Scoring,
+            ..SpamFilterConfig::default()
+        })
+        .unwrap();
+        let token_names = ["BTC".to_owned(), "USDC".to_owned()];
+        let has_price = sheet_price_lookup(&token_names);
+        let spam_scan = spam_filter.scan(Some(&has_price));
+        let token = |symbol: &str| ERC20TokenInfo {
+            token_name: symbol.into(),
+            token_symbol: symbol.into(),
+            contract_address: format!("0x{}", symbol).into(),
+            token_decimal: "18".into(),
+        };
+
+        // Only ever received, so the price decides
+        assert!(!spam_scan.is_spam(&token("WBTC"), true));
+        assert!(!spam_scan.is_spam(&token("aOptUSDC"), true));
+        assert!(spam_scan.is_spam(&token("NEW"), true));
+        assert_eq!(spam_scan.into_report().filtered.len(), 1);
+    }
+
+    #[test]
+    fn test_layout_by_target() {
+        let target = |range: &str| HoldBalanceTarget {
+            range: range.to_owned(),
+            sheet_title: None,
+        };
+        let (hold, other) = (target("Hold"), target("Other"));
+        let sources = ["Optimism".to_owned(), "Bitcoin".to_owned()];
+        let tokens = ["BTC".to_owned(), "ETH".to_owned()];
+        let eth = HashMap::from([("ETH".to_owned(), 1.0)]);
+        let btc = HashMap::from([("BTC".to_owned(), 0.5)]);
+
+        let layout = layout_by_target(
+            &sources,
+            &[
+                ("Hold", &hold, vec![Some(eth.clone()), Some(btc)]),
+                ("SC", &hold, vec![Some(eth.clone()), None]),
+                ("Cold", &other, vec![Some(eth), None]),
+            ],
+            &tokens,
+        );
+
+        assert_eq!(layout.len(), 2);
+        let hold_sources = &layout[0].sources;
+        assert_eq!(hold_sources.len(), 2);
+        assert_eq!(hold_sources[1].title, "Bitcoin");
+        assert_eq!(
+            hold_sources[1].columns,
+            vec![
+                HoldBalanceColumn {
+                    label: "Hold".to_owned(),
+                    balances: vec![Some(0.5), None],
+                },
+                HoldBalanceColumn {
+                    label: "SC".to_owned(),
+                    balances: vec![None, None],
+                },
+            ]
+        );
+
+        // No group on "Other" has a Bitcoin wallet
+        let other_sources = &layout[1].sources;
+        assert_eq!(layout[1].target, other);
+        assert_eq!(other_sources.len(), 1);
+        assert_eq!(other_sources[0].columns[0].balances, vec![None, Some(1.0)]);
+    }
+
+    #[test]
+    fn test_hold_snapshots() {
+        let sources = ["Optimism".to_owned(), "Bitcoin".to_owned()];
+        let hold = vec![
+            Some(HashMap::from([("ETH".to_owned(), 1.0)])),
+            Some(HashMap::from([("BTC".to_owned(), 0.5)])),
+        ];
+        let cold = vec![Some(HashMap::from([("OP".to_owned(), 3.0)])), None];
+        let timestamp = chrono::Utc::now();
+
+        let snapshots = hold_snapshots(&sources, [("Hold", &hold), ("Cold", &cold)], timestamp);
+
+        assert_eq!(
+            snapshots,
+            vec![
+                BalanceSnapshot::new("Optimism", "Hold", "ETH", 1.0, timestamp),
+                BalanceSnapshot::new("Bitcoin", "Hold", "BTC", 0.5, timestamp),
+                BalanceSnapshot::new("Optimism", "Cold", "OP", 3.0, timestamp),
+            ]
+        );
+    }
 }
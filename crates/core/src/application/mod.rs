@@ -1,5 +1,8 @@
+pub mod airdrops;
 pub mod debank;
 pub mod exchange;
 pub mod hold;
 pub mod price;
+pub mod scheduler;
 pub mod service;
+pub mod snapshots;
@@ -1,9 +1,11 @@
-use crate::adapters::price::price::get_token_prices;
+use crate::adapters::price::api::CoinGeckoApi;
 use crate::adapters::sheets::spreadsheet_manager::SpreadsheetManager;
 use crate::adapters::sheets::spreadsheet_read::SpreadsheetRead;
 use crate::adapters::sheets::spreadsheet_write::SpreadsheetWrite;
 use crate::domain::routine::{Routine, RoutineError};
+use crate::domain::sheets::cell_value::CellValue;
 use crate::domain::sheets::ranges;
+use crate::ports::token_price_source::TokenPriceSource;
 use error_stack::{report, ResultExt};
 use std::{collections::HashMap, sync::Arc};
 use thiserror::Error;
@@ -21,15 +23,23 @@ enum TokenPricesRoutineError {
 #[derive(Debug)]
 pub struct TokenPricesRoutine {
     pub spreadsheet_manager: Arc<SpreadsheetManager>,
+    price_source: Arc<dyn TokenPriceSource>,
 }
 
 impl TokenPricesRoutine {
     pub fn new(spreadsheet_manager: Arc<SpreadsheetManager>) -> Self {
         Self {
             spreadsheet_manager,
+            price_source: Arc::new(CoinGeckoApi::default()),
         }
     }
 
+    /// Replaces Coingecko as the source of the prices
+    pub fn with_price_source(mut self, price_source: Arc<dyn TokenPriceSource>) -> Self {
+        self.price_source = price_source;
+        self
+    }
+
     #[instrument]
     async fn get_token_ids_from_spreadsheet(
         &self,
@@ -49,17 +59,16 @@ impl TokenPricesRoutine {
     ) -> error_stack::Result<Vec<f64>, TokenPricesRoutineError> {
         let current_prices = self
             .spreadsheet_manager
-            .read_named_range(ranges::tokens::RW_PRICES)
+            .read_named_typed_range(ranges::tokens::RW_PRICES)
             .await
             .change_context(TokenPricesRoutineError::SpreadsheetError)?
             .into_iter()
-            .map(|x| {
-                x.replace(['$', ','], "")
-                    .parse::<f64>()
-                    .change_context(TokenPricesRoutineError::InvalidDataError {
-                        details: "Failed to parse price from spreadsheet",
-                    })
-                    .attach_printable_lazy(|| format!("Failed to parse price: {}", x))
+            .map(|cell| {
+                cell.as_f64()
+                    .ok_or(report!(TokenPricesRoutineError::InvalidDataError {
+                        details: "Price in spreadsheet is not a number",
+                    }))
+                    .attach_printable_lazy(|| format!("Invalid price: {:?}", cell))
             })
             .collect::<error_stack::Result<_, _>>()?;
 
@@ -94,10 +103,10 @@ impl TokenPricesRoutine {
     ) -> error_stack::Result<(), TokenPricesRoutineError> {
         let values = new_prices
             .iter()
-            .map(|x| format!("${}", x))
+            .map(|price| CellValue::Currency(*price))
             .collect::<Vec<_>>();
         self.spreadsheet_manager
-            .write_named_column(ranges::tokens::RW_PRICES, values.as_ref())
+            .write_named_typed_column(ranges::tokens::RW_PRICES, &values)
             .await
             .change_context(TokenPricesRoutineError::SpreadsheetError)?;
 
@@ -121,7 +130,7 @@ impl Routine for TokenPricesRoutine {
         )?;
 
         tracing::info!("Prices: ☁️  Getting prices of all tokens from Coingecko");
-        let prices = get_token_prices(tokens.as_ref()).await;
+        let prices = self.price_source.token_prices(tokens.as_ref()).await;
 
         tracing::info!("Prices: 📝 Reading the current prices from the spreadsheet");
         let spreadsheet_prices = self
@@ -147,3 +156,68 @@ impl Routine for TokenPricesRoutine {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::adapters::config::sheets_config::SpreadsheetConfig;
+    use crate::adapters::sheets::backend::in_memory::InMemorySheetsBackend;
+    use crate::domain::sheets::ranges::DEFAULT_LAYOUT;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakePriceSource(HashMap<String, Option<f64>>);
+
+    #[async_trait::async_trait]
+    impl TokenPriceSource for FakePriceSource {
+        async fn token_prices(&self, token_ids: &[String]) -> HashMap<String, Option<f64>> {
+            token_ids
+                .iter()
+                .filter_map(|id| self.0.get(id).map(|price| (id.clone(), *price)))
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_updates_prices_keeping_unknown_tokens() {
+        let backend = Arc::new(
+            InMemorySheetsBackend::from_layout(DEFAULT_LAYOUT)
+                .with_values(
+                    "'Tokens'!A2",
+                    vec![
+                        vec![json!("bitcoin")],
+                        vec![json!("ethereum")],
+                        vec![json!("delisted")],
+                    ],
+                )
+                .with_values(
+                    "'Tokens'!C2",
+                    vec![vec![json!(1)], vec![json!(2)], vec![json!(3)]],
+                ),
+        );
+        let prices = FakePriceSource(HashMap::from([
+            ("bitcoin".to_owned(), Some(100_000.0)),
+            ("ethereum".to_owned(), Some(4_000.5)),
+        ]));
+
+        TokenPricesRoutine::new(Arc::new(
+            backend.spreadsheet_manager(SpreadsheetConfig::default()),
+        ))
+        .with_price_source(Arc::new(prices))
+        .run()
+        .await
+        .unwrap();
+
+        // Coingecko does not know the last token, so its price is left as it was
+        assert_eq!(
+            backend.values("'Tokens'!C2:C201"),
+            vec![
+                vec![json!(100_000.0)],
+                vec![json!(4_000.5)],
+                vec![json!(3.0)]
+            ]
+        );
+    }
+}
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use error_stack::{report, ResultExt};
+use thiserror::Error;
+use tokio::task::{JoinError, JoinSet};
+use tracing::{error, info, instrument, warn};
+
+use crate::adapters::config::scheduler_config::{RoutineScheduleConfig, SchedulerConfig};
+use crate::adapters::sheets::backend::dry_run::DryRunRecorder;
+use crate::ports::application_service::ApplicationService;
+use crate::ports::schedule_state_repository::ScheduleStateRepository;
+
+/// Longest the scheduler sleeps between checks, so a late wake-up is never far off
+const MAX_SLEEP: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Error, Debug)]
+pub enum SchedulerError {
+    #[error("Invalid schedule for routine '{routine}'")]
+    InvalidSchedule { routine: String },
+    #[error("Routine '{routine}' is scheduled but not registered")]
+    UnknownRoutine { routine: String },
+    #[error("Failed to load the last run times")]
+    StateError,
+}
+
+/// When a routine runs
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    /// Every interval after the last start
+    Interval(chrono::Duration),
+    /// On the times of a cron expression, in UTC
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    pub fn from_config(
+        config: &RoutineScheduleConfig,
+    ) -> error_stack::Result<Self, SchedulerError> {
+        let invalid = || SchedulerError::InvalidSchedule {
+            routine: config.routine.to_string(),
+        };
+
+        match (config.interval_secs, config.cron.as_deref()) {
+            (Some(0), None) => {
+                Err(report!(invalid())).attach_printable("interval_secs must be positive")
+            }
+            (Some(interval_secs), None) => {
+                let interval_secs = i64::try_from(interval_secs)
+                    .change_context_lazy(invalid)
+                    .attach_printable("interval_secs is too large")?;
+                Ok(Schedule::Interval(chrono::Duration::seconds(interval_secs)))
+            }
+            (None, Some(expression)) => Self::cron(expression).change_context_lazy(invalid),
+            _ => Err(report!(invalid()))
+                .attach_printable("Set exactly one of interval_secs and cron"),
+        }
+    }
+
+    /// Parses a 5-field cron expression, or a 6/7-field one starting with seconds
+    pub fn cron(expression: &str) -> error_stack::Result<Self, cron::error::Error> {
+        let expression = match expression.split_whitespace().count() {
+            5 => format!("0 {}", expression),
+            _ => expression.to_owned(),
+        };
+
+        cron::Schedule::from_str(&expression)
+            .map(|schedule| Schedule::Cron(Box::new(schedule)))
+            .map_err(|error| report!(error).attach_printable(expression))
+    }
+
+    /// First run strictly after `time`, if the schedule has any
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Interval(interval) => Some(time + *interval),
+            Schedule::Cron(schedule) => schedule.after(&time).next(),
+        }
+    }
+
+    /// Next run of a routine last started at `last_run`. A run missed while the scheduler was
+    /// down is due right away, but only once, however many were missed. A routine that never
+    /// ran starts right away on an interval, or at the next cron time.
+    pub fn first_run(
+        &self,
+        last_run: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        match (last_run, self) {
+            (Some(last_run), _) => self.next_after(last_run),
+            (None, Schedule::Interval(_)) => Some(now),
+            (None, Schedule::Cron(_)) => self.next_after(now),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Job {
+    routine: String,
+    schedule: Schedule,
+    next_run: Option<DateTime<Utc>>,
+    /// Set while a run is in progress, so the next one is skipped instead of overlapping it
+    running: Arc<AtomicBool>,
+}
+
+/// Runs each scheduled routine of the application service on its own schedule. A run that
+/// comes due while the previous one is still in progress is skipped. Start times are saved, so
+/// a restart does not run everything again.
+pub struct Scheduler {
+    application_service: Arc<dyn ApplicationService>,
+    state: Arc<dyn ScheduleStateRepository>,
+    jobs: Vec<Job>,
+    /// Runs in progress, waited for before stopping
+    runs: JoinSet<()>,
+    dry_run: Option<(Arc<DryRunRecorder>, Option<Box<str>>)>,
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("state", &self.state)
+            .field("jobs", &self.jobs)
+            .finish()
+    }
+}
+
+impl Scheduler {
+    pub fn new(
+        application_service: Arc<dyn ApplicationService>,
+        state: Arc<dyn ScheduleStateRepository>,
+    ) -> Self {
+        Self {
+            application_service,
+            state,
+            jobs: Vec::new(),
+            runs: JoinSet::new(),
+            dry_run: None,
+        }
+    }
+
+    /// Fails on a routine scheduled twice: its runs would overlap, each tracked separately
+    pub fn from_config(
+        application_service: Arc<dyn ApplicationService>,
+        state: Arc<dyn ScheduleStateRepository>,
+        config: &SchedulerConfig,
+    ) -> error_stack::Result<Self, SchedulerError> {
+        config.routines.iter().try_fold(
+            Self::new(application_service, state),
+            |scheduler, routine| {
+                if scheduler
+                    .jobs
+                    .iter()
+                    .any(|job| job.routine == routine.routine.as_ref())
+                {
+                    return Err(report!(SchedulerError::InvalidSchedule {
+                        routine: routine.routine.to_string(),
+                    }))
+                    .attach_printable("The routine is scheduled more than once");
+                }
+
+                Ok(scheduler
+                    .with_schedule(routine.routine.as_ref(), Schedule::from_config(routine)?))
+            },
+        )
+    }
+
+    pub fn with_schedule(mut self, routine: impl Into<String>, schedule: Schedule) -> Self {
+        self.jobs.push(Job {
+            routine: routine.into(),
+            schedule,
+            next_run: None,
+            running: Arc::new(AtomicBool::new(false)),
+        });
+        self
+    }
+
+    /// Exports the writes recorded by `recorder` at the end of every run, either to `output` or
+    /// to stdout.
+    pub fn with_dry_run(mut self, recorder: Arc<DryRunRecorder>, output: Option<Box<str>>) -> Self {
+        self.dry_run = Some((recorder, output));
+        self
+    }
+
+    /// Runs the routines on their schedules until Ctrl-C, then waits for the runs in progress
+    #[instrument(skip(self))]
+    pub async fn serve(mut self) -> error_stack::Result<(), SchedulerError> {
+        self.start(Utc::now()).await?;
+
+        loop {
+            self.run_due(Utc::now()).await;
+
+            let sleep = self
+                .jobs
+                .iter()
+                .filter_map(|job| job.next_run)
+                .min()
+                .and_then(|next_run| (next_run - Utc::now()).to_std().ok())
+                .map_or(MAX_SLEEP, |until_next| until_next.min(MAX_SLEEP));
+
+            tokio::select! {
+                _ = tokio::time::sleep(sleep) => {}
+                Some(result) = self.runs.join_next(), if !self.runs.is_empty() => {
+                    log_failed_run(result);
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    info!(
+                        "Stopping the scheduler once the {} runs in progress end",
+                        self.runs.len()
+                    );
+                    self.wait_for_runs().await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn wait_for_runs(&mut self) {
+        while let Some(result) = self.runs.join_next().await {
+            log_failed_run(result);
+        }
+    }
+
+    /// Checks every scheduled routine is registered and plans the first runs from the saved
+    /// start times
+    async fn start(&mut self, now: DateTime<Utc>) -> error_stack::Result<(), SchedulerError> {
+        let available = self.application_service.list_available_routines().await;
+        if let Some(job) = self
+            .jobs
+            .iter()
+            .find(|job| !available.contains(&job.routine))
+        {
+            return Err(report!(SchedulerError::UnknownRoutine {
+                routine: job.routine.clone(),
+            }))
+            .attach_printable(format!("Registered routines: {}", available.join(", ")));
+        }
+        for routine in available
+            .iter()
+            .filter(|routine| !self.jobs.iter().any(|job| &job.routine == *routine))
+        {
+            info!("{} has no schedule, it will not run", routine);
+        }
+
+        let last_runs: HashMap<String, DateTime<Utc>> = self
+            .state
+            .last_runs()
+            .await
+            .change_context(SchedulerError::StateError)?;
+
+        for job in &mut self.jobs {
+            job.next_run = job
+                .schedule
+                .first_run(last_runs.get(&job.routine).copied(), now);
+            info!("{} next runs at {:?}", job.routine, job.next_run);
+        }
+
+        Ok(())
+    }
+
+    /// Starts every routine due at `now` in the background and plans its next run. Returns how
+    /// many were started.
+    async fn run_due(&mut self, now: DateTime<Utc>) -> usize {
+        let mut started = 0;
+
+        for job in &mut self.jobs {
+            if job.next_run.is_none_or(|next_run| next_run > now) {
+                continue;
+            }
+            job.next_run = job.schedule.next_after(now);
+
+            if job.running.swap(true, Ordering::SeqCst) {
+                warn!(
+                    "⏭️ {}: previous run still in progress, skipping this one",
+                    job.routine
+                );
+                continue;
+            }
+
+            if let Err(report) = self.state.save_last_run(&job.routine, now).await {
+                error!(
+                    "Failed to save the last run of {}: {:?}",
+                    job.routine, report
+                );
+            }
+
+            let application_service = Arc::clone(&self.application_service);
+            let routine = job.routine.clone();
+            let running = Arc::clone(&job.running);
+            let dry_run = self.dry_run.clone();
+            self.runs.spawn(async move {
+                info!("Running {}", routine);
+                match application_service.run_routine_by_name(&routine).await {
+                    Ok(()) => info!("✅ {}: OK", routine),
+                    Err(report) => error!("❌ {}: {:?}", routine, report),
+                }
+                if let Some((recorder, output)) = dry_run {
+                    match recorder.export(output.as_deref()) {
+                        Ok(count) => info!("Dry run: {} writes recorded", count),
+                        Err(e) => error!("Failed to export dry run writes: {:?}", e),
+                    }
+                }
+                running.store(false, Ordering::SeqCst);
+            });
+            started += 1;
+        }
+
+        started
+    }
+}
+
+fn log_failed_run(result: Result<(), JoinError>) {
+    if let Err(error) = result {
+        error!("A scheduled run did not complete: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use chrono::TimeZone;
+    use tokio::sync::Semaphore;
+
+    use super::*;
+    use crate::adapters::config::scheduler_config::SchedulerConfig;
+    use crate::adapters::persistence::file_schedule_state_repository::FileScheduleStateRepository;
+    use crate::adapters::persistence::temp_dir;
+    use crate::adapters::sheets::backend::dry_run::RecordedWrite;
+    use crate::application::service::CryptoBalanceApplicationService;
+    use crate::ports::routine::{Routine, RoutineError};
+
+    /// Counts its runs, each waiting for a permit of `release`
+    #[derive(Debug)]
+    struct BlockingRoutine {
+        runs: Arc<AtomicUsize>,
+        release: Arc<Semaphore>,
+    }
+
+    #[async_trait::async_trait]
+    impl Routine for BlockingRoutine {
+        fn name(&self) -> &str {
+            "BlockingRoutine"
+        }
+
+        async fn run(&self) -> error_stack::Result<(), RoutineError> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            self.release.acquire().await.unwrap().forget();
+            Ok(())
+        }
+    }
+
+    /// Records one write per run
+    #[derive(Debug)]
+    struct RecordingRoutine {
+        recorder: Arc<DryRunRecorder>,
+    }
+
+    #[async_trait::async_trait]
+    impl Routine for RecordingRoutine {
+        fn name(&self) -> &str {
+            "RecordingRoutine"
+        }
+
+        async fn run(&self) -> error_stack::Result<(), RoutineError> {
+            self.recorder.record(RecordedWrite {
+                named_range: None,
+                range: "Sheet1!A1".to_owned(),
+                old_values: Vec::new(),
+                new_values: vec![vec![serde_json::json!(1.0)]],
+                append: false,
+            });
+            Ok(())
+        }
+    }
+
+    fn state(name: &str) -> Arc<FileScheduleStateRepository> {
+        let dir = temp_dir(&format!("scheduler_{}", name));
+        Arc::new(FileScheduleStateRepository::new(
+            dir.join("schedule_state.json"),
+        ))
+    }
+
+    fn time(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_schedule_from_config() {
+        let config = |interval_secs: Option<u64>, cron: Option<&str>| RoutineScheduleConfig {
+            routine: "DebankRoutine".into(),
+            interval_secs,
+            cron: cron.map(Into::into),
+        };
+
+        let hourly = Schedule::from_config(&config(Some(3600), None)).unwrap();
+        assert_eq!(hourly.next_after(time(10, 15)), Some(time(11, 15)));
+
+        let every_six_hours = Schedule::from_config(&config(None, Some("0 */6 * * *"))).unwrap();
+        assert_eq!(every_six_hours.next_after(time(10, 15)), Some(time(12, 0)));
+        let with_seconds = Schedule::from_config(&config(None, Some("0 30 9 * * *"))).unwrap();
+        assert_eq!(with_seconds.next_after(time(8, 0)), Some(time(9, 30)));
+
+        assert!(Schedule::from_config(&config(None, Some("every hour"))).is_err());
+        assert!(Schedule::from_config(&config(Some(0), None)).is_err());
+        assert!(Schedule::from_config(&config(None, None)).is_err());
+        assert!(Schedule::from_config(&config(Some(60), Some("* * * * *"))).is_err());
+    }
+
+    #[test]
+    fn test_routines_scheduled_twice_are_rejected() {
+        let schedule = |interval_secs| RoutineScheduleConfig {
+            routine: "DebankRoutine".into(),
+            interval_secs: Some(interval_secs),
+            cron: None,
+        };
+        let config = SchedulerConfig {
+            routines: vec![schedule(3600), schedule(600)],
+            ..Default::default()
+        };
+        let application_service = Arc::new(CryptoBalanceApplicationService::new(Vec::new()));
+
+        let error =
+            Scheduler::from_config(application_service, state("twice"), &config).unwrap_err();
+        assert!(matches!(
+            error.current_context(),
+            SchedulerError::InvalidSchedule { routine } if routine == "DebankRoutine"
+        ));
+    }
+
+    #[test]
+    fn test_missed_runs_are_caught_up_once() {
+        let hourly = Schedule::Interval(chrono::Duration::hours(1));
+        let daily = Schedule::cron("0 9 * * *").unwrap();
+        let now = time(18, 0);
+
+        assert_eq!(hourly.first_run(None, now), Some(now));
+        assert_eq!(
+            hourly.first_run(Some(time(17, 30)), now),
+            Some(time(18, 30))
+        );
+        // Down for hours: a single run, right away
+        assert_eq!(hourly.first_run(Some(time(9, 0)), now), Some(time(10, 0)));
+
+        assert_eq!(
+            daily.first_run(None, now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 2, 9, 0, 0).unwrap())
+        );
+        assert_eq!(
+            daily.first_run(Some(time(9, 0)), now),
+            daily.first_run(None, now)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_runs_are_skipped_and_start_times_saved() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(Semaphore::new(0));
+        let application_service = Arc::new(CryptoBalanceApplicationService::new(vec![Box::new(
+            BlockingRoutine {
+                runs: Arc::clone(&runs),
+                release: Arc::clone(&release),
+            },
+        )]));
+        let state = state("overlap");
+
+        let mut scheduler = Scheduler::new(application_service.clone(), state.clone())
+            .with_schedule(
+                "BlockingRoutine",
+                Schedule::Interval(chrono::Duration::minutes(10)),
+            );
+        scheduler.start(time(12, 0)).await.unwrap();
+
+        assert_eq!(scheduler.run_due(time(12, 0)).await, 1);
+        assert_eq!(scheduler.run_due(time(12, 5)).await, 0);
+        // Due again, but the first run is still going
+        assert_eq!(scheduler.run_due(time(12, 10)).await, 0);
+
+        release.add_permits(1);
+        scheduler.wait_for_runs().await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        assert_eq!(scheduler.run_due(time(12, 20)).await, 1);
+        release.add_permits(1);
+        scheduler.wait_for_runs().await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        // A restart picks up from the saved start time instead of running right away
+        let mut restarted = Scheduler::new(application_service, state).with_schedule(
+            "BlockingRoutine",
+            Schedule::Interval(chrono::Duration::minutes(10)),
+        );
+        restarted.start(time(12, 25)).await.unwrap();
+        assert_eq!(restarted.run_due(time(12, 25)).await, 0);
+        assert_eq!(restarted.jobs[0].next_run, Some(time(12, 30)));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_are_exported_after_each_run() {
+        let recorder = Arc::new(DryRunRecorder::default());
+        let application_service = Arc::new(CryptoBalanceApplicationService::new(vec![Box::new(
+            RecordingRoutine {
+                recorder: Arc::clone(&recorder),
+            },
+        )]));
+        let dir = temp_dir("scheduler_dry_run");
+        let output = dir.join("dry_run.json");
+
+        let mut scheduler = Scheduler::new(application_service, state("dry_run"))
+            .with_schedule(
+                "RecordingRoutine",
+                Schedule::Interval(chrono::Duration::minutes(10)),
+            )
+            .with_dry_run(
+                Arc::clone(&recorder),
+                Some(output.display().to_string().into()),
+            );
+        scheduler.start(time(12, 0)).await.unwrap();
+
+        for now in [time(12, 0), time(12, 10)] {
+            assert_eq!(scheduler.run_due(now).await, 1);
+            scheduler.wait_for_runs().await;
+
+            // Only the writes of the run that just ended
+            let exported: Vec<serde_json::Value> =
+                serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+            assert_eq!(exported.len(), 1);
+            assert!(recorder.take().is_empty());
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unknown_routines_are_rejected() {
+        let application_service = Arc::new(CryptoBalanceApplicationService::new(Vec::new()));
+        let mut scheduler = Scheduler::new(application_service, state("unknown")).with_schedule(
+            "MissingRoutine",
+            Schedule::Interval(chrono::Duration::minutes(10)),
+        );
+
+        let error = scheduler.start(time(12, 0)).await.unwrap_err();
+        assert!(matches!(
+            error.current_context(),
+            SchedulerError::UnknownRoutine { routine } if routine == "MissingRoutine"
+        ));
+    }
+}
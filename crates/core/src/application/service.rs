@@ -1,16 +1,39 @@
 use crate::ports::application_service::{ApplicationService, ApplicationServiceError};
 use crate::ports::routine::{Routine, RoutineError};
+use crate::ports::schema_bootstrapper::{BootstrapReport, SchemaBootstrapper};
+use crate::ports::schema_validator::{SchemaReport, SchemaValidator};
+use error_stack::ResultExt;
 use futures::future::join_all;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::{error, info, instrument, Instrument};
 
 pub struct CryptoBalanceApplicationService {
     routines: Vec<Box<dyn Routine>>,
+    schema_validator: Option<Arc<dyn SchemaValidator>>,
+    schema_bootstrapper: Option<Arc<dyn SchemaBootstrapper>>,
 }
 
 impl CryptoBalanceApplicationService {
     pub fn new(routines: Vec<Box<dyn Routine>>) -> Self {
-        Self { routines }
+        Self {
+            routines,
+            schema_validator: None,
+            schema_bootstrapper: None,
+        }
+    }
+
+    pub fn with_schema_validator(mut self, schema_validator: Arc<dyn SchemaValidator>) -> Self {
+        self.schema_validator = Some(schema_validator);
+        self
+    }
+
+    pub fn with_schema_bootstrapper(
+        mut self,
+        schema_bootstrapper: Arc<dyn SchemaBootstrapper>,
+    ) -> Self {
+        self.schema_bootstrapper = Some(schema_bootstrapper);
+        self
     }
 }
 
@@ -119,4 +142,43 @@ impl ApplicationService for CryptoBalanceApplicationService {
             routine_names.join(", ")
         ))
     }
+
+    #[instrument(skip(self))]
+    async fn validate_schema(&self) -> error_stack::Result<SchemaReport, ApplicationServiceError> {
+        let schema_validator = self.schema_validator.as_ref().ok_or_else(|| {
+            error_stack::report!(ApplicationServiceError::SchemaValidationFailed {
+                details: "No spreadsheet configured".to_string(),
+            })
+        })?;
+
+        schema_validator.validate().await.change_context(
+            ApplicationServiceError::SchemaValidationFailed {
+                details: "Could not read the spreadsheet metadata".to_string(),
+            },
+        )
+    }
+
+    #[instrument(skip(self))]
+    async fn bootstrap_schema(
+        &self,
+    ) -> error_stack::Result<BootstrapReport, ApplicationServiceError> {
+        let schema_bootstrapper = self.schema_bootstrapper.as_ref().ok_or_else(|| {
+            error_stack::report!(ApplicationServiceError::SchemaBootstrapFailed {
+                details: "No spreadsheet configured".to_string(),
+            })
+        })?;
+
+        let report = schema_bootstrapper.bootstrap().await.change_context(
+            ApplicationServiceError::SchemaBootstrapFailed {
+                details: "Could not create the missing sheets and named ranges".to_string(),
+            },
+        )?;
+
+        info!(
+            "Bootstrap created {} sheets and {} named ranges",
+            report.created_sheets.len(),
+            report.created_ranges.len()
+        );
+        Ok(report)
+    }
 }
@@ -0,0 +1,36 @@
+use crate::ports::asset_price_source::AssetPriceSource;
+use crate::ports::snapshot_repository::{BalanceSnapshot, SnapshotRepository};
+
+/// Prices `records` with `prices`, when given, and appends them to `snapshots`. History is
+/// secondary to the spreadsheet, so failing to price or save it does not fail the run: the
+/// error is logged, and records without a price are saved without a USD value.
+pub async fn save_snapshots(
+    routine: &str,
+    snapshots: &dyn SnapshotRepository,
+    prices: Option<&dyn AssetPriceSource>,
+    records: Vec<BalanceSnapshot>,
+) {
+    let prices = match prices {
+        Some(prices) => match prices.asset_prices().await {
+            Ok(prices) => prices,
+            Err(error) => {
+                tracing::warn!("{}: Snapshots saved without prices: {:?}", routine, error);
+                Default::default()
+            }
+        },
+        None => Default::default(),
+    };
+
+    let records = records
+        .into_iter()
+        .map(|record| match prices.get(&record.asset) {
+            Some(price) => record.with_price(*price),
+            None => record,
+        })
+        .collect::<Vec<_>>();
+
+    match snapshots.save_snapshots(&records).await {
+        Ok(()) => tracing::trace!("{}: 🗄️  Saved {} snapshots", routine, records.len()),
+        Err(error) => tracing::error!("{}: Failed to save snapshots: {:?}", routine, error),
+    }
+}
@@ -4,7 +4,7 @@ use super::{explorer::BlockExplorer, token::Token};
 
 #[derive(Debug, Clone)]
 pub struct Chain {
-    pub name: &'static str,
+    pub name: String,
     pub native_token: Arc<Token>,
-    pub explorer: &'static dyn BlockExplorer,
+    pub explorer: Arc<dyn BlockExplorer>,
 }
@@ -4,7 +4,6 @@ use std::{collections::HashMap, fmt::Debug, sync::Arc};
 use thiserror::Error;
 
 use super::{
-    chain::Chain,
     token::{ERC20TokenInfo, Token},
     token_balance::TokenBalance,
 };
@@ -17,8 +16,14 @@ pub enum FetchBalanceError {
     ResponseParsingError,
 }
 
+/// Decides, for one run, which discovered ERC-20 tokens are spam and left out of the balances
+pub trait SpamCheck: Send + Sync {
+    /// `received_never_sent` tells whether the wallet only ever received this token
+    fn is_spam(&self, token: &ERC20TokenInfo, received_never_sent: bool) -> bool;
+}
+
 #[async_trait]
-pub trait BlockExplorer: Sync + Debug {
+pub trait BlockExplorer: Send + Sync + Debug {
     async fn fetch_native_balance(&self, address: &str) -> Result<TokenBalance, FetchBalanceError>;
 
     async fn fetch_erc20_balance(
@@ -30,7 +35,6 @@ pub trait BlockExplorer: Sync + Debug {
     async fn fetch_erc20_balances(
         &self,
         address: &str,
+        spam_check: &dyn SpamCheck,
     ) -> Result<HashMap<Arc<Token>, TokenBalance>, FetchBalanceError>;
-
-    fn chain(&self) -> &'static Chain;
 }
@@ -12,7 +12,7 @@ pub struct ERC20TokenInfo {
     pub token_decimal: Box<str>,
 }
 
-#[derive(strum::Display, Debug, Clone, PartialEq, Eq, Hash, EnumString)]
+#[derive(strum::Display, Debug, Clone, PartialEq, Eq, Hash, EnumString, serde::Deserialize)]
 pub enum NativeTokenSymbol {
     ETH,
     MATIC,
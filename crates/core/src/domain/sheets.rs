@@ -1,5 +1,6 @@
 pub mod a1_notation;
 pub mod cell_position;
+pub mod cell_value;
 pub mod column;
 pub mod ranges;
 pub mod row;
@@ -89,19 +89,35 @@ impl FromA1Notation for CellPosition {
 
     fn from_a1_notation(a1_notation: &A1Notation) -> error_stack::Result<Self, Self::Err> {
         let parts: A1NotationParts = generic_a1_notation_split(a1_notation);
+        let (col, row) = parts
+            .start
+            .split_at(parts.start.find(|c: char| c.is_ascii_digit()).unwrap_or(0));
 
         Ok(CellPosition {
-            row: parts
-                .start
-                .as_str()
+            row: row
                 .parse::<Row>()
                 .change_context(A1NotationParseError::RowParseError)
                 .attach_printable_lazy(|| format!("Failed to parse row from: {}", parts.start))?,
-            col: parts
-                .end
+            col: col
                 .parse::<Column>()
                 .change_context(A1NotationParseError::ColumnParseError)
-                .attach_printable_lazy(|| format!("Failed to parse column from: {}", parts.end))?,
+                .attach_printable_lazy(|| {
+                    format!("Failed to parse column from: {}", parts.start)
+                })?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_a1_notation() {
+        let position = CellPosition::from_a1_notation(&A1Notation("AB12".to_owned())).unwrap();
+        assert_eq!(position.col, Column::from_col(28));
+        assert_eq!(position.row, Row::from_row(12));
+
+        assert!(CellPosition::from_a1_notation(&A1Notation("AB".to_owned())).is_err());
+    }
+}
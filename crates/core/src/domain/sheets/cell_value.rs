@@ -0,0 +1,131 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// Spreadsheet epoch: date serial numbers count days from it
+const SERIAL_EPOCH: NaiveDate = match NaiveDate::from_ymd_opt(1899, 12, 30) {
+    Some(date) => date,
+    None => panic!("Invalid spreadsheet epoch"),
+};
+
+/// Content of a single cell, written as a typed value rather than text for the sheet to parse
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Empty,
+    /// Kept as-is, even when it looks like a number, a date or a formula
+    Text(String),
+    /// Parsed by the sheet as if typed in, e.g. `$1,234.50` or `=A1`. How untyped strings are
+    /// written.
+    Entered(String),
+    Number(f64),
+    /// Amount in USD
+    Currency(f64),
+    /// Fraction shown as a percentage: `0.25` is 25%
+    Percent(f64),
+    Date(NaiveDate),
+    /// Formula, with or without the leading `=`
+    Formula(String),
+}
+
+/// Number format applied to a cell, as the Sheets API `NumberFormat` type and pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub kind: &'static str,
+    pub pattern: &'static str,
+}
+
+impl CellValue {
+    /// Format the cell is given when written. `None` leaves the current format untouched.
+    pub fn number_format(&self) -> Option<NumberFormat> {
+        let (kind, pattern) = match self {
+            CellValue::Number(_) => ("NUMBER", "#,##0.00########"),
+            CellValue::Currency(_) => ("CURRENCY", "\"$\"#,##0.00########"),
+            CellValue::Percent(_) => ("PERCENT", "0.00%"),
+            CellValue::Date(_) => ("DATE", "yyyy-mm-dd"),
+            CellValue::Empty
+            | CellValue::Text(_)
+            | CellValue::Entered(_)
+            | CellValue::Formula(_) => return None,
+        };
+
+        Some(NumberFormat { kind, pattern })
+    }
+
+    /// The number behind numeric cells, dates included as serial numbers
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            CellValue::Number(value) | CellValue::Currency(value) | CellValue::Percent(value) => {
+                Some(*value)
+            }
+            CellValue::Date(date) => Some(date_to_serial(*date)),
+            CellValue::Empty
+            | CellValue::Text(_)
+            | CellValue::Entered(_)
+            | CellValue::Formula(_) => None,
+        }
+    }
+}
+
+impl From<f64> for CellValue {
+    fn from(value: f64) -> Self {
+        CellValue::Number(value)
+    }
+}
+
+impl From<String> for CellValue {
+    fn from(value: String) -> Self {
+        CellValue::Text(value)
+    }
+}
+
+impl From<&str> for CellValue {
+    fn from(value: &str) -> Self {
+        CellValue::Text(value.to_owned())
+    }
+}
+
+pub fn date_to_serial(date: NaiveDate) -> f64 {
+    (date - SERIAL_EPOCH).num_days() as f64
+}
+
+/// Serial number of a date-time cell: days, with the time of day as their fraction
+pub fn date_time_to_serial(date_time: NaiveDateTime) -> f64 {
+    (date_time - SERIAL_EPOCH.and_time(chrono::NaiveTime::MIN)).num_seconds() as f64 / 86_400.0
+}
+
+/// Date-time behind the serial number of a date or date-time cell, to the nearest second
+pub fn serial_to_date_time(serial: f64) -> Option<NaiveDateTime> {
+    // Beyond year 9999, which sheets do not display anyway
+    if !(0.0..=2_958_465.0).contains(&serial) {
+        return None;
+    }
+
+    let seconds = (serial * 86_400.0).round() as i64;
+    SERIAL_EPOCH
+        .and_time(chrono::NaiveTime::MIN)
+        .checked_add_signed(chrono::Duration::seconds(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_to_serial() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(date_to_serial(date), 45292.0);
+        assert_eq!(CellValue::Date(date).as_f64(), Some(45292.0));
+    }
+
+    #[test]
+    fn test_date_time_serial_round_trip() {
+        let date_time = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap();
+
+        assert_eq!(date_time_to_serial(date_time), 45292.75);
+        assert_eq!(serial_to_date_time(45292.75), Some(date_time));
+        // Serials read back from a sheet are not exact
+        assert_eq!(serial_to_date_time(45292.750000001), Some(date_time));
+        assert_eq!(serial_to_date_time(-1.0), None);
+    }
+}
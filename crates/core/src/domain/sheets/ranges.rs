@@ -58,4 +58,94 @@ pub mod AaH {
 
 pub mod airdrops {
     pub const RW_DEBANK_TOTAL_USD: &str = "Airdrops__cDebankTotalUSD";
+    pub const RW_SOLANA_AMOUNTS: &str = "Airdrops__vSolanaAmounts";
+    pub const RW_COSMOS_AMOUNTS: &str = "Airdrops__vCosmosAmounts";
 }
+
+/// Shape a named range must have for the routine writing to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeShape {
+    Cell,
+    Column,
+    TwoColumns,
+    /// Any number of columns, with at least `min_rows` rows
+    Table {
+        min_rows: u32,
+    },
+}
+
+impl RangeShape {
+    pub fn matches(&self, rows: u32, columns: u32) -> bool {
+        match self {
+            RangeShape::Cell => rows == 1 && columns == 1,
+            RangeShape::Column => columns == 1,
+            RangeShape::TwoColumns => columns == 2,
+            RangeShape::Table { min_rows } => rows >= *min_rows,
+        }
+    }
+}
+
+impl std::fmt::Display for RangeShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RangeShape::Cell => write!(f, "a single cell"),
+            RangeShape::Column => write!(f, "a single column"),
+            RangeShape::TwoColumns => write!(f, "two columns"),
+            RangeShape::Table { min_rows } => write!(f, "at least {} rows", min_rows),
+        }
+    }
+}
+
+/// Every range above with the shape its readers and writers expect. Keep it in sync when adding
+/// a range, so `validate` checks it and `bootstrap` creates it (see [`DEFAULT_LAYOUT`]).
+pub const EXPECTED_SHAPES: &[(&str, RangeShape)] = &[
+    (tokens::RO_IDS, RangeShape::Column),
+    (tokens::RO_NAMES, RangeShape::Column),
+    (tokens::RW_PRICES, RangeShape::Column),
+    (balances::binance::RW_AMOUNTS, RangeShape::Column),
+    (balances::kraken::RW_AMOUNTS, RangeShape::Column),
+    // Title row, wallet group label row, then at least one token row
+    (balances::hold::RW_DATA, RangeShape::Table { min_rows: 3 }),
+    (AaH::RW_USDT_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_ETH_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_PENDLE_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_BTC_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_ENA_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_GS_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_TANGO_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_PEAR_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_INST_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_SPECTRA_BALANCES_NAMES, RangeShape::TwoColumns),
+    (AaH::RW_HYPE_BALANCES_NAMES, RangeShape::TwoColumns),
+    (airdrops::RW_DEBANK_TOTAL_USD, RangeShape::Cell),
+    (airdrops::RW_SOLANA_AMOUNTS, RangeShape::Column),
+    (airdrops::RW_COSMOS_AMOUNTS, RangeShape::Column),
+];
+
+/// Title row, wallet label row and 200 token rows, 26 columns wide
+pub const HOLD_TABLE_LAYOUT: &str = "A1:Z202";
+
+/// Sheet and local A1 range `bootstrap` gives each range of [`EXPECTED_SHAPES`] when it is
+/// missing, leaving room for 200 tokens
+pub const DEFAULT_LAYOUT: &[(&str, &str, &str)] = &[
+    (tokens::RO_IDS, "Tokens", "A2:A201"),
+    (tokens::RO_NAMES, "Tokens", "B2:B201"),
+    (tokens::RW_PRICES, "Tokens", "C2:C201"),
+    (balances::binance::RW_AMOUNTS, "Balances", "A2:A201"),
+    (balances::kraken::RW_AMOUNTS, "Balances", "B2:B201"),
+    (balances::hold::RW_DATA, "Hold", HOLD_TABLE_LAYOUT),
+    (AaH::RW_USDT_BALANCES_NAMES, "AaH", "A2:B201"),
+    (AaH::RW_ETH_BALANCES_NAMES, "AaH", "D2:E201"),
+    (AaH::RW_PENDLE_BALANCES_NAMES, "AaH", "G2:H201"),
+    (AaH::RW_BTC_BALANCES_NAMES, "AaH", "J2:K201"),
+    (AaH::RW_ENA_BALANCES_NAMES, "AaH", "M2:N201"),
+    (AaH::RW_GS_BALANCES_NAMES, "AaH", "P2:Q201"),
+    (AaH::RW_TANGO_BALANCES_NAMES, "AaH", "S2:T201"),
+    (AaH::RW_PEAR_BALANCES_NAMES, "AaH", "V2:W201"),
+    (AaH::RW_INST_BALANCES_NAMES, "AaH", "Y2:Z201"),
+    (AaH::RW_SPECTRA_BALANCES_NAMES, "AaH", "AB2:AC201"),
+    (AaH::RW_HYPE_BALANCES_NAMES, "AaH", "AE2:AF201"),
+    (airdrops::RW_DEBANK_TOTAL_USD, "Airdrops", "A2"),
+    (airdrops::RW_SOLANA_AMOUNTS, "Airdrops", "B2:B201"),
+    (airdrops::RW_COSMOS_AMOUNTS, "Airdrops", "C2:C201"),
+];
@@ -1,4 +1,6 @@
 use crate::ports::routine::RoutineError;
+use crate::ports::schema_bootstrapper::BootstrapReport;
+use crate::ports::schema_validator::SchemaReport;
 use std::collections::HashMap;
 use thiserror::Error;
 
@@ -8,6 +10,10 @@ pub enum ApplicationServiceError {
     InitializationFailed { details: String },
     #[error("Routine execution failed: {details}")]
     RoutineExecutionFailed { details: String },
+    #[error("Schema validation failed: {details}")]
+    SchemaValidationFailed { details: String },
+    #[error("Schema bootstrap failed: {details}")]
+    SchemaBootstrapFailed { details: String },
     #[error("Multiple routines failed")]
     MultipleFailures {
         failures: HashMap<String, RoutineError>,
@@ -32,4 +38,13 @@ pub trait ApplicationService: Send + Sync {
     async fn list_available_routines(&self) -> Vec<String>;
 
     async fn health_check(&self) -> error_stack::Result<String, ApplicationServiceError>;
+
+    /// Checks that the spreadsheet has every range the routines use, without writing anything
+    async fn validate_schema(&self) -> error_stack::Result<SchemaReport, ApplicationServiceError>;
+
+    /// Creates the sheets and named ranges the spreadsheet is missing, leaving existing ones as
+    /// they are
+    async fn bootstrap_schema(
+        &self,
+    ) -> error_stack::Result<BootstrapReport, ApplicationServiceError>;
 }
@@ -0,0 +1,19 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AssetPriceSourceError {
+    #[error("Failed to fetch asset prices")]
+    FetchPricesError,
+}
+
+#[async_trait::async_trait]
+pub trait AssetPriceSource: Send + Sync + Debug {
+    /// USD price of each asset with a known price, by the name balances are stored under (e.g.
+    /// `BTC`)
+    async fn asset_prices(
+        &self,
+    ) -> error_stack::Result<HashMap<String, f64>, AssetPriceSourceError>;
+}
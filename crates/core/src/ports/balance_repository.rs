@@ -4,6 +4,8 @@ use thiserror::Error;
 pub enum BalanceRepositoryError {
     #[error("Failed to fetch token names from repository")]
     FetchTokenNamesError,
+    #[error("Failed to fetch balances from repository")]
+    FetchBalancesError,
     #[error("Failed to update balances in repository")]
     UpdateBalancesError,
 }
@@ -12,9 +14,21 @@ pub enum BalanceRepositoryError {
 pub enum BalanceUpdateTarget {
     Binance,
     Kraken,
+    Solana,
+    Cosmos,
 }
 
 impl BalanceUpdateTarget {
+    /// Stable identifier, used by repositories that are not keyed by named ranges
+    pub fn name(&self) -> &'static str {
+        match &self {
+            BalanceUpdateTarget::Binance => "binance",
+            BalanceUpdateTarget::Kraken => "kraken",
+            BalanceUpdateTarget::Solana => "solana",
+            BalanceUpdateTarget::Cosmos => "cosmos",
+        }
+    }
+
     pub fn range(&self) -> &'static str {
         match &self {
             BalanceUpdateTarget::Binance => {
@@ -23,6 +37,12 @@ impl BalanceUpdateTarget {
             BalanceUpdateTarget::Kraken => {
                 crate::domain::sheets::ranges::balances::kraken::RW_AMOUNTS
             }
+            BalanceUpdateTarget::Solana => {
+                crate::domain::sheets::ranges::airdrops::RW_SOLANA_AMOUNTS
+            }
+            BalanceUpdateTarget::Cosmos => {
+                crate::domain::sheets::ranges::airdrops::RW_COSMOS_AMOUNTS
+            }
         }
     }
 }
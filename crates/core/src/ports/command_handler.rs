@@ -14,6 +14,9 @@ pub enum Command {
     RunSpecificRoutine { name: String },
     ListRoutines,
     HealthCheck,
+    ValidateSchema,
+    BootstrapSchema,
+    Serve,
 }
 
 #[async_trait::async_trait]
@@ -0,0 +1,20 @@
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+use crate::domain::debank::DebankResponse;
+
+#[derive(Error, Debug)]
+pub enum DebankPortfolioSourceError {
+    #[error("Failed to fetch the Debank portfolio")]
+    FetchPortfolioError,
+}
+
+#[async_trait::async_trait]
+pub trait DebankPortfolioSource: Send + Sync + Debug {
+    /// Every chain, wallet token and project position of `wallet_address`
+    async fn portfolio(
+        &self,
+        wallet_address: &str,
+    ) -> error_stack::Result<DebankResponse, DebankPortfolioSourceError>;
+}
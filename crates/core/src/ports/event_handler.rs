@@ -23,6 +23,9 @@ pub enum CryptoEvent {
     RunDebankUpdate {
         timestamp: chrono::DateTime<chrono::Utc>,
     },
+    RunHoldUpdate {
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
     HealthCheck {
         timestamp: chrono::DateTime<chrono::Utc>,
     },
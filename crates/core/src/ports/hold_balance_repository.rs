@@ -0,0 +1,54 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HoldBalanceRepositoryError {
+    #[error("Failed to fetch token names from repository")]
+    FetchTokenNamesError,
+    #[error("Failed to update hold balances in repository")]
+    UpdateBalancesError,
+}
+
+/// Named range the hold balances are written to, optionally on another sheet than its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoldBalanceTarget {
+    pub range: String,
+    pub sheet_title: Option<String>,
+}
+
+/// Balances of one wallet group within a source, ordered like the repository token names.
+/// `None` means the group holds none of that token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoldBalanceColumn {
+    pub label: String,
+    pub balances: Vec<Option<f64>>,
+}
+
+/// Balances found on one source (an EVM chain, Solana, Cosmos or Bitcoin), one column per
+/// wallet group
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoldBalanceSource {
+    pub title: String,
+    pub columns: Vec<HoldBalanceColumn>,
+}
+
+/// Everything written to one target
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoldBalanceTable {
+    pub target: HoldBalanceTarget,
+    pub sources: Vec<HoldBalanceSource>,
+}
+
+#[async_trait::async_trait]
+pub trait HoldBalanceRepository: Send + Sync {
+    /// Fetches the token names from the repository. Those names are used to order the balances
+    /// of every column.
+    async fn get_token_names(&self)
+        -> error_stack::Result<Vec<String>, HoldBalanceRepositoryError>;
+
+    /// Replaces the hold balances at the target of each table with its sources, laid out side
+    /// by side. Either every table is written or none is.
+    async fn update_hold_balances(
+        &self,
+        tables: &[HoldBalanceTable],
+    ) -> error_stack::Result<(), HoldBalanceRepositoryError>;
+}
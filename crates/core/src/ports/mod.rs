@@ -1,6 +1,14 @@
 pub mod application_service;
+pub mod asset_price_source;
 pub mod balance_repository;
 pub mod command_handler;
+pub mod debank_portfolio_source;
 pub mod event_handler;
 pub mod exchange_use_cases;
+pub mod hold_balance_repository;
 pub mod routine;
+pub mod schedule_state_repository;
+pub mod schema_bootstrapper;
+pub mod schema_validator;
+pub mod snapshot_repository;
+pub mod token_price_source;
@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ScheduleStateRepositoryError {
+    #[error("Failed to load the last run times")]
+    LoadError,
+    #[error("Failed to save the last run time")]
+    SaveError,
+}
+
+/// When each scheduled routine last started, so a restarted scheduler picks up where it stopped
+#[async_trait::async_trait]
+pub trait ScheduleStateRepository: std::fmt::Debug + Send + Sync {
+    /// Last start time of every routine that ever ran, by routine name
+    async fn last_runs(
+        &self,
+    ) -> error_stack::Result<HashMap<String, DateTime<Utc>>, ScheduleStateRepositoryError>;
+
+    async fn save_last_run(
+        &self,
+        routine: &str,
+        started_at: DateTime<Utc>,
+    ) -> error_stack::Result<(), ScheduleStateRepositoryError>;
+}
@@ -0,0 +1,32 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SchemaBootstrapperError {
+    #[error("Failed to fetch the spreadsheet metadata")]
+    FetchMetadataError,
+    #[error("Invalid spreadsheet layout")]
+    InvalidLayoutError,
+    #[error("Failed to create the missing sheets and named ranges")]
+    CreateError,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootstrapReport {
+    pub created_sheets: Vec<String>,
+    pub created_ranges: Vec<String>,
+    /// Named ranges already in the spreadsheet, left untouched
+    pub existing_ranges: Vec<String>,
+}
+
+impl BootstrapReport {
+    pub fn is_empty(&self) -> bool {
+        self.created_sheets.is_empty() && self.created_ranges.is_empty()
+    }
+}
+
+#[async_trait::async_trait]
+pub trait SchemaBootstrapper: Send + Sync {
+    /// Creates the sheets and named ranges the routines need but the spreadsheet lacks, in a
+    /// single update. Existing ones are never moved or resized.
+    async fn bootstrap(&self) -> error_stack::Result<BootstrapReport, SchemaBootstrapperError>;
+}
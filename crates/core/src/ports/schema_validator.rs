@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SchemaValidatorError {
+    #[error("Failed to fetch the spreadsheet metadata")]
+    FetchMetadataError,
+}
+
+/// Something a routine would trip on when writing to `range`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaProblem {
+    pub range: String,
+    pub problem: String,
+}
+
+impl std::fmt::Display for SchemaProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.range, self.problem)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaReport {
+    /// Number of ranges and sheets checked
+    pub checked: usize,
+    pub problems: Vec<SchemaProblem>,
+}
+
+impl SchemaReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+#[async_trait::async_trait]
+pub trait SchemaValidator: Send + Sync {
+    /// Checks every expected range at once, without writing anything. Problems with the ranges
+    /// are part of the report; only failing to fetch the metadata is an error.
+    async fn validate(&self) -> error_stack::Result<SchemaReport, SchemaValidatorError>;
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SnapshotRepositoryError {
+    #[error("Failed to save balance snapshots")]
+    SaveSnapshotsError,
+    #[error("Failed to fetch balance snapshots")]
+    FetchSnapshotsError,
+}
+
+/// Balance of one asset, held by one account on one source, at the time a routine ran
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceSnapshot {
+    /// Where the balance was fetched from: an exchange, a chain, ...
+    pub source: String,
+    /// Which account or wallet group of the source holds it
+    pub account: String,
+    pub asset: String,
+    pub amount: f64,
+    /// USD price of the asset, when the routine knows it
+    pub price: Option<f64>,
+    pub usd_value: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl BalanceSnapshot {
+    pub fn new(
+        source: impl Into<String>,
+        account: impl Into<String>,
+        asset: impl Into<String>,
+        amount: f64,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            source: source.into(),
+            account: account.into(),
+            asset: asset.into(),
+            amount,
+            price: None,
+            usd_value: None,
+            timestamp,
+        }
+    }
+
+    /// Sets the price, and the USD value derived from it
+    pub fn with_price(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self.usd_value = Some(self.amount * price);
+        self
+    }
+}
+
+/// Filters for [`SnapshotRepository::get_snapshots`]; `None` matches everything
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotQuery {
+    pub source: Option<String>,
+    pub account: Option<String>,
+    pub asset: Option<String>,
+    /// Inclusive
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[async_trait::async_trait]
+pub trait SnapshotRepository: std::fmt::Debug + Send + Sync {
+    /// Appends the snapshots of a run. Existing snapshots are never overwritten.
+    async fn save_snapshots(
+        &self,
+        snapshots: &[BalanceSnapshot],
+    ) -> error_stack::Result<(), SnapshotRepositoryError>;
+
+    /// Snapshots matching `query`, oldest first
+    async fn get_snapshots(
+        &self,
+        query: &SnapshotQuery,
+    ) -> error_stack::Result<Vec<BalanceSnapshot>, SnapshotRepositoryError>;
+}
@@ -0,0 +1,9 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+#[async_trait::async_trait]
+pub trait TokenPriceSource: Send + Sync + Debug {
+    /// USD price of each token id, `None` when the source knows the token but has no price for
+    /// it. Tokens the source does not know are left out.
+    async fn token_prices(&self, token_ids: &[String]) -> HashMap<String, Option<f64>>;
+}
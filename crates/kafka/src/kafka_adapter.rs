@@ -1,3 +1,4 @@
+use crypto_balance_core::adapters::sheets::backend::dry_run::DryRunRecorder;
 use crypto_balance_core::ports::application_service::ApplicationService;
 use crypto_balance_core::ports::event_handler::{CryptoEvent, EventError, EventHandler};
 use rdkafka::config::ClientConfig;
@@ -12,6 +13,7 @@ pub struct KafkaEventAdapter {
     consumer: StreamConsumer<DefaultConsumerContext>,
     application_service: Arc<dyn ApplicationService>,
     topics: Vec<String>,
+    dry_run: Option<(Arc<DryRunRecorder>, Option<Box<str>>)>,
 }
 
 impl KafkaEventAdapter {
@@ -35,9 +37,28 @@ impl KafkaEventAdapter {
             consumer,
             application_service,
             topics,
+            dry_run: None,
         })
     }
 
+    /// Exports the writes recorded by `recorder` after every processed message, either to
+    /// `output` or to stdout.
+    pub fn with_dry_run(mut self, recorder: Arc<DryRunRecorder>, output: Option<Box<str>>) -> Self {
+        self.dry_run = Some((recorder, output));
+        self
+    }
+
+    fn export_dry_run(&self) {
+        let Some((recorder, output)) = &self.dry_run else {
+            return;
+        };
+
+        match recorder.export(output.as_deref()) {
+            Ok(count) => info!("Dry run: {} writes recorded", count),
+            Err(e) => error!("Failed to export dry run writes: {:?}", e),
+        }
+    }
+
     #[instrument(skip(self))]
     pub async fn start_consuming(&self) -> Result<(), Box<dyn std::error::Error>> {
         info!("Starting Kafka consumer for topics: {:?}", self.topics);
@@ -64,7 +85,10 @@ impl KafkaEventAdapter {
                         message.offset()
                     );
 
-                    match self.process_message(payload).await {
+                    let result = self.process_message(payload).await;
+                    self.export_dry_run();
+
+                    match result {
                         Ok(_) => {
                             if let Err(e) =
                                 self.consumer.commit_message(&message, CommitMode::Async)
@@ -156,6 +180,16 @@ impl EventHandler for KafkaEventAdapter {
                         })
                     })?;
             }
+            CryptoEvent::RunHoldUpdate { .. } => {
+                self.application_service
+                    .run_routine_by_name("UpdateHoldBalanceOnSheetsRoutine")
+                    .await
+                    .map_err(|e| {
+                        error_stack::report!(EventError::ProcessingFailed {
+                            details: format!("Failed to run hold update: {:?}", e),
+                        })
+                    })?;
+            }
             CryptoEvent::HealthCheck { .. } => {
                 let health = self.application_service.health_check().await.map_err(|e| {
                     error_stack::report!(EventError::ProcessingFailed {
@@ -1,8 +1,11 @@
+use crypto_balance_core::adapters::config::app_config::CONFIG;
+use crypto_balance_core::adapters::sheets::backend::dry_run::DryRunRecorder;
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::trace as sdktrace;
 use opentelemetry_sdk::Resource;
 use std::env;
+use std::sync::Arc;
 use tokio::signal;
 use tracing::{error, info, instrument};
 use tracing_opentelemetry::OpenTelemetryLayer;
@@ -36,8 +39,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         brokers, group_id, topics
     );
 
-    let app_service = ApplicationServiceFactory::create().await?;
-    let kafka_adapter = KafkaEventAdapter::new(&brokers, &group_id, topics, app_service)?;
+    let mut dry_run = CONFIG.sheets.dry_run.clone();
+    if let Ok(value) = env::var("DRY_RUN") {
+        dry_run.enabled = matches!(value.trim(), "1" | "true");
+    }
+    if let Ok(output) = env::var("DRY_RUN_OUTPUT") {
+        dry_run.output = Some(output.into());
+    }
+
+    let recorder = dry_run.enabled.then(|| Arc::new(DryRunRecorder::default()));
+    if recorder.is_some() {
+        info!("Dry run: spreadsheet writes will be recorded, not sent");
+    }
+
+    let app_service = ApplicationServiceFactory::create(recorder.clone()).await?;
+    let mut kafka_adapter = KafkaEventAdapter::new(&brokers, &group_id, topics, app_service)?;
+    if let Some(recorder) = recorder {
+        kafka_adapter = kafka_adapter.with_dry_run(recorder, dry_run.output);
+    }
 
     // Setup graceful shutdown
     let shutdown_signal = async {